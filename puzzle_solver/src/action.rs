@@ -5,4 +5,11 @@ pub trait Action: Clone + FromStr + ToString + PartialEq {
 
     fn get_cost(&self) -> f64;
     
-}
\ No newline at end of file
+}
+
+/// An action that another action undoes, which lets a search work backwards from the goal
+pub trait InvertibleAction: Action {
+
+    fn inverse(&self) -> Self;
+
+}
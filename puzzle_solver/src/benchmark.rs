@@ -0,0 +1,168 @@
+// Author: Harper Davis
+use std::{collections::BTreeMap, time::Instant};
+
+use colored::Colorize;
+
+use crate::{
+    state::State,
+    action::Action,
+    limits::SearchLimits,
+    search::SearchOutcome,
+    registry::Solver,
+    test::TestCase,
+    memory,
+    json::json_string,
+};
+
+/// How one search did on one test case
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    solver: String,
+    depth_limit: u32,
+    start_state: String,
+    /// solved, invalid, exhausted or the limit that stopped it
+    outcome: String,
+    time: f64,
+    nodes_generated: u64,
+    nodes_expanded: u64,
+    max_frontier_size: usize,
+    /// The most memory the search had allocated at once, on top of what was allocated before it started
+    peak_memory: usize,
+    /// None unless it found a valid solution
+    cost: Option<f64>,
+    correct_cost: f64,
+}
+
+impl BenchmarkResult {
+
+    pub fn get_solver(&self) -> &str {
+        &self.solver
+    }
+
+    pub fn get_depth_limit(&self) -> u32 {
+        self.depth_limit
+    }
+
+    /// How the cost compares to the correct path's, like test::test prints it
+    pub fn quality(&self) -> Option<f64> {
+        self.cost.map(|cost| if self.correct_cost == 0.0 { if cost == 0.0 { 1.0 } else { f64::INFINITY } } else { cost / self.correct_cost })
+    }
+
+}
+
+/// Runs every solver on every test case, one after the other. The limit of each case is what the searches get
+/// unless one is given, and `limits` is called again for every search so time limits start with it
+pub fn run_benchmark<S: State<Action = A>, A: Action>(solvers: &[&Solver<S, A>], test_cases: &[TestCase<S, A>], limit: Option<f64>, limits: impl Fn() -> SearchLimits) -> Vec<BenchmarkResult> {
+    let mut results = Vec::new();
+    for solver in solvers {
+        for (i, test_case) in test_cases.iter().enumerate() {
+            eprint!("\r{} {}/{}", solver, i + 1, test_cases.len());
+
+            let limits = limits();
+            let start_state = test_case.get_start_state().clone();
+            let allocated = memory::get_allocated();
+            memory::reset_peak();
+            let now = Instant::now();
+            let outcome = solver.solve(start_state, limit.unwrap_or(test_case.get_limit()), &limits);
+            let time = now.elapsed().as_secs_f64() * 1000.0;
+            let peak_memory = memory::get_peak().saturating_sub(allocated);
+
+            let statistics = outcome.get_statistics();
+            let (outcome_name, cost) = match &outcome {
+                SearchOutcome::Solved(solution) => match solution.verify() {
+                    Ok(_) => (String::from("solved"), Some(solution.path_cost())),
+                    Err(_) => (String::from("invalid"), None),
+                },
+                SearchOutcome::Exhausted(_) => (String::from("exhausted"), None),
+                SearchOutcome::LimitReached(limit, _) => (limit.to_string(), None),
+            };
+            results.push(BenchmarkResult {
+                solver: solver.to_string(),
+                depth_limit: test_case.get_depth_limit(),
+                start_state: test_case.get_start_state().to_string(),
+                outcome: outcome_name,
+                time,
+                nodes_generated: statistics.get_nodes_generated(),
+                nodes_expanded: statistics.get_nodes_expanded(),
+                max_frontier_size: statistics.get_max_frontier_size(),
+                peak_memory,
+                cost,
+                correct_cost: test_case.get_correct_cost(),
+            });
+        }
+        eprintln!();
+    }
+    results
+}
+
+///////////////////////////////////////////////////////
+
+pub fn to_csv(results: &[BenchmarkResult]) -> String {
+    let mut csv = String::from("solver,depth,start,outcome,time_ms,generated,expanded,max_frontier,peak_memory_bytes,cost,correct_cost\n");
+    for result in results {
+        csv.push_str(&format!("{},{},{},{},{:.3},{},{},{},{},{},{}\n",
+            csv_field(&result.solver), result.depth_limit, csv_field(&result.start_state), csv_field(&result.outcome), result.time,
+            result.nodes_generated, result.nodes_expanded, result.max_frontier_size, result.peak_memory,
+            result.cost.map_or(String::new(), |cost| cost.to_string()), result.correct_cost));
+    }
+    csv
+}
+
+/// Quoted if it has to be, the cube's states have commas in them
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn to_json(results: &[BenchmarkResult]) -> String {
+    let results = results.iter().map(|result| {
+        format!("{{\"solver\":{},\"depth\":{},\"start\":{},\"outcome\":{},\"time_ms\":{:.3},\"generated\":{},\"expanded\":{},\"max_frontier\":{},\"peak_memory_bytes\":{},\"cost\":{},\"correct_cost\":{}}}",
+            json_string(&result.solver), result.depth_limit, json_string(&result.start_state), json_string(&result.outcome), result.time,
+            result.nodes_generated, result.nodes_expanded, result.max_frontier_size, result.peak_memory,
+            result.cost.map_or(String::from("null"), |cost| cost.to_string()), result.correct_cost)
+    }).collect::<Vec<String>>();
+    format!("[{}]", results.join(","))
+}
+
+///////////////////////////////////////////////////////
+
+/// A table for each solver with the averages for each depth, the same buckets test::test averages over.
+/// Time, nodes and memory are averaged over every case and the quality only over the solved ones
+pub fn print_summary(results: &[BenchmarkResult]) {
+    let mut solvers: Vec<&str> = Vec::new();
+    for result in results {
+        if !solvers.contains(&result.get_solver()) {
+            solvers.push(result.get_solver());
+        }
+    }
+
+    for solver in solvers {
+        println!("{}", solver.blue());
+        println!("{:>7} {:>7} {:>12} {:>14} {:>14} {:>12} {:>9}", "depth", "solved", "time (ms)", "generated", "expanded", "memory (KiB)", "quality");
+        let mut depths: BTreeMap<u32, Vec<&BenchmarkResult>> = BTreeMap::new();
+        for result in results.iter().filter(|result| result.get_solver() == solver) {
+            depths.entry(result.get_depth_limit()).or_default().push(result);
+        }
+        for (depth, results) in &depths {
+            print_summary_row(&depth.to_string(), results);
+        }
+        print_summary_row("all", &depths.values().flatten().copied().collect::<Vec<&BenchmarkResult>>());
+        println!();
+    }
+}
+
+fn print_summary_row(depth: &str, results: &[&BenchmarkResult]) {
+    let count = results.len() as f64;
+    let average = |value: fn(&BenchmarkResult) -> f64| results.iter().map(|result| value(result)).sum::<f64>() / count;
+    let qualities = results.iter().filter_map(|result| result.quality()).collect::<Vec<f64>>();
+    let quality = if qualities.is_empty() { String::from("-") } else { format!("{:.3}", qualities.iter().sum::<f64>() / qualities.len() as f64) };
+    let solved = format!("{}/{}", qualities.len(), results.len());
+    let solved = if qualities.len() == results.len() { solved.green() } else { solved.red() };
+
+    println!("{:>7} {:>7} {:>12.3} {:>14.0} {:>14.0} {:>12.1} {:>9}", depth, solved, average(|result| result.time),
+        average(|result| result.nodes_generated as f64), average(|result| result.nodes_expanded as f64),
+        average(|result| result.peak_memory as f64 / 1024.0), quality);
+}
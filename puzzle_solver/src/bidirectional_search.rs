@@ -0,0 +1,275 @@
+// Author: Harper Davis
+
+use std::{collections::HashMap, fmt, marker::PhantomData, sync::Arc};
+
+use crate::{state::State, action::InvertibleAction, frontier::{Frontier, QueueFrontier, PriorityQueueFrontier}, node::Node, statistics::SearchStatistics, limits::SearchLimits, search::{Search, SearchOutcome, Solution}, heuristic::PairwiseHeuristic};
+
+/// One half of a bidirectional search. `reached` has the cheapest node found so far for every state
+/// this side has generated, and `open` the path costs of the ones that haven't been expanded yet
+struct Direction<S: State<Action = A>, A: InvertibleAction, F: Frontier<S, A>> {
+    frontier: F,
+    reached: HashMap<S, Node<S, A>>,
+    open: HashMap<S, f64>,
+}
+
+impl<S: State<Action = A>, A: InvertibleAction, F: Frontier<S, A>> Direction<S, A, F> {
+
+    fn new() -> Direction<S, A, F> {
+        Direction { frontier: F::new(), reached: HashMap::new(), open: HashMap::new() }
+    }
+
+    fn insert(&mut self, node: Node<S, A>) {
+        self.reached.insert(node.get_state().clone(), node.clone());
+        self.open.insert(node.get_state().clone(), node.get_path_cost());
+        self.frontier.insert(node);
+    }
+
+}
+
+/// Where the two halves touched: a forward node from the start and a backward node from a goal with the same state
+struct Meeting<S: State<Action = A>, A: InvertibleAction> {
+    cost: f64,
+    forward: Node<S, A>,
+    backward: Node<S, A>,
+}
+
+/// Searches forward from the start and backwards from every goal state at the same time, always expanding
+/// whichever side has the smaller frontier, and keeps the cheapest path found where they meet. Since only
+/// invertible actions are allowed, walking backwards is the same as doing actions from the goal.
+/// `estimate` gives the heuristic of a state on one side given the open states of the other side, and
+/// `finished` decides from the cheapest meeting and the next node of each side that nothing cheaper is left
+fn meet_in_the_middle<S: State<Action = A>, A: InvertibleAction, F: Frontier<S, A>>(
+    start_state: S,
+    limits: &SearchLimits,
+    statistics: &mut SearchStatistics,
+    estimate: impl Fn(&S, &HashMap<S, f64>) -> f64,
+    finished: impl Fn(f64, &Node<S, A>, &Node<S, A>) -> bool,
+) -> SearchOutcome<S, A> {
+    let goal_states = start_state.goal_states();
+    if !start_state.is_solvable() || goal_states.is_empty() {
+        return SearchOutcome::Exhausted(statistics.clone());
+    }
+    statistics.iteration();
+
+    let mut forward = Direction::<S, A, F>::new();
+    let mut backward = Direction::<S, A, F>::new();
+    let goal_costs = goal_states.iter().map(|goal_state| (goal_state.clone(), 0.0)).collect::<HashMap<S, f64>>();
+    forward.insert(Node::new(&start_state, 0.0, 0, &|state: &S| estimate(state, &goal_costs)));
+    for goal_state in &goal_states {
+        backward.insert(Node::new(goal_state, 0.0, 0, &|state: &S| estimate(state, &forward.open)));
+    }
+    statistics.frontier_size(forward.frontier.size() + backward.frontier.size());
+
+    let mut best: Option<Meeting<S, A>> = forward.reached.get(&start_state).and_then(|start| {
+        backward.reached.get(&start_state).map(|goal| Meeting { cost: 0.0, forward: start.clone(), backward: goal.clone() })
+    });
+
+    while let (Some(next_forward), Some(next_backward)) = (forward.frontier.peek(), backward.frontier.peek()) {
+        if best.as_ref().is_some_and(|meeting| finished(meeting.cost, next_forward, next_backward)) {
+            break;
+        }
+        if let Some(limit) = limits.check(statistics, forward.frontier.size() + backward.frontier.size()) {
+            return SearchOutcome::LimitReached(limit, statistics.clone());
+        }
+
+        let is_forward = forward.frontier.size() <= backward.frontier.size();
+        let (this, other) = if is_forward { (&mut forward, &backward) } else { (&mut backward, &forward) };
+
+        let node = this.frontier.pop().unwrap();
+        // a cheaper path to this state was found after this node was queued, so it is stale
+        if this.reached.get(node.get_state()).is_some_and(|known| known.get_path_cost() < node.get_path_cost()) {
+            continue;
+        }
+        this.open.remove(node.get_state());
+
+        statistics.expanded();
+        let shared_node = Arc::new(node);
+        for action in shared_node.get_possible_actions() {
+            let next_node = Node::<S, A>::next_node(shared_node.clone(), &action, &|state: &S| estimate(state, &other.open));
+            statistics.generated();
+
+            if this.reached.get(next_node.get_state()).is_some_and(|known| known.get_path_cost() <= next_node.get_path_cost()) {
+                statistics.pruned();
+                continue;
+            }
+
+            if let Some(other_node) = other.reached.get(next_node.get_state()) {
+                let cost = next_node.get_path_cost() + other_node.get_path_cost();
+                if best.as_ref().is_none_or(|meeting| cost < meeting.cost) {
+                    let (forward_node, backward_node) = if is_forward { (next_node.clone(), other_node.clone()) } else { (other_node.clone(), next_node.clone()) };
+                    best = Some(Meeting { cost, forward: forward_node, backward: backward_node });
+                }
+            }
+
+            this.insert(next_node);
+        }
+        statistics.frontier_size(forward.frontier.size() + backward.frontier.size());
+    }
+
+    match best {
+        Some(meeting) => SearchOutcome::Solved(joined_solution(start_state, &meeting, statistics.clone())),
+        None => SearchOutcome::Exhausted(statistics.clone()),
+    }
+}
+
+/// The forward path up to the meeting point, followed by the backward path undone in reverse order
+fn joined_solution<S: State<Action = A>, A: InvertibleAction>(start_state: S, meeting: &Meeting<S, A>, statistics: SearchStatistics) -> Solution<S, A> {
+    let forward = Solution::from_node(start_state.clone(), &meeting.forward, SearchStatistics::new());
+    let mut path = forward.get_path().clone();
+
+    let mut current_node = &meeting.backward;
+    while let Some(parent_node) = current_node.get_parent_node() {
+        path.push(current_node.get_action().unwrap().inverse());
+        current_node = parent_node;
+    }
+
+    Solution::new(start_state, current_node.get_state().clone(), path, statistics)
+}
+
+///////////////////////////////////////////////////////
+
+/// Uninformed bidirectional search. It stops once the two next nodes' path costs add up to at least the
+/// cheapest meeting, which is only right when both sides come off the frontier cheapest first, so use it with
+/// a QueueFrontier for unit cost actions or a PriorityQueueFrontier otherwise (not a StackFrontier)
+pub struct BidirectionalSearch<S: State<Action = A>, A: InvertibleAction, F: Frontier<S, A>> {
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+    phantom_f: PhantomData<F>,
+}
+
+impl<S: State<Action = A>, A: InvertibleAction, F: Frontier<S, A>> fmt::Display for BidirectionalSearch<S, A, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BidirectionalSearch")
+    }
+}
+
+impl<S: State<Action = A>, A: InvertibleAction, F: Frontier<S, A>> Search<S, A, F> for BidirectionalSearch<S, A, F> {
+
+    fn new(_limit: f64) -> BidirectionalSearch<S, A, F> {
+        BidirectionalSearch { statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData, phantom_f: PhantomData }
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        meet_in_the_middle::<S, A, F>(start_state, limits, &mut self.statistics,
+            |_, _| 0.0,
+            |cost, next_forward, next_backward| cost <= next_forward.get_path_cost() + next_backward.get_path_cost())
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        false
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+pub type BidirectionalBreadthFirstSearch<S, A> = BidirectionalSearch<S, A, QueueFrontier<S, A>>;
+pub type BidirectionalUniformCostSearch<S, A> = BidirectionalSearch<S, A, PriorityQueueFrontier<S, A>>;
+
+///////////////////////////////////////////////////////
+
+/// Bidirectional A* where each side aims at the other side's frontier instead of at the far end. A node's
+/// estimate is the cheapest path cost plus heuristic over every open node of the other side, so it gets more
+/// accurate the closer the two frontiers get, at the cost of comparing against all of them for every node.
+/// It stops once the cheapest meeting is no more than the evaluation of the next node of either side
+pub struct FrontToFrontSearch<S: State<Action = A>, A: InvertibleAction, H: PairwiseHeuristic<S>> {
+    heuristic: H,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+}
+
+impl<S: State<Action = A>, A: InvertibleAction, H: PairwiseHeuristic<S>> FrontToFrontSearch<S, A, H> {
+
+    pub fn with_heuristic(heuristic: H) -> FrontToFrontSearch<S, A, H> {
+        FrontToFrontSearch { heuristic, statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData }
+    }
+}
+
+impl<S: State<Action = A>, A: InvertibleAction, H: PairwiseHeuristic<S>> fmt::Display for FrontToFrontSearch<S, A, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FrontToFrontSearch")
+    }
+}
+
+impl<S: State<Action = A>, A: InvertibleAction, H: PairwiseHeuristic<S> + Default> Search<S, A, PriorityQueueFrontier<S, A>> for FrontToFrontSearch<S, A, H> {
+
+    fn new(_limit: f64) -> FrontToFrontSearch<S, A, H> {
+        FrontToFrontSearch::with_heuristic(H::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        let heuristic = &self.heuristic;
+        meet_in_the_middle::<S, A, PriorityQueueFrontier<S, A>>(start_state, limits, &mut self.statistics,
+            |state, other_open| other_open.iter()
+                .map(|(other_state, other_cost)| other_cost + heuristic.estimate_between(state, other_state))
+                .fold(f64::INFINITY, f64::min),
+            |cost, next_forward, next_backward| cost <= f64::max(next_forward.eval(), next_backward.eval()))
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        false
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+
+    use crate::{generator::Generator, heuristic::ZeroHeuristic, limits::Limit, search::AStarGraphSearch, puzzles::{sliding_puzzle::{EightPuzzle, SlidingPuzzleAction}, fifteen_puzzle_heuristics::ManhattanDistance}};
+
+    use super::*;
+
+    fn optimal_cost<S: State<Action = A>, A: InvertibleAction>(start_state: S) -> f64 {
+        match AStarGraphSearch::<S, A>::new(f64::INFINITY).search(start_state) {
+            SearchOutcome::Solved(solution) => solution.path_cost(),
+            _ => panic!("A* didn't find a solution"),
+        }
+    }
+
+    /// Solves every case with the search and checks it found a real path that costs as little as A*'s
+    fn assert_optimal<S: State<Action = A>, A: InvertibleAction, F: Frontier<S, A>, E: Search<S, A, F>>(start_states: &[S]) {
+        for start_state in start_states {
+            match E::new(f64::INFINITY).search(start_state.clone()) {
+                SearchOutcome::Solved(solution) => {
+                    assert_eq!(solution.verify(), Ok(optimal_cost(start_state.clone())), "from {}", start_state.to_string());
+                },
+                _ => panic!("no solution from {}", start_state.to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn as_cheap_as_a_star_on_eight_puzzles() {
+        let start_states = Generator::<StdRng>::new(10).sliding_puzzle_walks::<3, 3>(0..=24, 4).into_iter()
+            .map(|test_case| test_case.get_start_state().clone()).collect::<Vec<EightPuzzle>>();
+        assert_optimal::<_, _, QueueFrontier<_, _>, BidirectionalSearch<EightPuzzle, SlidingPuzzleAction, QueueFrontier<_, _>>>(&start_states);
+        assert_optimal::<_, _, PriorityQueueFrontier<_, _>, BidirectionalSearch<EightPuzzle, SlidingPuzzleAction, PriorityQueueFrontier<_, _>>>(&start_states);
+        assert_optimal::<_, _, PriorityQueueFrontier<_, _>, FrontToFrontSearch<EightPuzzle, SlidingPuzzleAction, ZeroHeuristic>>(&start_states);
+    }
+
+    #[test]
+    fn front_to_front_as_cheap_as_a_star_on_fifteen_puzzles() {
+        let start_states = Generator::<StdRng>::new(10).fifteen_puzzle_walks(0..=16, 2).into_iter()
+            .map(|test_case| test_case.get_start_state().clone()).collect::<Vec<_>>();
+        assert_optimal::<_, _, PriorityQueueFrontier<_, _>, FrontToFrontSearch<_, _, ManhattanDistance>>(&start_states);
+    }
+
+    #[test]
+    fn stops_at_the_node_limit() {
+        let test_case = Generator::<StdRng>::new(10).sliding_puzzle_walks::<3, 3>(30..=30, 1).pop().unwrap();
+        let limits = SearchLimits::none().with_max_nodes(50);
+        let outcome = BidirectionalSearch::<EightPuzzle, SlidingPuzzleAction, QueueFrontier<_, _>>::new(f64::INFINITY).search_with_limits(test_case.get_start_state().clone(), &limits);
+        assert!(matches!(outcome, SearchOutcome::LimitReached(Limit::Nodes, _)));
+    }
+}
@@ -0,0 +1,670 @@
+// Author: Harper Davis
+use std::{fs, io::{self, BufRead}, ops::RangeInclusive, str::FromStr, time::{Duration, Instant}};
+
+use rand::rngs::StdRng;
+
+use crate::{
+    state::State,
+    action::Action,
+    limits::SearchLimits,
+    search::SearchOutcome,
+    json::{json_string, json_statistics},
+    benchmark,
+    generator::Generator,
+    registry::Registry,
+    suite::{self, SuiteError},
+    test::TestCase,
+    heuristic::{StateHeuristic, ZeroHeuristic},
+    frontier::StackFrontier,
+    puzzles::{
+        silly_puzzle::{SillyPuzzle, SillyPuzzleAction},
+        fifteen_puzzle::{FifteenPuzzle, FifteenPuzzleAction},
+        fifteen_puzzle_fast::FifteenPuzzleFast,
+        fifteen_puzzle_heuristics::{MisplacedTiles, ManhattanDistance, LinearConflict, WalkingDistance},
+        pattern_database::PatternDatabaseHeuristic,
+        sliding_puzzle::{EightPuzzle, TwentyFourPuzzle, SlidingPuzzleAction},
+        rubiks_cube::{CubeState, CubeAction},
+        facelets,
+        rubiks_cube_heuristics::CubieManhattanDistance,
+        n_cube::{NCube, NCubeAction},
+        pocket_cube::PocketCubeDistance,
+        reduction::ReductionSearch,
+    },
+};
+
+pub const USAGE: &str = "\
+Usage:
+    puzzle_solver solve [options] [state...]
+    puzzle_solver benchmark [options] --file <suite>
+    puzzle_solver generate [options]
+    puzzle_solver list [--puzzle <name>]
+    puzzle_solver experiment [name]
+    puzzle_solver help
+
+Start states are read from the arguments, or from a test suite given with --file, or otherwise
+one per line from stdin, where a line can also be a line of a suite. Suites have one case per line,
+<limit> <start state> <path...>, and the fifteen puzzles can also read tests/15_puzzle.txt.
+A cube can also be given as its 54 sticker facelet string, faces U, R, F, D, L and B each read
+across the rows, like UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB, and cube
+solutions are written as the turns to make on a real cube.
+
+Options for solve:
+    -p, --puzzle <name>          silly, fifteen, fifteen-fast, eight, twenty-four, cube, cube-2, cube-4 or cube-5
+                                 (default fifteen)
+    -a, --algorithm <name>       see `list` for every algorithm, frontier and heuristic (default idastar).
+                                 cube-4 and cube-5 also have reduction, which is quick but far from optimal
+    -f, --frontier <name>        frontier for algorithms that take more than one
+    -H, --heuristic <name>       heuristic for informed algorithms (default state, the puzzle's own, or
+                                 pocket-cube-distance for cube-2)
+        --limit <n>              the depth limit for depth-limited, which every other algorithm ignores
+                                 (default from the suite or the puzzle)
+        --max-nodes <n>          stop after generating this many nodes
+        --max-frontier-size <n>  stop once the frontier holds more than this many nodes
+        --max-memory <n>         how many nodes smastar keeps at once (default 1000000)
+        --time-limit <seconds>   stop each search after this long
+        --replay <seconds>       show every state along each solution, this long apart (0 prints them all at once)
+        --file <path>            read the start states from a file
+        --format <text|json>     how to write the solutions (default text)
+
+Benchmark runs every search that matches the options on every case of the suite and prints a
+table of averages for each depth. -a, -f and -H take lists separated by commas, and leaving out
+-f or -H runs every frontier or heuristic the algorithms can use. It takes the same limits as solve,
+as well as:
+        --csv <path>             write the result of every case to a CSV file
+        --json <path>            write the result of every case to a JSON file
+
+Generate makes a suite that solve and benchmark can read, the same one every time for a seed:
+    -p, --puzzle <name>          any of the puzzles solve takes (default fifteen)
+        --seed <n>               the seed for the random moves (default a random one, written in the suite)
+        --moves <n|min-max>      how long the scrambles are (default depends on the puzzle)
+        --count <n>              how many cases for each scramble length, or in all without one (default 10)
+        --random                 fifteen puzzles picked evenly from every solvable board, rather than scrambled
+    -o, --output <path>          where to write the suite (default stdout)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    Arguments(Vec<String>),
+    File(String),
+    Stdin,
+}
+
+/// Everything solve needs to know, filled in from the command line
+#[derive(Debug, Clone)]
+pub struct Options {
+    puzzle: String,
+    algorithm: String,
+    frontier: Option<String>,
+    heuristic: Option<String>,
+    limit: Option<f64>,
+    max_nodes: Option<u64>,
+    max_frontier_size: Option<usize>,
+    max_memory: Option<usize>,
+    time_limit: Option<Duration>,
+    /// How long to wait between the states when replaying each solution, None to not replay them
+    replay: Option<Duration>,
+    /// Where benchmark writes its results
+    csv: Option<String>,
+    json: Option<String>,
+    input: Input,
+    format: OutputFormat,
+}
+
+impl Default for Options {
+
+    fn default() -> Self {
+        Options {
+            puzzle: String::from("fifteen"),
+            algorithm: String::from("idastar"),
+            frontier: None,
+            heuristic: None,
+            limit: None,
+            max_nodes: None,
+            max_frontier_size: None,
+            max_memory: None,
+            time_limit: None,
+            replay: None,
+            csv: None,
+            json: None,
+            input: Input::Stdin,
+            format: OutputFormat::Text,
+        }
+    }
+
+}
+
+impl Options {
+
+    /// Fresh limits for one search, since the time limit counts from when each one starts
+    fn limits(&self) -> SearchLimits {
+        let mut limits = SearchLimits::none();
+        if let Some(max_nodes) = self.max_nodes {
+            limits = limits.with_max_nodes(max_nodes);
+        }
+        if let Some(max_frontier_size) = self.max_frontier_size {
+            limits = limits.with_max_frontier_size(max_frontier_size);
+        }
+        if let Some(max_memory) = self.max_memory {
+            limits = limits.with_max_memory(max_memory);
+        }
+        if let Some(time_limit) = self.time_limit {
+            limits = limits.with_time_limit(time_limit);
+        }
+        limits
+    }
+
+}
+
+/// What generate makes, filled in from the command line
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    puzzle: String,
+    /// None picks one at random, which still ends up in the suite's header
+    seed: Option<u64>,
+    /// None uses the puzzle's default range
+    moves: Option<RangeInclusive<u32>>,
+    count: usize,
+    /// Evenly random boards rather than random walks from the goal
+    random: bool,
+    output: Option<String>,
+}
+
+impl Default for GenerateOptions {
+
+    fn default() -> Self {
+        GenerateOptions {
+            puzzle: String::from("fifteen"),
+            seed: None,
+            moves: None,
+            count: 10,
+            random: false,
+            output: None,
+        }
+    }
+
+}
+
+pub enum Command {
+    Solve(Options),
+    Benchmark(Options),
+    Generate(GenerateOptions),
+    List(Option<String>),
+    Experiment(Option<String>),
+    Help,
+}
+
+///////////////////////////////////////////////////////
+
+/// Reads the command, not counting the program name
+pub fn parse_arguments(args: &[String]) -> Result<Command, String> {
+    let Some((command, args)) = args.split_first() else {
+        return Ok(Command::Help);
+    };
+    match command.as_str() {
+        "solve" => parse_options(args, false).map(Command::Solve),
+        "benchmark" => parse_options(args, true).map(Command::Benchmark),
+        "generate" => parse_generate_options(args).map(Command::Generate),
+        "list" => match args {
+            [] => Ok(Command::List(None)),
+            [flag, puzzle] if flag == "-p" || flag == "--puzzle" => Ok(Command::List(Some(puzzle.clone()))),
+            _ => Err(String::from("list only takes --puzzle")),
+        },
+        "experiment" => match args {
+            [] => Ok(Command::Experiment(None)),
+            [name] => Ok(Command::Experiment(Some(name.clone()))),
+            _ => Err(String::from("experiment takes one name")),
+        },
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        _ => Err(format!("unknown command {}", command)),
+    }
+}
+
+/// The options of solve, or of benchmark which writes its results to files rather than replaying them
+fn parse_options(args: &[String], benchmark: bool) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut states = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') || arg == "-" {
+            states.push(arg.clone());
+            continue;
+        }
+        let mut value = || args.next().cloned().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "-p" | "--puzzle" => options.puzzle = value()?,
+            "-a" | "--algorithm" => options.algorithm = value()?,
+            "-f" | "--frontier" => options.frontier = Some(value()?),
+            "-H" | "--heuristic" => options.heuristic = Some(value()?),
+            "--limit" => options.limit = Some(parse_number(arg, &value()?)?),
+            "--max-nodes" => options.max_nodes = Some(parse_number(arg, &value()?)?),
+            "--max-frontier-size" => options.max_frontier_size = Some(parse_number(arg, &value()?)?),
+            "--max-memory" => options.max_memory = Some(parse_number(arg, &value()?)?),
+            "--time-limit" => options.time_limit = Some(Duration::try_from_secs_f64(parse_number(arg, &value()?)?).map_err(|_| format!("{} needs a positive number of seconds", arg))?),
+            "--csv" if benchmark => options.csv = Some(value()?),
+            "--json" if benchmark => options.json = Some(value()?),
+            "--replay" if !benchmark => options.replay = Some(Duration::try_from_secs_f64(parse_number(arg, &value()?)?).map_err(|_| format!("{} needs a positive number of seconds", arg))?),
+            "--file" => options.input = Input::File(value()?),
+            "--format" if !benchmark => options.format = match value()?.as_str() {
+                "text" => OutputFormat::Text,
+                "json" => OutputFormat::Json,
+                format => return Err(format!("unknown format {}, expected text or json", format)),
+            },
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    if !states.is_empty() {
+        if options.input != Input::Stdin {
+            return Err(String::from("start states can't be given both as arguments and with --file"));
+        }
+        options.input = Input::Arguments(states);
+    }
+    Ok(options)
+}
+
+fn parse_generate_options(args: &[String]) -> Result<GenerateOptions, String> {
+    let mut options = GenerateOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "-p" | "--puzzle" => options.puzzle = value()?,
+            "--seed" => options.seed = Some(parse_number(arg, &value()?)?),
+            "--moves" => {
+                let moves = value()?;
+                options.moves = Some(match moves.split_once('-') {
+                    Some((min, max)) => parse_number(arg, min)?..=parse_number(arg, max)?,
+                    None => parse_number(arg, &moves)?..=parse_number(arg, &moves)?,
+                });
+            }
+            "--count" => options.count = parse_number(arg, &value()?)?,
+            "--random" => options.random = true,
+            "-o" | "--output" => options.output = Some(value()?),
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_number<T: FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value.parse::<T>().map_err(|_| format!("{} needs a number, not {}", option, value))
+}
+
+///////////////////////////////////////////////////////
+
+pub const PUZZLES: [&str; 9] = ["silly", "fifteen", "fifteen-fast", "eight", "twenty-four", "cube", "cube-2", "cube-4", "cube-5"];
+
+fn silly_puzzle_registry() -> Registry<SillyPuzzle, SillyPuzzleAction> {
+    let mut registry = Registry::default();
+    registry.register_uninformed();
+    registry.register_informed::<StateHeuristic>("state");
+    registry.register_informed::<ZeroHeuristic>("zero");
+    registry.register_bidirectional::<ZeroHeuristic>("zero");
+    registry
+}
+
+fn fifteen_puzzle_registry() -> Registry<FifteenPuzzle, FifteenPuzzleAction> {
+    let mut registry = Registry::default();
+    registry.register_uninformed();
+    registry.register_informed::<StateHeuristic>("state");
+    registry.register_informed::<ZeroHeuristic>("zero");
+    registry.register_informed::<MisplacedTiles>("misplaced-tiles");
+    registry.register_informed::<ManhattanDistance>("manhattan");
+    registry.register_informed::<LinearConflict>("linear-conflict");
+    registry.register_informed::<WalkingDistance>("walking-distance");
+    registry.register_informed::<PatternDatabaseHeuristic>("pattern-database");
+    registry.register_bidirectional::<ManhattanDistance>("manhattan");
+    registry.register_bidirectional::<ZeroHeuristic>("zero");
+    registry
+}
+
+fn fifteen_puzzle_fast_registry() -> Registry<FifteenPuzzleFast, FifteenPuzzleAction> {
+    let mut registry = Registry::default();
+    registry.register_uninformed();
+    registry.register_informed::<StateHeuristic>("state");
+    registry.register_informed::<ZeroHeuristic>("zero");
+    registry.register_informed::<PatternDatabaseHeuristic>("pattern-database");
+    registry.register_bidirectional::<ZeroHeuristic>("zero");
+    registry
+}
+
+fn sliding_puzzle_registry<S: State<Action = SlidingPuzzleAction> + Send + Sync>() -> Registry<S, SlidingPuzzleAction> {
+    let mut registry = Registry::default();
+    registry.register_uninformed();
+    registry.register_informed::<StateHeuristic>("state");
+    registry.register_informed::<ZeroHeuristic>("zero");
+    registry.register_bidirectional::<ZeroHeuristic>("zero");
+    registry
+}
+
+fn cube_registry() -> Registry<CubeState, CubeAction> {
+    let mut registry = Registry::default();
+    registry.register_uninformed();
+    registry.register_informed::<StateHeuristic>("state");
+    registry.register_informed::<ZeroHeuristic>("zero");
+    registry.register_informed::<CubieManhattanDistance>("cubie-manhattan");
+    registry.register_bidirectional::<ZeroHeuristic>("zero");
+    registry
+}
+
+fn n_cube_registry<const N: usize>() -> Registry<NCube<N>, NCubeAction<N>> {
+    let mut registry = Registry::default();
+    registry.register_uninformed();
+    registry.register_informed::<StateHeuristic>("state");
+    registry.register_informed::<ZeroHeuristic>("zero");
+    registry.register_bidirectional::<ZeroHeuristic>("zero");
+    registry
+}
+
+/// The exact distance goes first so that it's the default, the others only just manage 7 moves
+fn pocket_cube_registry() -> Registry<NCube<2>, NCubeAction<2>> {
+    let mut registry = Registry::default();
+    registry.register_uninformed();
+    registry.register_informed::<PocketCubeDistance>("pocket-cube-distance");
+    registry.register_informed::<StateHeuristic>("state");
+    registry.register_informed::<ZeroHeuristic>("zero");
+    registry.register_bidirectional::<ZeroHeuristic>("zero");
+    registry
+}
+
+/// Nothing optimal gets far on a 4x4x4 or 5x5x5, so these can also be solved by reduction
+fn big_cube_registry<const N: usize>() -> Registry<NCube<N>, NCubeAction<N>> {
+    let mut registry = n_cube_registry::<N>();
+    registry.register::<StackFrontier<NCube<N>, NCubeAction<N>>, ReductionSearch<N>>("reduction", "stack", None);
+    registry
+}
+
+/// Prints every search that can be picked for the puzzle, or for all of them
+pub fn list(puzzle: Option<&str>) -> Result<(), String> {
+    fn print<S: State<Action = A>, A: Action>(puzzle: &str, registry: Registry<S, A>) {
+        println!("{}:", puzzle);
+        for solver in registry.get_solvers() {
+            println!("    {}", solver);
+        }
+    }
+
+    let puzzles = match puzzle {
+        Some(puzzle) => vec![puzzle],
+        None => PUZZLES.to_vec(),
+    };
+    for puzzle in puzzles {
+        match puzzle {
+            "silly" => print(puzzle, silly_puzzle_registry()),
+            "fifteen" => print(puzzle, fifteen_puzzle_registry()),
+            "fifteen-fast" => print(puzzle, fifteen_puzzle_fast_registry()),
+            "eight" => print(puzzle, sliding_puzzle_registry::<EightPuzzle>()),
+            "twenty-four" => print(puzzle, sliding_puzzle_registry::<TwentyFourPuzzle>()),
+            "cube" => print(puzzle, cube_registry()),
+            "cube-2" => print(puzzle, pocket_cube_registry()),
+            "cube-4" => print(puzzle, big_cube_registry::<4>()),
+            "cube-5" => print(puzzle, big_cube_registry::<5>()),
+            _ => return Err(unknown_puzzle(puzzle)),
+        }
+    }
+    Ok(())
+}
+
+/// Solves every start state with the search the options pick
+pub fn solve(options: &Options) -> Result<(), String> {
+    // the default limits are the longest optimal solutions (or close to it) for each puzzle
+    match options.puzzle.as_str() {
+        "silly" => solve_all(&silly_puzzle_registry(), options, 100.0, suite::read_test_cases),
+        "fifteen" => solve_all(&fifteen_puzzle_registry(), options, 80.0, read_fifteen_puzzle_file),
+        "fifteen-fast" => solve_all(&fifteen_puzzle_fast_registry(), options, 80.0, read_fifteen_puzzle_fast_file),
+        "eight" => solve_all(&sliding_puzzle_registry::<EightPuzzle>(), options, 31.0, suite::read_test_cases),
+        "twenty-four" => solve_all(&sliding_puzzle_registry::<TwentyFourPuzzle>(), options, 210.0, suite::read_test_cases),
+        "cube" => solve_all_with(&cube_registry(), options, 20.0, suite::read_test_cases, read_cube_state, |path| write_path(&facelets::real_turns(path))),
+        "cube-2" => solve_all(&pocket_cube_registry(), options, 11.0, suite::read_test_cases),
+        "cube-4" => solve_all(&big_cube_registry::<4>(), options, 20.0, suite::read_test_cases),
+        "cube-5" => solve_all(&big_cube_registry::<5>(), options, 20.0, suite::read_test_cases),
+        puzzle => Err(unknown_puzzle(puzzle)),
+    }
+}
+
+/// Runs the matrix of searches the options pick over the suite in --file
+pub fn benchmark(options: &Options) -> Result<(), String> {
+    match options.puzzle.as_str() {
+        "silly" => benchmark_all(&silly_puzzle_registry(), options, suite::read_test_cases),
+        "fifteen" => benchmark_all(&fifteen_puzzle_registry(), options, read_fifteen_puzzle_file),
+        "fifteen-fast" => benchmark_all(&fifteen_puzzle_fast_registry(), options, read_fifteen_puzzle_fast_file),
+        "eight" => benchmark_all(&sliding_puzzle_registry::<EightPuzzle>(), options, suite::read_test_cases),
+        "twenty-four" => benchmark_all(&sliding_puzzle_registry::<TwentyFourPuzzle>(), options, suite::read_test_cases),
+        "cube" => benchmark_all(&cube_registry(), options, suite::read_test_cases),
+        "cube-2" => benchmark_all(&pocket_cube_registry(), options, suite::read_test_cases),
+        "cube-4" => benchmark_all(&big_cube_registry::<4>(), options, suite::read_test_cases),
+        "cube-5" => benchmark_all(&big_cube_registry::<5>(), options, suite::read_test_cases),
+        puzzle => Err(unknown_puzzle(puzzle)),
+    }
+}
+
+/// Makes a suite from a seed, and writes it to --output or stdout
+pub fn generate(options: &GenerateOptions) -> Result<(), String> {
+    if options.moves.is_some() && (options.puzzle == "silly" || options.random) {
+        return Err(String::from("--moves only works for scrambles"));
+    }
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut generator = Generator::<StdRng>::new(seed);
+    let contents = match (options.puzzle.as_str(), options.random) {
+        ("silly", _) => suite::format_test_cases(&generate_header(options, seed, None), &generator.silly_puzzles(options.count)),
+        ("fifteen" | "fifteen-fast", true) => suite::format_test_cases(&generate_header(options, seed, None), &generator.random_fifteen_puzzles(options.count)),
+        ("fifteen" | "fifteen-fast", false) => {
+            let moves = options.moves.clone().unwrap_or(1..=40);
+            suite::format_test_cases(&generate_header(options, seed, Some(&moves)), &generator.fifteen_puzzle_walks(moves, options.count))
+        }
+        ("eight", false) => {
+            let moves = options.moves.clone().unwrap_or(1..=30);
+            suite::format_test_cases(&generate_header(options, seed, Some(&moves)), &generator.sliding_puzzle_walks::<3, 3>(moves, options.count))
+        }
+        ("twenty-four", false) => {
+            let moves = options.moves.clone().unwrap_or(1..=60);
+            suite::format_test_cases(&generate_header(options, seed, Some(&moves)), &generator.sliding_puzzle_walks::<5, 5>(moves, options.count))
+        }
+        ("eight" | "twenty-four" | "cube" | "cube-2" | "cube-4" | "cube-5", true) => return Err(format!("only fifteen puzzles can be picked at random, {} can only be scrambled", options.puzzle)),
+        ("cube", false) => {
+            let moves = options.moves.clone().unwrap_or(1..=10);
+            suite::format_test_cases(&generate_header(options, seed, Some(&moves)), &generator.cube_scrambles(moves, options.count))
+        }
+        ("cube-2", false) => {
+            let moves = options.moves.clone().unwrap_or(1..=11);
+            suite::format_test_cases(&generate_header(options, seed, Some(&moves)), &generator.n_cube_scrambles::<2>(moves, options.count))
+        }
+        ("cube-4", false) => {
+            let moves = options.moves.clone().unwrap_or(1..=5);
+            suite::format_test_cases(&generate_header(options, seed, Some(&moves)), &generator.n_cube_scrambles::<4>(moves, options.count))
+        }
+        ("cube-5", false) => {
+            let moves = options.moves.clone().unwrap_or(1..=5);
+            suite::format_test_cases(&generate_header(options, seed, Some(&moves)), &generator.n_cube_scrambles::<5>(moves, options.count))
+        }
+        (puzzle, _) => return Err(unknown_puzzle(puzzle)),
+    };
+
+    match &options.output {
+        Some(path) => fs::write(path, contents).map_err(|error| format!("couldn't write {}: {}", path, error)),
+        None => {
+            print!("{}", contents);
+            Ok(())
+        }
+    }
+}
+
+/// The command that makes the same suite again, for the top of the file
+fn generate_header(options: &GenerateOptions, seed: u64, moves: Option<&RangeInclusive<u32>>) -> String {
+    let mut command = format!("puzzle_solver generate -p {} --seed {} --count {}", options.puzzle, seed, options.count);
+    if let Some(moves) = moves {
+        command.push_str(&format!(" --moves {}-{}", moves.start(), moves.end()));
+    }
+    if options.random {
+        command.push_str(" --random");
+    }
+    format!("Generated with\n    {}\n<limit> <start> <path>", command)
+}
+
+fn unknown_puzzle(puzzle: &str) -> String {
+    format!("unknown puzzle {}, expected one of {}", puzzle, PUZZLES.join(", "))
+}
+
+/// How a puzzle reads a --file
+type ReadFile<S, A> = fn(&str) -> Result<Vec<TestCase<S, A>>, SuiteError>;
+/// How a puzzle reads a start state on its own, with anything more to say about why it isn't one
+type ReadState<S> = fn(&str) -> Result<S, Option<String>>;
+/// How a puzzle writes out the moves of a solution
+type WritePath<A> = fn(&[A]) -> String;
+
+/// Start states are what Display writes for every puzzle
+fn read_state<S: State>(state: &str) -> Result<S, Option<String>> {
+    state.parse::<S>().map_err(|_| None)
+}
+
+fn write_path<A: Action>(path: &[A]) -> String {
+    path.iter().map(|action| action.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+/// Cubes can also be given as a facelet string, which is how a real one gets typed in
+fn read_cube_state(state: &str) -> Result<CubeState, Option<String>> {
+    state.parse::<CubeState>().or_else(|_| {
+        // the faces Display writes are separated by commas, which a facelet string never has
+        if state.contains(',') {
+            return Err(None);
+        }
+        CubeState::from_facelets(state).map_err(|error| Some(error.to_string()))
+    })
+}
+
+/// Suites are what files usually are, but the fifteen puzzles can also read the layout of tests/15_puzzle.txt
+fn read_fifteen_puzzle_file(path: &str) -> Result<Vec<TestCase<FifteenPuzzle, FifteenPuzzleAction>>, SuiteError> {
+    suite::read_test_cases(path).or_else(|error| suite::read_fifteen_puzzle_test_cases(path).map_err(|_| error))
+}
+
+fn read_fifteen_puzzle_fast_file(path: &str) -> Result<Vec<TestCase<FifteenPuzzleFast, FifteenPuzzleAction>>, SuiteError> {
+    suite::read_test_cases(path).or_else(|error| {
+        let test_cases = suite::read_fifteen_puzzle_test_cases(path).map_err(|_| error)?;
+        Ok(test_cases.iter().map(|test_case| TestCase::new(FifteenPuzzleFast::from(test_case.get_start_state()), test_case.get_correct_path().clone(), test_case.get_limit())).collect())
+    })
+}
+
+fn solve_all<S: State<Action = A>, A: Action>(registry: &Registry<S, A>, options: &Options, default_limit: f64, read_file: ReadFile<S, A>) -> Result<(), String> {
+    solve_all_with(registry, options, default_limit, read_file, read_state::<S>, write_path::<A>)
+}
+
+/// solve_all for puzzles with more than one way of writing their start states or their moves
+fn solve_all_with<S: State<Action = A>, A: Action>(registry: &Registry<S, A>, options: &Options, default_limit: f64, read_file: ReadFile<S, A>, read_state: ReadState<S>, write_path: WritePath<A>) -> Result<(), String> {
+    let solver = registry.find(&options.algorithm, options.frontier.as_deref(), options.heuristic.as_deref())?;
+    let start_states = read_start_states::<S, A>(&options.input, &options.puzzle, read_file, read_state)?;
+
+    let mut results = Vec::new();
+    for (start_state, limit) in start_states {
+        // a limit given on the command line wins over the one in the file
+        let limit = options.limit.or(limit).unwrap_or(default_limit);
+        let now = Instant::now();
+        let outcome = solver.solve(start_state.clone(), limit, &options.limits());
+        let time = now.elapsed().as_secs_f64() * 1000.0;
+        match options.format {
+            OutputFormat::Text => {
+                print_text(&start_state, &outcome, time, write_path);
+                if let (Some(delay), SearchOutcome::Solved(solution)) = (options.replay, &outcome) {
+                    solution.replay(delay);
+                }
+            }
+            OutputFormat::Json => results.push(json_result(&start_state, &outcome, time, write_path)),
+        }
+    }
+
+    if options.format == OutputFormat::Json {
+        println!("{{\"puzzle\":{},\"solver\":{},\"results\":[{}]}}", json_string(&options.puzzle), json_string(&solver.to_string()), results.join(","));
+    }
+    Ok(())
+}
+
+/// The names in a list separated by commas, none if it wasn't given
+fn split_names(names: Option<&str>) -> Vec<&str> {
+    names.map_or(Vec::new(), |names| names.split(',').collect())
+}
+
+fn benchmark_all<S: State<Action = A>, A: Action>(registry: &Registry<S, A>, options: &Options, read_file: ReadFile<S, A>) -> Result<(), String> {
+    let Input::File(path) = &options.input else {
+        return Err(String::from("benchmark needs a suite, given with --file"));
+    };
+    let solvers = registry.find_all(&split_names(Some(&options.algorithm)), &split_names(options.frontier.as_deref()), &split_names(options.heuristic.as_deref()))?;
+    let test_cases = read_file(path).map_err(|error| format!("{}: {}", path, error))?;
+
+    let results = benchmark::run_benchmark(&solvers, &test_cases, options.limit, || options.limits());
+    benchmark::print_summary(&results);
+    if let Some(csv) = &options.csv {
+        fs::write(csv, benchmark::to_csv(&results)).map_err(|error| format!("couldn't write {}: {}", csv, error))?;
+    }
+    if let Some(json) = &options.json {
+        fs::write(json, benchmark::to_json(&results)).map_err(|error| format!("couldn't write {}: {}", json, error))?;
+    }
+    Ok(())
+}
+
+/// The start states, with the limit that came along with each one if it was read from a suite
+fn read_start_states<S: State<Action = A>, A: Action>(input: &Input, puzzle: &str, read_file: ReadFile<S, A>, read_state: ReadState<S>) -> Result<Vec<(S, Option<f64>)>, String> {
+    match input {
+        Input::Arguments(states) => {
+            states.iter().map(|state| read_state(state).map(|state| (state, None)).map_err(|reason| match reason {
+                Some(reason) => format!("{} isn't a {} state, {}", state, puzzle, reason),
+                None => format!("{} isn't a {} state", state, puzzle),
+            })).collect()
+        }
+        Input::File(path) => {
+            let test_cases = read_file(path).map_err(|error| format!("{}: {}", path, error))?;
+            Ok(test_cases.iter().map(|test_case| (test_case.get_start_state().clone(), Some(test_case.get_limit()))).collect())
+        }
+        // every line is either a state on its own or a line of a suite
+        Input::Stdin => {
+            let mut start_states = Vec::new();
+            for (number, line) in io::stdin().lock().lines().enumerate() {
+                let line = line.map_err(|error| format!("couldn't read stdin: {}", error))?;
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if let Ok(state) = read_state(line) {
+                    start_states.push((state, None));
+                    continue;
+                }
+                let test_case = suite::parse_test_case::<S, A>(line).map_err(|message| format!("{}", SuiteError::new(number + 1, message)))?;
+                start_states.push((test_case.get_start_state().clone(), Some(test_case.get_limit())));
+            }
+            Ok(start_states)
+        }
+    }
+}
+
+///////////////////////////////////////////////////////
+
+fn print_text<S: State<Action = A>, A: Action>(start_state: &S, outcome: &SearchOutcome<S, A>, time: f64, write_path: WritePath<A>) {
+    match outcome {
+        SearchOutcome::Solved(solution) => {
+            match solution.verify() {
+                Ok(cost) => println!("{}: solved with {} moves (cost {}) in {:.3} ms", start_state.to_string(), solution.path_length(), cost, time),
+                Err(error) => println!("{}: found an invalid solution in {:.3} ms, {}", start_state.to_string(), time, error),
+            }
+            println!("    {}", write_path(solution.get_path()));
+        }
+        SearchOutcome::Exhausted(_) => println!("{}: no solution found in {:.3} ms", start_state.to_string(), time),
+        SearchOutcome::LimitReached(limit, _) => println!("{}: stopped by the {} after {:.3} ms", start_state.to_string(), limit, time),
+    }
+    println!("    {}", outcome.get_statistics());
+}
+
+fn json_result<S: State<Action = A>, A: Action>(start_state: &S, outcome: &SearchOutcome<S, A>, time: f64, write_path: WritePath<A>) -> String {
+    let result = match outcome {
+        SearchOutcome::Solved(solution) => {
+            let path = write_path(solution.get_path()).split_whitespace().map(json_string).collect::<Vec<String>>().join(",");
+            match solution.verify() {
+                Ok(cost) => format!("\"outcome\":\"solved\",\"length\":{},\"cost\":{},\"path\":[{}]", solution.path_length(), cost, path),
+                Err(error) => format!("\"outcome\":\"invalid\",\"error\":{},\"length\":{},\"path\":[{}]", json_string(&error), solution.path_length(), path),
+            }
+        }
+        SearchOutcome::Exhausted(_) => String::from("\"outcome\":\"exhausted\""),
+        SearchOutcome::LimitReached(limit, _) => format!("\"outcome\":\"limit reached\",\"limit\":{}", json_string(&limit.to_string())),
+    };
+    format!("{{\"start\":{},{},\"time_ms\":{:.3},\"statistics\":{}}}", json_string(&start_state.to_string()), result, time, json_statistics(outcome.get_statistics()))
+}
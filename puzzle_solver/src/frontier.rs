@@ -1,6 +1,6 @@
 // Author: Harper Davis
 
-use std::collections::{VecDeque, BinaryHeap};
+use std::{collections::{VecDeque, BinaryHeap}, cmp::Ordering, marker::PhantomData};
 
 use crate::{state::State, action::Action, node::Node};
 
@@ -10,6 +10,8 @@ pub trait Frontier<S: State<Action = A>, A: Action> {
     fn is_empty(&self) -> bool;
     fn insert(&mut self, node: Node<S, A>);
     fn pop(&mut self) -> Option<Node<S, A>>;
+    /// The node that pop would return next, without removing it
+    fn peek(&self) -> Option<&Node<S, A>>;
     fn size(&self) -> usize;
 }
 
@@ -39,6 +41,10 @@ impl <S: State<Action = A>, A: Action> Frontier<S, A> for QueueFrontier<S, A> {
         self.queue.pop_front()
     }
 
+    fn peek(&self) -> Option<&Node<S, A>> {
+        self.queue.front()
+    }
+
     fn size(&self) -> usize {
         self.queue.len()
     }
@@ -49,7 +55,7 @@ pub struct StackFrontier<S: State<Action = A>, A: Action> {
     stack: VecDeque<Node<S, A>>,
 }
 
-impl <'a, S: State<Action = A>, A: Action> Frontier<S, A> for StackFrontier<S, A> {
+impl <S: State<Action = A>, A: Action> Frontier<S, A> for StackFrontier<S, A> {
 
     fn new() -> StackFrontier<S, A> {
         StackFrontier { stack: VecDeque::new() }
@@ -71,20 +77,103 @@ impl <'a, S: State<Action = A>, A: Action> Frontier<S, A> for StackFrontier<S, A
         self.stack.pop_back()
     }
 
+    fn peek(&self) -> Option<&Node<S, A>> {
+        self.stack.back()
+    }
+
     fn size(&self) -> usize {
         self.stack.len()
     }
 
 }
 
-pub struct PriorityQueueFrontier<S: State<Action = A>, A: Action> {
-    queue: BinaryHeap<Node<S, A>>
+/// What a PriorityQueueFrontier orders its nodes by, lowest first
+pub trait Priority<S: State<Action = A>, A: Action> {
+    fn priority(node: &Node<S, A>) -> f64;
+}
+
+/// Path cost plus heuristic, which makes A*
+pub struct Evaluation;
+
+impl<S: State<Action = A>, A: Action> Priority<S, A> for Evaluation {
+    fn priority(node: &Node<S, A>) -> f64 {
+        node.eval()
+    }
+}
+
+/// Only the path cost, which makes uniform cost search
+pub struct PathCost;
+
+impl<S: State<Action = A>, A: Action> Priority<S, A> for PathCost {
+    fn priority(node: &Node<S, A>) -> f64 {
+        node.get_path_cost()
+    }
+}
+
+/// Only the heuristic, which makes greedy best first search
+pub struct Estimate;
+
+impl<S: State<Action = A>, A: Action> Priority<S, A> for Estimate {
+    fn priority(node: &Node<S, A>) -> f64 {
+        node.get_heuristic()
+    }
 }
 
-impl <S: State<Action = A>, A: Action> Frontier<S, A> for PriorityQueueFrontier<S, A> {
+/// A node along with the priority it was queued under, BinaryHeap puts the lowest priority on top
+pub struct Prioritized<S: State<Action = A>, A: Action> {
+    priority: f64,
+    node: Node<S, A>,
+}
+
+impl<S: State<Action = A>, A: Action> Prioritized<S, A> {
+
+    pub fn new(priority: f64, node: Node<S, A>) -> Prioritized<S, A> {
+        Prioritized { priority, node }
+    }
+
+    pub fn get_priority(&self) -> f64 {
+        self.priority
+    }
 
-    fn new() -> PriorityQueueFrontier<S, A> {
-        PriorityQueueFrontier { queue: BinaryHeap::new() }
+    pub fn get_node(&self) -> &Node<S, A> {
+        &self.node
+    }
+
+    pub fn into_node(self) -> Node<S, A> {
+        self.node
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action> PartialEq for Prioritized<S, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl<S: State<Action = A>, A: Action> Eq for Prioritized<S, A> {}
+
+impl<S: State<Action = A>, A: Action> PartialOrd for Prioritized<S, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: State<Action = A>, A: Action> Ord for Prioritized<S, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+pub struct PriorityQueueFrontier<S: State<Action = A>, A: Action, P: Priority<S, A> = Evaluation> {
+    queue: BinaryHeap<Prioritized<S, A>>,
+    phantom_p: PhantomData<P>,
+}
+
+impl <S: State<Action = A>, A: Action, P: Priority<S, A>> Frontier<S, A> for PriorityQueueFrontier<S, A, P> {
+
+    fn new() -> PriorityQueueFrontier<S, A, P> {
+        PriorityQueueFrontier { queue: BinaryHeap::new(), phantom_p: PhantomData }
     }
 
     fn clear(&mut self) {
@@ -96,11 +185,15 @@ impl <S: State<Action = A>, A: Action> Frontier<S, A> for PriorityQueueFrontier<
     }
 
     fn insert(&mut self, node: Node<S, A>) {
-        self.queue.push(node);
+        self.queue.push(Prioritized::new(P::priority(&node), node));
     }
 
     fn pop(&mut self) -> Option<Node<S, A>> {
-        self.queue.pop()
+        self.queue.pop().map(Prioritized::into_node)
+    }
+
+    fn peek(&self) -> Option<&Node<S, A>> {
+        self.queue.peek().map(Prioritized::get_node)
     }
 
     fn size(&self) -> usize {
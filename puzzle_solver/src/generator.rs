@@ -0,0 +1,146 @@
+// Author: Harper Davis
+use std::ops::RangeInclusive;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    action::InvertibleAction,
+    search::{Search, SearchOutcome},
+    suboptimal_search::WeightedAStarSearch,
+    test::TestCase,
+    puzzles::{
+        silly_puzzle::{SillyPuzzle, SillyPuzzleAction},
+        fifteen_puzzle::{FifteenPuzzle, FifteenPuzzleAction},
+        fifteen_puzzle_heuristics::LinearConflict,
+        sliding_puzzle::{SlidingPuzzle, SlidingPuzzleAction},
+        rubiks_cube::{CubeState, CubeAction},
+        n_cube::{NCube, NCubeAction},
+    },
+};
+
+/// Makes test cases out of a seeded rng, so the same seed always makes the same suite and a run can be repeated
+/// without keeping the suite around (though suite::write_test_cases can keep it too)
+pub struct Generator<R: Rng + SeedableRng = StdRng> {
+    rng: R,
+}
+
+impl<R: Rng + SeedableRng> Generator<R> {
+
+    pub fn new(seed: u64) -> Generator<R> {
+        Generator { rng: R::seed_from_u64(seed) }
+    }
+
+    pub fn get_rng(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// `count` cubes for every scramble length in `moves`, solved by undoing the scramble. The limit is the length of
+    /// the scramble, which can be more than the fewest moves since nothing stops a scramble from undoing itself
+    pub fn cube_scrambles(&mut self, moves: RangeInclusive<u32>, count: usize) -> Vec<TestCase<CubeState, CubeAction>> {
+        let mut test_cases = Vec::new();
+        for action_count in moves {
+            for _ in 0..count {
+                let (cube, scramble) = CubeState::scrambled_cube_with(&mut self.rng, action_count);
+                let path = scramble.iter().rev().map(|action| action.inverse()).collect();
+                test_cases.push(TestCase::new(cube, path, action_count as f64));
+            }
+        }
+        test_cases
+    }
+
+    /// The same as cube_scrambles for a cube of any size
+    pub fn n_cube_scrambles<const N: usize>(&mut self, moves: RangeInclusive<u32>, count: usize) -> Vec<TestCase<NCube<N>, NCubeAction<N>>> {
+        let mut test_cases = Vec::new();
+        for action_count in moves {
+            for _ in 0..count {
+                let (cube, scramble) = NCube::<N>::scrambled_with(&mut self.rng, action_count);
+                let path = scramble.iter().rev().map(|action| action.inverse()).collect();
+                test_cases.push(TestCase::new(cube, path, action_count as f64));
+            }
+        }
+        test_cases
+    }
+
+    /// `count` boards for every walk length in `moves`, solved by walking back. Like the cube the limit is the length
+    /// of the walk, it never moves a piece straight back but it can still go around in a circle
+    pub fn fifteen_puzzle_walks(&mut self, moves: RangeInclusive<u32>, count: usize) -> Vec<TestCase<FifteenPuzzle, FifteenPuzzleAction>> {
+        let mut test_cases = Vec::new();
+        for action_count in moves {
+            for _ in 0..count {
+                let (puzzle, path) = FifteenPuzzle::scrambled_with(&mut self.rng, action_count);
+                test_cases.push(TestCase::new(puzzle, path, action_count as f64));
+            }
+        }
+        test_cases
+    }
+
+    /// The same as fifteen_puzzle_walks for a sliding puzzle of any size
+    pub fn sliding_puzzle_walks<const W: usize, const H: usize>(&mut self, moves: RangeInclusive<u32>, count: usize) -> Vec<TestCase<SlidingPuzzle<W, H>, SlidingPuzzleAction>> {
+        let mut test_cases = Vec::new();
+        for action_count in moves {
+            for _ in 0..count {
+                let (puzzle, path) = SlidingPuzzle::<W, H>::scrambled_with(&mut self.rng, action_count);
+                test_cases.push(TestCase::new(puzzle, path, action_count as f64));
+            }
+        }
+        test_cases
+    }
+
+    /// Boards picked evenly out of every solvable one, in order of how long their paths are. Finding the shortest
+    /// path for that many would take ages, so the path is whatever weighted A* finds, which is at most twice as long
+    pub fn random_fifteen_puzzles(&mut self, count: usize) -> Vec<TestCase<FifteenPuzzle, FifteenPuzzleAction>> {
+        let mut test_cases = Vec::new();
+        for _ in 0..count {
+            let puzzle = FifteenPuzzle::random_with(&mut self.rng);
+            let path = match WeightedAStarSearch::<FifteenPuzzle, FifteenPuzzleAction, LinearConflict>::new(0.0).search(puzzle.clone()) {
+                SearchOutcome::Solved(solution) => solution.get_path().clone(),
+                _ => unreachable!("every solvable board has a solution"),
+            };
+            let limit = path.len() as f64;
+            test_cases.push(TestCase::new(puzzle, path, limit));
+        }
+        test_cases.sort_by_key(|test_case| test_case.get_depth_limit());
+        test_cases
+    }
+
+    /// Starts anywhere on the line, in order of how far they are from 69
+    pub fn silly_puzzles(&mut self, count: usize) -> Vec<TestCase<SillyPuzzle, SillyPuzzleAction>> {
+        let mut test_cases = (0..count).map(|_| {
+            let puzzle = SillyPuzzle::random_with(&mut self.rng);
+            let path = puzzle.path_to_goal();
+            let limit = path.len() as f64;
+            TestCase::new(puzzle, path, limit)
+        }).collect::<Vec<TestCase<SillyPuzzle, SillyPuzzleAction>>>();
+        test_cases.sort_by_key(|test_case| test_case.get_depth_limit());
+        test_cases
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{state::State, action::Action, suite::{format_test_cases, parse_test_cases}};
+
+    use super::*;
+
+    /// Writing the suite out and reading it back gives the same suite, and so does the same seed
+    fn assert_reproducible<S: State<Action = A>, A: Action>(generate: impl Fn(&mut Generator) -> Vec<TestCase<S, A>>) {
+        let contents = format_test_cases("header", &generate(&mut Generator::new(22)));
+        assert_eq!(contents, format_test_cases("header", &generate(&mut Generator::new(22))));
+        assert_ne!(contents, format_test_cases("header", &generate(&mut Generator::new(23))));
+
+        let test_cases = parse_test_cases::<S, A>(&contents).unwrap();
+        assert_eq!(format_test_cases("header", &test_cases), contents);
+    }
+
+    #[test]
+    fn suites_are_reproducible() {
+        assert_reproducible(|generator| generator.cube_scrambles(0..=8, 5));
+        assert_reproducible(|generator| generator.n_cube_scrambles::<4>(0..=8, 5));
+        assert_reproducible(|generator| generator.fifteen_puzzle_walks(0..=30, 5));
+        assert_reproducible(|generator| generator.sliding_puzzle_walks::<3, 3>(0..=20, 5));
+        assert_reproducible(|generator| generator.random_fifteen_puzzles(3));
+        assert_reproducible(|generator| generator.silly_puzzles(20));
+    }
+
+}
@@ -0,0 +1,125 @@
+// Author: Harper Davis
+
+use crate::state::State;
+
+/// An estimate of the remaining cost from a state to the goal. Informed searches are generic
+/// over one of these, so different heuristics can be compared without touching the puzzle itself
+pub trait Heuristic<S: State> {
+    fn estimate(&self, state: &S) -> f64;
+}
+
+/// An estimate of the cost between any two states rather than just to the goal, which is what a
+/// search needs when the thing it's heading towards keeps changing, like the other half of a bidirectional search
+pub trait PairwiseHeuristic<S: State> {
+    fn estimate_between(&self, from: &S, to: &S) -> f64;
+}
+
+/// Whatever the puzzle itself returns from State::heuristic
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StateHeuristic;
+
+impl<S: State> Heuristic<S> for StateHeuristic {
+    fn estimate(&self, state: &S) -> f64 {
+        state.heuristic()
+    }
+}
+
+/// Always 0, which turns A* into uniform cost search
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZeroHeuristic;
+
+impl<S: State> Heuristic<S> for ZeroHeuristic {
+    fn estimate(&self, _state: &S) -> f64 {
+        0.0
+    }
+}
+
+impl<S: State> PairwiseHeuristic<S> for ZeroHeuristic {
+    fn estimate_between(&self, _from: &S, _to: &S) -> f64 {
+        0.0
+    }
+}
+
+/// The larger of two estimates, which is still admissible if both of them are
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaxOf<H1, H2> {
+    first: H1,
+    second: H2,
+}
+
+impl<H1, H2> MaxOf<H1, H2> {
+    pub fn new(first: H1, second: H2) -> MaxOf<H1, H2> {
+        MaxOf { first, second }
+    }
+}
+
+impl<S: State, H1: Heuristic<S>, H2: Heuristic<S>> Heuristic<S> for MaxOf<H1, H2> {
+    fn estimate(&self, state: &S) -> f64 {
+        f64::max(self.first.estimate(state), self.second.estimate(state))
+    }
+}
+
+/// first_weight * first + second_weight * second. The default weights are both 1, which is
+/// only admissible when the two heuristics count disjoint parts of the cost
+#[derive(Debug, Clone, Copy)]
+pub struct WeightedSum<H1, H2> {
+    first: H1,
+    first_weight: f64,
+    second: H2,
+    second_weight: f64,
+}
+
+impl<H1, H2> WeightedSum<H1, H2> {
+    pub fn new(first: H1, first_weight: f64, second: H2, second_weight: f64) -> WeightedSum<H1, H2> {
+        WeightedSum { first, first_weight, second, second_weight }
+    }
+}
+
+impl<H1: Default, H2: Default> Default for WeightedSum<H1, H2> {
+    fn default() -> Self {
+        WeightedSum::new(H1::default(), 1.0, H2::default(), 1.0)
+    }
+}
+
+impl<S: State, H1: Heuristic<S>, H2: Heuristic<S>> Heuristic<S> for WeightedSum<H1, H2> {
+    fn estimate(&self, state: &S) -> f64 {
+        self.first_weight * self.first.estimate(state) + self.second_weight * self.second.estimate(state)
+    }
+}
+
+/// weight * another heuristic. Over 1 it isn't admissible anymore, but A* using it still finds a solution
+/// that costs at most `weight` times the cheapest one, usually a lot faster. The default weight is 2
+#[derive(Debug, Clone, Copy)]
+pub struct Weighted<H> {
+    heuristic: H,
+    weight: f64,
+}
+
+impl<H> Weighted<H> {
+    pub fn new(weight: f64, heuristic: H) -> Weighted<H> {
+        Weighted { heuristic, weight }
+    }
+
+    pub fn get_weight(&self) -> f64 {
+        self.weight
+    }
+}
+
+impl<H: Default> Default for Weighted<H> {
+    fn default() -> Self {
+        Weighted::new(2.0, H::default())
+    }
+}
+
+impl<S: State, H: Heuristic<S>> Heuristic<S> for Weighted<H> {
+    fn estimate(&self, state: &S) -> f64 {
+        self.weight * self.heuristic.estimate(state)
+    }
+}
+
+/// Any function of the state works as a heuristic too
+impl<S: State, F: Fn(&S) -> f64> Heuristic<S> for F {
+    fn estimate(&self, state: &S) -> f64 {
+        self(state)
+    }
+}
@@ -0,0 +1,24 @@
+// Author: Harper Davis
+use crate::statistics::SearchStatistics;
+
+// Just enough JSON for the command line and benchmark output, so there's no need for a crate
+
+pub fn json_statistics(statistics: &SearchStatistics) -> String {
+    format!("{{\"generated\":{},\"expanded\":{},\"pruned\":{},\"max_frontier\":{},\"iterations\":{}}}",
+        statistics.get_nodes_generated(), statistics.get_nodes_expanded(), statistics.get_pruned(), statistics.get_max_frontier_size(), statistics.get_iterations())
+}
+
+pub fn json_string(string: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
@@ -0,0 +1,132 @@
+// Author: Harper Davis
+use std::{fmt, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::{Duration, Instant}};
+
+use crate::statistics::SearchStatistics;
+
+/// Which budget stopped a search before it could finish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Nodes,
+    FrontierSize,
+    Time,
+    Cancelled,
+    /// A memory bounded search couldn't fit a path to a goal in the nodes it was allowed
+    Memory,
+}
+
+impl fmt::Display for Limit {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Nodes => write!(f, "node limit"),
+            Limit::FrontierSize => write!(f, "frontier size limit"),
+            Limit::Time => write!(f, "time limit"),
+            Limit::Cancelled => write!(f, "cancelled"),
+            Limit::Memory => write!(f, "memory limit"),
+        }
+    }
+
+}
+
+/// Budgets a search has to stay inside of, every one of them is optional
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    max_nodes: Option<u64>,
+    max_frontier_size: Option<usize>,
+    max_memory: Option<usize>,
+    deadline: Option<Instant>,
+    cancelled: Vec<Arc<AtomicBool>>,
+}
+
+impl SearchLimits {
+
+    pub fn none() -> SearchLimits {
+        SearchLimits::default()
+    }
+
+    /// Stop after this many nodes have been generated
+    pub fn with_max_nodes(mut self, max_nodes: u64) -> SearchLimits {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// Stop once the frontier holds more than this many nodes, which is what actually uses up the memory
+    pub fn with_max_frontier_size(mut self, max_frontier_size: usize) -> SearchLimits {
+        self.max_frontier_size = Some(max_frontier_size);
+        self
+    }
+
+    /// How many nodes a memory bounded search may keep at once. Unlike the others it doesn't stop the search,
+    /// searches that don't bound their memory ignore it
+    pub fn with_max_memory(mut self, max_memory: usize) -> SearchLimits {
+        self.max_memory = Some(max_memory);
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> SearchLimits {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_time_limit(self, time_limit: Duration) -> SearchLimits {
+        self.with_deadline(Instant::now() + time_limit)
+    }
+
+    /// The search stops the next time it checks its limits after the flag is set, from any thread.
+    /// More than one flag can be given, and setting any of them stops it
+    pub fn with_cancel_flag(mut self, cancelled: Arc<AtomicBool>) -> SearchLimits {
+        self.cancelled.push(cancelled);
+        self
+    }
+
+    pub fn get_max_nodes(&self) -> Option<u64> {
+        self.max_nodes
+    }
+
+    pub fn get_max_frontier_size(&self) -> Option<usize> {
+        self.max_frontier_size
+    }
+
+    pub fn get_max_memory(&self) -> Option<usize> {
+        self.max_memory
+    }
+
+    pub fn get_deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns the first limit that has been exceeded, if any
+    pub fn check(&self, statistics: &SearchStatistics, frontier_size: usize) -> Option<Limit> {
+        self.check_counts(statistics.get_nodes_generated(), frontier_size)
+    }
+
+    /// Same as check, for searches that keep their counts somewhere other than a single SearchStatistics
+    pub fn check_counts(&self, nodes_generated: u64, frontier_size: usize) -> Option<Limit> {
+        if self.cancelled.iter().any(|cancelled| cancelled.load(Ordering::Relaxed)) {
+            return Some(Limit::Cancelled);
+        }
+        if self.max_nodes.is_some_and(|max_nodes| nodes_generated >= max_nodes) {
+            return Some(Limit::Nodes);
+        }
+        if self.max_frontier_size.is_some_and(|max_frontier_size| frontier_size > max_frontier_size) {
+            return Some(Limit::FrontierSize);
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Some(Limit::Time);
+        }
+        None
+    }
+
+    /// The limits left over for a follow up search once the given work has been done,
+    /// used by the iterative searches so that the node budget covers every iteration
+    pub fn remaining(&self, statistics: &SearchStatistics) -> SearchLimits {
+        self.remaining_after(statistics.get_nodes_generated())
+    }
+
+    pub fn remaining_after(&self, nodes_generated: u64) -> SearchLimits {
+        let mut remaining = self.clone();
+        remaining.max_nodes = self.max_nodes.map(|max_nodes| max_nodes.saturating_sub(nodes_generated));
+        remaining
+    }
+
+}
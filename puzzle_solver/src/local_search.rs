@@ -0,0 +1,421 @@
+// Author: Harper Davis
+
+use std::{collections::{HashMap, HashSet}, fmt, marker::PhantomData, sync::Arc};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{state::State, action::Action, frontier::{Priority, Evaluation, QueueFrontier, StackFrontier}, node::Node, statistics::SearchStatistics, limits::SearchLimits, search::{Search, SearchOutcome, Solution}, heuristic::{Heuristic, StateHeuristic}};
+
+/// Simulated annealing stops once it has cooled down past this
+const MINIMUM_TEMPERATURE: f64 = 0.01;
+/// What the random local searches are seeded with when they aren't given a seed, so every run goes the same way
+const DEFAULT_SEED: u64 = 14;
+
+/// Breadth first search that only keeps the `width` best nodes of every level, by whatever `P` says is best.
+/// Memory stays at about width times depth, but the solution can be far from the cheapest, and it gives up
+/// without one if every path to a goal falls out of the beam
+pub struct BeamSearch<S: State<Action = A>, A: Action, P: Priority<S, A> = Evaluation, H: Heuristic<S> = StateHeuristic> {
+    width: usize,
+    heuristic: H,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+    phantom_p: PhantomData<P>,
+}
+
+impl<S: State<Action = A>, A: Action, P: Priority<S, A>, H: Heuristic<S>> BeamSearch<S, A, P, H> {
+
+    pub fn with_width(width: usize, heuristic: H) -> BeamSearch<S, A, P, H> {
+        BeamSearch { width: usize::max(width, 1), heuristic, statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData, phantom_p: PhantomData }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, P: Priority<S, A>, H: Heuristic<S>> fmt::Display for BeamSearch<S, A, P, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BeamSearch")
+    }
+}
+
+impl<S: State<Action = A>, A: Action, P: Priority<S, A>, H: Heuristic<S> + Default> Search<S, A, QueueFrontier<S, A>> for BeamSearch<S, A, P, H> {
+
+    fn new(_limit: f64) -> BeamSearch<S, A, P, H> {
+        BeamSearch::with_width(1000, H::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+        self.statistics.iteration();
+
+        let start_node = Node::new(&start_state, 0.0, 0, &self.heuristic);
+        if start_state.is_goal_state() {
+            return SearchOutcome::Solved(Solution::from_node(start_state, &start_node, self.statistics.clone()));
+        }
+        let mut reached = HashSet::from([start_state.clone()]);
+        let mut level = vec![start_node];
+        self.statistics.frontier_size(level.len());
+
+        while !level.is_empty() {
+            let mut next_level = Vec::new();
+            for node in level {
+                if let Some(limit) = limits.check(&self.statistics, next_level.len()) {
+                    return SearchOutcome::LimitReached(limit, self.statistics.clone());
+                }
+
+                self.statistics.expanded();
+                let shared_node = Arc::new(node);
+                for action in shared_node.get_possible_actions() {
+                    let next_node = Node::<S, A>::next_node(shared_node.clone(), &action, &self.heuristic);
+                    self.statistics.generated();
+
+                    if reached.contains(next_node.get_state()) {
+                        self.statistics.pruned();
+                        continue;
+                    }
+                    if next_node.get_state().is_goal_state() {
+                        return SearchOutcome::Solved(Solution::from_node(start_state, &next_node, self.statistics.clone()));
+                    }
+                    reached.insert(next_node.get_state().clone());
+                    next_level.push(next_node);
+                }
+            }
+
+            if next_level.len() > self.width {
+                next_level.select_nth_unstable_by(self.width, |a, b| P::priority(a).total_cmp(&P::priority(b)));
+                next_level.truncate(self.width);
+            }
+            self.statistics.frontier_size(next_level.len());
+            level = next_level;
+        }
+
+        SearchOutcome::Exhausted(self.statistics.clone())
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        unreachable!()
+    }
+
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &self.heuristic
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+///////////////////////////////////////////////////////
+
+/// The states a local search has walked through, and the actions between them. Coming back to a
+/// state it went through before cuts the loop out, so the path at the end never repeats a state
+struct Walk<S: State<Action = A>, A: Action> {
+    states: Vec<S>,
+    path: Vec<A>,
+    positions: HashMap<S, usize>,
+}
+
+impl<S: State<Action = A>, A: Action> Walk<S, A> {
+
+    fn new(start_state: S) -> Walk<S, A> {
+        Walk { positions: HashMap::from([(start_state.clone(), 0)]), states: vec![start_state], path: Vec::new() }
+    }
+
+    fn current(&self) -> &S {
+        self.states.last().unwrap()
+    }
+
+    fn step(&mut self, action: A, state: S) {
+        if let Some(&position) = self.positions.get(&state) {
+            for state in self.states.drain(position + 1..) {
+                self.positions.remove(&state);
+            }
+            self.path.truncate(position);
+        } else {
+            self.positions.insert(state.clone(), self.states.len());
+            self.states.push(state);
+            self.path.push(action);
+        }
+    }
+
+    fn into_solution(mut self, statistics: SearchStatistics) -> Solution<S, A> {
+        let final_state = self.states.pop().unwrap();
+        let start_state = self.states.into_iter().next().unwrap_or_else(|| final_state.clone());
+        Solution::new(start_state, final_state, self.path, statistics)
+    }
+
+}
+
+/// Steepest descent on the heuristic: always moves to the neighbour with the lowest estimate (a random one
+/// out of any that are tied), and stops at a goal or when every neighbour is worse. Up to `max_sideways_moves`
+/// moves in a row are allowed to a neighbour that's only as good, to get across flat stretches
+pub struct HillClimbingSearch<S: State<Action = A>, A: Action, H: Heuristic<S> = StateHeuristic> {
+    max_sideways_moves: u32,
+    heuristic: H,
+    rng: StdRng,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> HillClimbingSearch<S, A, H> {
+
+    pub fn with_heuristic(heuristic: H) -> HillClimbingSearch<S, A, H> {
+        HillClimbingSearch { max_sideways_moves: 100, heuristic, rng: StdRng::seed_from_u64(DEFAULT_SEED), statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData }
+    }
+
+    /// Breaks ties with an rng seeded with `seed` rather than the default one
+    pub fn with_seed(mut self, seed: u64) -> HillClimbingSearch<S, A, H> {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    pub fn with_sideways_moves(mut self, max_sideways_moves: u32) -> HillClimbingSearch<S, A, H> {
+        self.max_sideways_moves = max_sideways_moves;
+        self
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> fmt::Display for HillClimbingSearch<S, A, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HillClimbingSearch")
+    }
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S> + Default> Search<S, A, StackFrontier<S, A>> for HillClimbingSearch<S, A, H> {
+
+    fn new(_limit: f64) -> HillClimbingSearch<S, A, H> {
+        HillClimbingSearch::with_heuristic(H::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+        self.statistics.iteration();
+
+        let mut estimate = self.heuristic.estimate(&start_state);
+        let mut walk = Walk::new(start_state);
+        let mut sideways_moves = 0;
+
+        while !walk.current().is_goal_state() {
+            if let Some(limit) = limits.check(&self.statistics, 1) {
+                return SearchOutcome::LimitReached(limit, self.statistics.clone());
+            }
+
+            self.statistics.expanded();
+            let mut best: Option<(f64, A, S)> = None;
+            let mut ties = 0;
+            for action in walk.current().list_actions() {
+                let next_state = walk.current().perform_action(&action);
+                let next_estimate = self.heuristic.estimate(&next_state);
+                self.statistics.generated();
+
+                match &best {
+                    Some((best_estimate, _, _)) if next_estimate > *best_estimate => continue,
+                    Some((best_estimate, _, _)) if next_estimate == *best_estimate => {
+                        ties += 1;
+                        if self.rng.gen_range(0..ties) != 0 {
+                            continue;
+                        }
+                    }
+                    _ => ties = 1,
+                }
+                best = Some((next_estimate, action, next_state));
+            }
+
+            let Some((next_estimate, action, next_state)) = best else {
+                break;
+            };
+            if next_estimate > estimate {
+                break;
+            }
+            if next_estimate == estimate {
+                sideways_moves += 1;
+                if sideways_moves > self.max_sideways_moves {
+                    break;
+                }
+            } else {
+                sideways_moves = 0;
+            }
+            estimate = next_estimate;
+            walk.step(action, next_state);
+        }
+
+        if walk.current().is_goal_state() {
+            SearchOutcome::Solved(walk.into_solution(self.statistics.clone()))
+        } else {
+            SearchOutcome::Exhausted(self.statistics.clone())
+        }
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        unreachable!()
+    }
+
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &self.heuristic
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+///////////////////////////////////////////////////////
+
+/// Random walk on the heuristic that always takes a move to a better state, and takes a move to a worse one
+/// with a chance of e^(-increase / temperature). The temperature starts at `initial_temperature` and gets
+/// multiplied by `cooling_rate` (somewhere between 0 and 1) after every move tried, so it wanders a lot at
+/// first and settles down towards the end. Gives up once the temperature drops below 0.01
+pub struct SimulatedAnnealingSearch<S: State<Action = A>, A: Action, H: Heuristic<S> = StateHeuristic> {
+    initial_temperature: f64,
+    cooling_rate: f64,
+    heuristic: H,
+    rng: StdRng,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> SimulatedAnnealingSearch<S, A, H> {
+
+    pub fn with_schedule(initial_temperature: f64, cooling_rate: f64, heuristic: H) -> SimulatedAnnealingSearch<S, A, H> {
+        SimulatedAnnealingSearch {
+            initial_temperature,
+            cooling_rate: cooling_rate.clamp(0.0, 1.0 - f64::EPSILON),
+            heuristic,
+            rng: StdRng::seed_from_u64(DEFAULT_SEED),
+            statistics: SearchStatistics::new(),
+            phantom_s: PhantomData,
+            phantom_a: PhantomData,
+        }
+    }
+
+    /// Picks moves and whether to take them with an rng seeded with `seed` rather than the default one
+    pub fn with_seed(mut self, seed: u64) -> SimulatedAnnealingSearch<S, A, H> {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> fmt::Display for SimulatedAnnealingSearch<S, A, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SimulatedAnnealingSearch")
+    }
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S> + Default> Search<S, A, StackFrontier<S, A>> for SimulatedAnnealingSearch<S, A, H> {
+
+    fn new(_limit: f64) -> SimulatedAnnealingSearch<S, A, H> {
+        SimulatedAnnealingSearch::with_schedule(2.0, 0.9999, H::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+        self.statistics.iteration();
+
+        let mut estimate = self.heuristic.estimate(&start_state);
+        let mut walk = Walk::new(start_state);
+        let mut temperature = self.initial_temperature;
+
+        while !walk.current().is_goal_state() && temperature >= MINIMUM_TEMPERATURE {
+            if let Some(limit) = limits.check(&self.statistics, 1) {
+                return SearchOutcome::LimitReached(limit, self.statistics.clone());
+            }
+
+            self.statistics.expanded();
+            let actions = walk.current().list_actions();
+            if actions.is_empty() {
+                break;
+            }
+            let action = actions[self.rng.gen_range(0..actions.len())].clone();
+            let next_state = walk.current().perform_action(&action);
+            let next_estimate = self.heuristic.estimate(&next_state);
+            self.statistics.generated();
+
+            let increase = next_estimate - estimate;
+            if increase <= 0.0 || self.rng.gen::<f64>() < (-increase / temperature).exp() {
+                estimate = next_estimate;
+                walk.step(action, next_state);
+            } else {
+                self.statistics.pruned();
+            }
+            temperature *= self.cooling_rate;
+        }
+
+        if walk.current().is_goal_state() {
+            SearchOutcome::Solved(walk.into_solution(self.statistics.clone()))
+        } else {
+            SearchOutcome::Exhausted(self.statistics.clone())
+        }
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        unreachable!()
+    }
+
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &self.heuristic
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{generator::Generator, puzzles::sliding_puzzle::{EightPuzzle, SlidingPuzzleAction}};
+
+    use super::*;
+
+    /// The path found from the start state, after checking it's a real one, or None if there wasn't one
+    fn verified_path<E: Search<EightPuzzle, SlidingPuzzleAction, StackFrontier<EightPuzzle, SlidingPuzzleAction>>>(mut search: E, start_state: &EightPuzzle) -> Option<Vec<SlidingPuzzleAction>> {
+        match search.search(start_state.clone()) {
+            SearchOutcome::Solved(solution) => {
+                assert!(solution.verify().is_ok(), "from {}", start_state);
+                Some(solution.get_path().clone())
+            },
+            SearchOutcome::Exhausted(_) => None,
+            SearchOutcome::LimitReached(limit, _) => panic!("stopped by the {} from {}", limit, start_state),
+        }
+    }
+
+    #[test]
+    fn seeded_walks_find_real_paths_the_same_way_every_time() {
+        let start_states = Generator::<StdRng>::new(14).sliding_puzzle_walks::<3, 3>(0..=8, 5).into_iter()
+            .map(|test_case| test_case.get_start_state().clone()).collect::<Vec<EightPuzzle>>();
+        let mut solved = (0, 0);
+        for start_state in &start_states {
+            for seed in 0..3 {
+                let hill_climbing = || HillClimbingSearch::<EightPuzzle, SlidingPuzzleAction>::with_heuristic(StateHeuristic).with_seed(seed);
+                let path = verified_path(hill_climbing(), start_state);
+                assert!(path == verified_path(hill_climbing(), start_state));
+                solved.0 += path.is_some() as usize;
+
+                let annealing = || SimulatedAnnealingSearch::<EightPuzzle, SlidingPuzzleAction>::with_schedule(2.0, 0.999, StateHeuristic).with_seed(seed);
+                let path = verified_path(annealing(), start_state);
+                assert!(path == verified_path(annealing(), start_state));
+                solved.1 += path.is_some() as usize;
+            }
+        }
+        // neither of them is complete, but on boards this close they should mostly get there
+        assert!(solved.0 > start_states.len() && solved.1 > start_states.len(), "{:?} of {}", solved, 3 * start_states.len());
+    }
+}
@@ -1,12 +1,24 @@
 // Author: Harper Davis
 
-use frontier::{StackFrontier, PriorityQueueFrontier};
-use puzzles::{silly_puzzle::{SillyPuzzle}, fifteen_puzzle::{FifteenPuzzle}, rubiks_cube::CubeState};
-use search::{Search, DepthFirstSearch, AStarSearch, IterativeDeepeningAStarSearch};
+use std::{env, process, time::Instant};
 
-use test::{test_fifteen_puzzle_from_file, TestCase, test};
+use colored::Colorize;
+use rand::rngs::StdRng;
+use frontier::{PriorityQueueFrontier, QueueFrontier, StackFrontier, Estimate};
+use puzzles::{silly_puzzle::{SillyPuzzle, SillyPuzzleAction}, fifteen_puzzle::{FifteenPuzzle}, sliding_puzzle::{SlidingPuzzle, SlidingPuzzleAction, EightPuzzle}, rubiks_cube::CubeState};
+use search::{Search, SearchOutcome, DepthFirstSearch, BreadthFirstSearch, AStarGraphSearch, IterativeDeepeningAStarSearch, GreedyBestFirstSearch};
+use parallel_search::{HashDistributedAStarSearch, ParallelIterativeDeepeningAStarSearch};
+use memory_bounded_search::{RecursiveBestFirstSearch, SimplifiedMemoryBoundedAStarSearch};
+use suboptimal_search::{WeightedAStarSearch, AnytimeRepairingAStarSearch, FocalSearch};
+use local_search::{BeamSearch, HillClimbingSearch, SimulatedAnnealingSearch};
 
-use crate::puzzles::{fifteen_puzzle::FifteenPuzzleAction, rubiks_cube::CubeAction};
+use test::{test_fifteen_puzzle_from_file, test_from_file, test};
+use generator::Generator;
+
+use crate::puzzles::{fifteen_puzzle::FifteenPuzzleAction, rubiks_cube::CubeAction, pattern_database::PatternDatabaseHeuristic, two_phase::TwoPhaseSolver};
+use crate::puzzles::{n_cube::{NCube, NCubeAction, PocketCube}, pocket_cube::PocketCubeDistance, reduction::ReductionSolver};
+use crate::state::State;
+use crate::cli::Command;
 
 
 pub mod state;
@@ -14,41 +26,201 @@ pub mod action;
 pub mod search;
 pub mod node;
 pub mod frontier;
+pub mod statistics;
+pub mod limits;
+pub mod heuristic;
+pub mod bidirectional_search;
+pub mod parallel_search;
+pub mod memory_bounded_search;
+pub mod suboptimal_search;
+pub mod local_search;
+pub mod registry;
+pub mod cli;
+pub mod json;
+pub mod memory;
+pub mod benchmark;
+pub mod generator;
 
 pub mod puzzles;
 
 pub mod test;
+pub mod suite;
 
+#[global_allocator]
+static ALLOCATOR: memory::TrackingAllocator = memory::TrackingAllocator;
 
 fn test_silly_puzzle() {
     let start_state = SillyPuzzle::new(0);
     let mut search = DepthFirstSearch::new(100.0);
     let solution = search.search(start_state);
     match solution {
-        Some(solution) => solution.display(),
-        None => println!("No Solution Found!"),
+        SearchOutcome::Solved(solution) => solution.display(),
+        SearchOutcome::Exhausted(_) => println!("No Solution Found!"),
+        SearchOutcome::LimitReached(limit, _) => println!("Stopped by the {}!", limit),
     }
 }
 
+fn test_silly_puzzle_suite() {
+    test_from_file::<SillyPuzzle, SillyPuzzleAction, QueueFrontier<SillyPuzzle, SillyPuzzleAction>, BreadthFirstSearch<SillyPuzzle, SillyPuzzleAction>>("./tests/silly_puzzle.txt");
+}
+
 fn test_fifteen_puzzle() {
     // ugh
-    test_fifteen_puzzle_from_file::<PriorityQueueFrontier<FifteenPuzzle, FifteenPuzzleAction>, AStarSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+    test_fifteen_puzzle_from_file::<PriorityQueueFrontier<FifteenPuzzle, FifteenPuzzleAction>, AStarGraphSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+}
+
+fn test_fifteen_puzzle_pattern_database() {
+    test_fifteen_puzzle_from_file::<StackFrontier<FifteenPuzzle, FifteenPuzzleAction>, IterativeDeepeningAStarSearch<FifteenPuzzle, FifteenPuzzleAction, PatternDatabaseHeuristic>>("./tests/15_puzzle.txt");
+}
+
+fn test_fifteen_puzzle_parallel() {
+    test_fifteen_puzzle_from_file::<PriorityQueueFrontier<FifteenPuzzle, FifteenPuzzleAction>, HashDistributedAStarSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+    test_fifteen_puzzle_from_file::<StackFrontier<FifteenPuzzle, FifteenPuzzleAction>, ParallelIterativeDeepeningAStarSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+}
+
+fn test_fifteen_puzzle_memory_bounded() {
+    test_fifteen_puzzle_from_file::<StackFrontier<FifteenPuzzle, FifteenPuzzleAction>, RecursiveBestFirstSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+    test_fifteen_puzzle_from_file::<PriorityQueueFrontier<FifteenPuzzle, FifteenPuzzleAction>, SimplifiedMemoryBoundedAStarSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+}
+
+fn test_fifteen_puzzle_suboptimal() {
+    test_fifteen_puzzle_from_file::<PriorityQueueFrontier<FifteenPuzzle, FifteenPuzzleAction>, WeightedAStarSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+    test_fifteen_puzzle_from_file::<PriorityQueueFrontier<FifteenPuzzle, FifteenPuzzleAction>, FocalSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+    test_fifteen_puzzle_from_file::<PriorityQueueFrontier<FifteenPuzzle, FifteenPuzzleAction>, AnytimeRepairingAStarSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+}
+
+fn test_fifteen_puzzle_local() {
+    test_fifteen_puzzle_from_file::<PriorityQueueFrontier<FifteenPuzzle, FifteenPuzzleAction, Estimate>, GreedyBestFirstSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+    test_fifteen_puzzle_from_file::<QueueFrontier<FifteenPuzzle, FifteenPuzzleAction>, BeamSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+    test_fifteen_puzzle_from_file::<StackFrontier<FifteenPuzzle, FifteenPuzzleAction>, HillClimbingSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+    test_fifteen_puzzle_from_file::<StackFrontier<FifteenPuzzle, FifteenPuzzleAction>, SimulatedAnnealingSearch<FifteenPuzzle, FifteenPuzzleAction>>("./tests/15_puzzle.txt");
+}
+
+fn test_sliding_puzzle() {
+    let mut generator = Generator::<StdRng>::new(15);
+    let test_set = generator.sliding_puzzle_walks::<3, 3>(1..=29, 20);
+    test::<EightPuzzle, SlidingPuzzleAction, PriorityQueueFrontier<EightPuzzle, SlidingPuzzleAction>, AStarGraphSearch<EightPuzzle, SlidingPuzzleAction>>(test_set);
+
+    let test_set = generator.sliding_puzzle_walks::<5, 3>(1..=39, 20);
+    test::<SlidingPuzzle<5, 3>, SlidingPuzzleAction, StackFrontier<SlidingPuzzle<5, 3>, SlidingPuzzleAction>, IterativeDeepeningAStarSearch<SlidingPuzzle<5, 3>, SlidingPuzzleAction>>(test_set);
 }
 
 fn test_rubiks_cube() {
-    let mut test_set = Vec::new();
-    for i in 1..100 {
-        for _ in 0..20 {
-            let (state, actions) = CubeState::scrambled_cube(i);
-            test_set.push(TestCase::new(state, actions, i as f64));
-        }
+    // seeded so every run gets the same scrambles
+    let test_set = Generator::<StdRng>::new(20).cube_scrambles(1..=99, 20);
+    test::<CubeState, CubeAction, StackFrontier<CubeState, CubeAction>, IterativeDeepeningAStarSearch<CubeState, CubeAction>>(test_set)
+}
+
+fn test_rubiks_cube_suite() {
+    test_from_file::<CubeState, CubeAction, StackFrontier<CubeState, CubeAction>, IterativeDeepeningAStarSearch<CubeState, CubeAction>>("./tests/rubiks_cube.txt");
+}
+
+fn test_rubiks_cube_two_phase() {
+    let now = Instant::now();
+    let solver = TwoPhaseSolver::new();
+    println!("Built the two phase tables in {:.2}ms", now.elapsed().as_secs_f64() * 1000.0);
+
+    let mut total_length = 0;
+    let mut total_time = 0.0;
+    let scrambles = 100;
+    for test_case in Generator::<StdRng>::new(8).cube_scrambles(40..=40, scrambles) {
+        let state = test_case.get_start_state();
+        let now = Instant::now();
+        let solution = solver.solve(state, 20).expect("every cube has a solution");
+        let time = now.elapsed().as_secs_f64() * 1000.0;
+
+        let solved = solution.iter().fold(state.clone(), |state, action| state.perform_action(action)).is_goal_state();
+        let result = if solved { "PASS".green() } else { "FAIL".red() };
+        println!("{} {} moves in {:.2}ms: {}", result, solution.len(), time, solution.iter().map(|action| action.to_string()).collect::<Vec<String>>().join(" "));
+        total_length += solution.len();
+        total_time += time;
     }
-    test::<CubeState, CubeAction, PriorityQueueFrontier<CubeState, CubeAction>, AStarSearch<CubeState, CubeAction>>(test_set)
+    println!("Average {:.2} moves in {:.2}ms", total_length as f64 / scrambles as f64, total_time / scrambles as f64);
 }
 
-fn main() {
-    // test_silly_puzzle();
-    test_fifteen_puzzle();
-    // test_rubiks_cube();
+fn test_pocket_cube() {
+    let now = Instant::now();
+    PocketCubeDistance.distance(&PocketCube::default());
+    println!("Built the 2x2x2 table in {:.2}ms", now.elapsed().as_secs_f64() * 1000.0);
+    test_n_cube(|cube| PocketCubeDistance.solve(cube), 30);
+}
+
+fn test_reduction() {
+    let now = Instant::now();
+    let (four, five) = (ReductionSolver::<4>::new(), ReductionSolver::<5>::new());
+    println!("Built the reduction tables in {:.2}ms", now.elapsed().as_secs_f64() * 1000.0);
+    test_n_cube(|cube| four.solve(cube), 60);
+    test_n_cube(|cube| five.solve(cube), 80);
+}
+
+fn test_n_cube<const N: usize>(solve: impl Fn(&NCube<N>) -> Option<Vec<NCubeAction<N>>>, scramble_length: u32) {
+    let mut total_length = 0;
+    let mut total_time = 0.0;
+    let scrambles = 100;
+    for test_case in Generator::<StdRng>::new(24).n_cube_scrambles::<N>(scramble_length..=scramble_length, scrambles) {
+        let cube = test_case.get_start_state();
+        let now = Instant::now();
+        let solution = solve(cube).expect("every scrambled cube has a solution");
+        let time = now.elapsed().as_secs_f64() * 1000.0;
 
+        let solved = solution.iter().fold(cube.clone(), |cube, action| cube.perform_action(action)).is_goal_state();
+        let result = if solved { "PASS".green() } else { "FAIL".red() };
+        println!("{} {}x{}x{} in {} moves in {:.2}ms", result, N, N, N, solution.len(), time);
+        total_length += solution.len();
+        total_time += time;
+    }
+    println!("Average {:.2} moves in {:.2}ms", total_length as f64 / scrambles as f64, total_time / scrambles as f64);
+}
+
+/// The experiments behind report.txt, run with `puzzle_solver experiment <name>`
+const EXPERIMENTS: [(&str, fn()); 14] = [
+    ("silly-puzzle", test_silly_puzzle),
+    ("silly-puzzle-suite", test_silly_puzzle_suite),
+    ("fifteen-puzzle", test_fifteen_puzzle),
+    ("fifteen-puzzle-pattern-database", test_fifteen_puzzle_pattern_database),
+    ("fifteen-puzzle-parallel", test_fifteen_puzzle_parallel),
+    ("fifteen-puzzle-memory-bounded", test_fifteen_puzzle_memory_bounded),
+    ("fifteen-puzzle-suboptimal", test_fifteen_puzzle_suboptimal),
+    ("fifteen-puzzle-local", test_fifteen_puzzle_local),
+    ("sliding-puzzle", test_sliding_puzzle),
+    ("rubiks-cube", test_rubiks_cube),
+    ("rubiks-cube-suite", test_rubiks_cube_suite),
+    ("rubiks-cube-two-phase", test_rubiks_cube_two_phase),
+    ("pocket-cube", test_pocket_cube),
+    ("reduction", test_reduction),
+];
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let result = match cli::parse_arguments(&args) {
+        Ok(Command::Solve(options)) => cli::solve(&options),
+        Ok(Command::Benchmark(options)) => cli::benchmark(&options),
+        Ok(Command::Generate(options)) => cli::generate(&options),
+        Ok(Command::List(puzzle)) => cli::list(puzzle.as_deref()),
+        Ok(Command::Experiment(Some(name))) => match EXPERIMENTS.iter().find(|(experiment, _)| *experiment == name) {
+            Some((_, experiment)) => {
+                experiment();
+                Ok(())
+            }
+            None => Err(format!("unknown experiment {}, expected one of {}", name, EXPERIMENTS.map(|(experiment, _)| experiment).join(", "))),
+        },
+        Ok(Command::Experiment(None)) => {
+            for (experiment, _) in EXPERIMENTS {
+                println!("{}", experiment);
+            }
+            Ok(())
+        }
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, cli::USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
 }
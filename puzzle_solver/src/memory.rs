@@ -0,0 +1,71 @@
+// Author: Harper Davis
+use std::{alloc::{GlobalAlloc, Layout, System}, sync::atomic::{AtomicUsize, Ordering}};
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, but counting how many bytes are allocated so benchmarks can see how much memory a search used.
+/// Every thread shares the counts, so the parallel searches are measured as a whole
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+
+    fn allocated(size: usize) {
+        let allocated = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+    }
+
+    fn deallocated(size: usize) {
+        ALLOCATED.fetch_sub(size, Ordering::Relaxed);
+    }
+
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = unsafe { System.alloc(layout) };
+        if !pointer.is_null() {
+            TrackingAllocator::allocated(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let pointer = unsafe { System.alloc_zeroed(layout) };
+        if !pointer.is_null() {
+            TrackingAllocator::allocated(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(pointer, layout) };
+        TrackingAllocator::deallocated(layout.size());
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = unsafe { System.realloc(pointer, layout, new_size) };
+        if !new_pointer.is_null() {
+            TrackingAllocator::deallocated(layout.size());
+            TrackingAllocator::allocated(new_size);
+        }
+        new_pointer
+    }
+
+}
+
+/// Bytes allocated right now
+pub fn get_allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// The most bytes that were allocated at once since the last reset
+pub fn get_peak() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+/// Starts measuring the peak again from what is allocated now
+pub fn reset_peak() {
+    PEAK.store(get_allocated(), Ordering::Relaxed);
+}
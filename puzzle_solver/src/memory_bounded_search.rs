@@ -0,0 +1,445 @@
+// Author: Harper Davis
+
+use std::{cmp::Reverse, collections::BTreeSet, fmt, marker::PhantomData, sync::Arc};
+
+use crate::{state::State, action::Action, frontier::{PriorityQueueFrontier, StackFrontier}, node::Node, statistics::SearchStatistics, limits::{SearchLimits, Limit}, search::{Search, SearchOutcome, Solution}, heuristic::{Heuristic, StateHeuristic}};
+
+/// How many nodes SimplifiedMemoryBoundedAStarSearch keeps when it isn't given a budget, either through
+/// `with_memory` or the search limits
+const DEFAULT_MAX_NODES: usize = 1_000_000;
+
+/// Korf's recursive best first search. It goes depth first down whichever child has the lowest evaluation, but
+/// gives up on it as soon as it gets worse than the best alternative anywhere above it, and remembers how good the
+/// abandoned subtree turned out to be (backed up into the child's evaluation) so it knows when it's worth coming back.
+/// Only the children of the nodes on the current path are ever kept, so memory only grows with the depth
+pub struct RecursiveBestFirstSearch<S: State<Action = A>, A: Action, H: Heuristic<S> = StateHeuristic> {
+    heuristic: H,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> RecursiveBestFirstSearch<S, A, H> {
+
+    pub fn with_heuristic(heuristic: H) -> RecursiveBestFirstSearch<S, A, H> {
+        RecursiveBestFirstSearch { heuristic, statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData }
+    }
+
+    /// Searches below `node` (whose backed up evaluation is `evaluation`) for a goal without going over `evaluation_limit`.
+    /// `stored` is how many nodes the levels above are holding on to
+    fn search_below(&mut self, node: Arc<Node<S, A>>, evaluation: f64, evaluation_limit: f64, limits: &SearchLimits, stored: usize) -> Recursion<S, A> {
+        if node.get_state().is_goal_state() {
+            return Recursion::Solved(node.as_ref().clone());
+        }
+        if let Some(limit) = limits.check(&self.statistics, stored) {
+            return Recursion::LimitReached(limit);
+        }
+
+        self.statistics.expanded();
+        let mut children = Vec::new();
+        for action in node.get_possible_actions() {
+            let next_node = Node::<S, A>::next_node(node.clone(), &action, &self.heuristic);
+            self.statistics.generated();
+
+            if node.get_parent_node().is_some_and(|parent_node| parent_node.get_state() == next_node.get_state()) {
+                self.statistics.pruned();
+                continue;
+            }
+
+            // a child can't be any better than what was already learned about its parent
+            children.push((f64::max(next_node.eval(), evaluation), Arc::new(next_node)));
+        }
+        if children.is_empty() {
+            return Recursion::Failed(f64::INFINITY);
+        }
+        let stored = stored + children.len();
+        self.statistics.frontier_size(stored);
+
+        loop {
+            children.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+            let (best_evaluation, best_node) = children[0].clone();
+            // at the start the limit is infinite too, and nothing under an infinite evaluation can ever get better
+            if best_evaluation > evaluation_limit || best_evaluation == f64::INFINITY {
+                return Recursion::Failed(best_evaluation);
+            }
+            let alternative = children.get(1).map_or(f64::INFINITY, |(evaluation, _)| *evaluation);
+
+            match self.search_below(best_node, best_evaluation, f64::min(evaluation_limit, alternative), limits, stored) {
+                Recursion::Failed(backed_up) => children[0].0 = backed_up,
+                finished => return finished,
+            }
+        }
+    }
+
+}
+
+/// How a call to RecursiveBestFirstSearch::search_below went. Failed has the lowest evaluation that was over the limit
+enum Recursion<S: State<Action = A>, A: Action> {
+    Solved(Node<S, A>),
+    Failed(f64),
+    LimitReached(Limit),
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> fmt::Display for RecursiveBestFirstSearch<S, A, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RecursiveBestFirstSearch")
+    }
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S> + Default> Search<S, A, StackFrontier<S, A>> for RecursiveBestFirstSearch<S, A, H> {
+
+    /// The limit is ignored, since the memory only grows with the depth anyway. Use the node or time limits to stop it
+    fn new(_limit: f64) -> RecursiveBestFirstSearch<S, A, H> {
+        RecursiveBestFirstSearch::with_heuristic(H::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+        self.statistics.iteration();
+
+        let start_node = Node::new(&start_state, 0.0, 0, &self.heuristic);
+        let evaluation = start_node.eval();
+        match self.search_below(Arc::new(start_node), evaluation, f64::INFINITY, limits, 1) {
+            Recursion::Solved(node) => SearchOutcome::Solved(Solution::from_node(start_state, &node, self.statistics.clone())),
+            Recursion::Failed(_) => SearchOutcome::Exhausted(self.statistics.clone()),
+            Recursion::LimitReached(limit) => SearchOutcome::LimitReached(limit, self.statistics.clone()),
+        }
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        unreachable!()
+    }
+
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &self.heuristic
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+///////////////////////////////////////////////////////
+
+/// Russell's simplified memory bounded A*. It works like A*, except that children are made one at a time and
+/// it never holds more than `max_nodes` nodes. When it runs out, the worst leaf (highest evaluation, shallowest
+/// first) is forgotten and its parent remembers its evaluation, so it gets made again only once everything else
+/// looks worse. A parent whose children have all been made takes the lowest of their evaluations. It finds the
+/// cheapest solution as long as the path to it fits in memory, a node that is too deep to be on one is given up on
+pub struct SimplifiedMemoryBoundedAStarSearch<S: State<Action = A>, A: Action, H: Heuristic<S> = StateHeuristic> {
+    max_nodes: usize,
+    heuristic: H,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> SimplifiedMemoryBoundedAStarSearch<S, A, H> {
+
+    pub fn with_memory(max_nodes: usize, heuristic: H) -> SimplifiedMemoryBoundedAStarSearch<S, A, H> {
+        SimplifiedMemoryBoundedAStarSearch { max_nodes: usize::max(max_nodes, 2), heuristic, statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData }
+    }
+
+    pub fn get_max_nodes(&self) -> usize {
+        self.max_nodes
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> fmt::Display for SimplifiedMemoryBoundedAStarSearch<S, A, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SimplifiedMemoryBoundedAStarSearch")
+    }
+}
+
+/// A node SimplifiedMemoryBoundedAStarSearch is holding on to
+struct Remembered<S: State<Action = A>, A: Action> {
+    node: Arc<Node<S, A>>,
+    evaluation: f64,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// The actions whose children haven't been made yet, None until the node is first expanded
+    unexplored: Option<Vec<A>>,
+    forgotten: Vec<(A, f64)>,
+}
+
+impl<S: State<Action = A>, A: Action> Remembered<S, A> {
+
+    fn has_children_left(&self) -> bool {
+        self.unexplored.as_ref().is_none_or(|unexplored| !unexplored.is_empty()) || !self.forgotten.is_empty()
+    }
+
+}
+
+/// Evaluation (as bits, which sort the same way for anything that isn't negative), depth the other way around, then
+/// an index. The lowest is the best node to work on next and the highest the worst one to forget
+type MemoryKey = (u64, Reverse<u32>, usize);
+
+/// Every node being remembered, plus the ones that still have children to make (`open`) and the ones with
+/// no children in memory (`leaves`). Removing a node and putting it back keeps both up to date after a change
+struct Memory<S: State<Action = A>, A: Action> {
+    nodes: Vec<Option<Remembered<S, A>>>,
+    free: Vec<usize>,
+    open: BTreeSet<MemoryKey>,
+    leaves: BTreeSet<MemoryKey>,
+    used: usize,
+}
+
+impl<S: State<Action = A>, A: Action> Memory<S, A> {
+
+    fn new() -> Memory<S, A> {
+        Memory { nodes: Vec::new(), free: Vec::new(), open: BTreeSet::new(), leaves: BTreeSet::new(), used: 0 }
+    }
+
+    fn get(&self, index: usize) -> &Remembered<S, A> {
+        self.nodes[index].as_ref().unwrap()
+    }
+
+    fn get_mut(&mut self, index: usize) -> &mut Remembered<S, A> {
+        self.nodes[index].as_mut().unwrap()
+    }
+
+    fn key(&self, index: usize) -> MemoryKey {
+        let remembered = self.get(index);
+        (remembered.evaluation.to_bits(), Reverse(remembered.node.get_depth()), index)
+    }
+
+    fn unlist(&mut self, index: usize) {
+        let key = self.key(index);
+        self.open.remove(&key);
+        self.leaves.remove(&key);
+    }
+
+    fn list(&mut self, index: usize) {
+        let key = self.key(index);
+        let remembered = self.get(index);
+        let is_open = remembered.has_children_left();
+        // the start is never forgotten
+        let is_leaf = remembered.children.is_empty() && remembered.parent.is_some();
+        if is_open {
+            self.open.insert(key);
+        }
+        if is_leaf {
+            self.leaves.insert(key);
+        }
+    }
+
+    fn insert(&mut self, remembered: Remembered<S, A>) -> usize {
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(remembered);
+                index
+            }
+            None => {
+                self.nodes.push(Some(remembered));
+                self.nodes.len() - 1
+            }
+        };
+        self.used += 1;
+        self.list(index);
+        index
+    }
+
+    /// Drops the worst leaf, leaving its action and evaluation with its parent
+    fn forget_worst(&mut self) {
+        let Some(&(_, _, worst)) = self.leaves.last() else {
+            return;
+        };
+        self.unlist(worst);
+        let forgotten = self.nodes[worst].take().unwrap();
+        self.free.push(worst);
+        self.used -= 1;
+
+        let parent = forgotten.parent.unwrap();
+        self.unlist(parent);
+        let parent_remembered = self.get_mut(parent);
+        parent_remembered.children.retain(|&child| child != worst);
+        parent_remembered.forgotten.push((forgotten.node.get_action().unwrap().clone(), forgotten.evaluation));
+        self.list(parent);
+    }
+
+    /// Once every child of a node has been made at least once, its evaluation is the lowest of theirs,
+    /// and if that changes it, its parent might change too
+    fn back_up(&mut self, mut index: usize) {
+        loop {
+            let remembered = self.get(index);
+            if remembered.unexplored.as_ref().is_none_or(|unexplored| !unexplored.is_empty()) {
+                return;
+            }
+            let evaluation = remembered.children.iter().map(|&child| self.get(child).evaluation)
+                .chain(remembered.forgotten.iter().map(|(_, evaluation)| *evaluation))
+                .fold(f64::INFINITY, f64::min);
+            if evaluation == remembered.evaluation {
+                return;
+            }
+
+            self.unlist(index);
+            self.get_mut(index).evaluation = evaluation;
+            self.list(index);
+            match self.get(index).parent {
+                Some(parent) => index = parent,
+                None => return,
+            }
+        }
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S> + Default> Search<S, A, PriorityQueueFrontier<S, A>> for SimplifiedMemoryBoundedAStarSearch<S, A, H> {
+
+    fn new(_limit: f64) -> SimplifiedMemoryBoundedAStarSearch<S, A, H> {
+        SimplifiedMemoryBoundedAStarSearch::with_memory(DEFAULT_MAX_NODES, H::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+        self.statistics.iteration();
+        let max_nodes = limits.get_max_memory().map_or(self.max_nodes, |max_memory| usize::max(max_memory, 2));
+
+        let mut memory = Memory::new();
+        let start_node = Node::new(&start_state, 0.0, 0, &self.heuristic);
+        let evaluation = start_node.eval();
+        memory.insert(Remembered { node: Arc::new(start_node), evaluation, parent: None, children: Vec::new(), unexplored: None, forgotten: Vec::new() });
+        self.statistics.frontier_size(memory.used);
+        let mut too_deep = false;
+
+        while let Some(&(_, _, index)) = memory.open.first() {
+            if let Some(limit) = limits.check(&self.statistics, memory.used) {
+                return SearchOutcome::LimitReached(limit, self.statistics.clone());
+            }
+
+            let remembered = memory.get(index);
+            if remembered.evaluation == f64::INFINITY {
+                // everything left is a dead end, or only too deep to fit
+                break;
+            }
+            if remembered.node.get_state().is_goal_state() {
+                return SearchOutcome::Solved(Solution::from_node(start_state, &remembered.node, self.statistics.clone()));
+            }
+
+            memory.unlist(index);
+            let remembered = memory.get_mut(index);
+            if remembered.unexplored.is_none() {
+                self.statistics.expanded();
+                let mut actions = remembered.node.get_possible_actions();
+                actions.reverse();
+                remembered.unexplored = Some(actions);
+            }
+            let (action, forgotten_evaluation) = match remembered.unexplored.as_mut().unwrap().pop() {
+                Some(action) => (action, 0.0),
+                None => {
+                    let (lowest, _) = remembered.forgotten.iter().enumerate()
+                        .min_by(|(_, (_, a)), (_, (_, b))| a.total_cmp(b)).unwrap();
+                    remembered.forgotten.swap_remove(lowest)
+                }
+            };
+
+            let parent_node = remembered.node.clone();
+            let next_node = Node::<S, A>::next_node(parent_node.clone(), &action, &self.heuristic);
+            self.statistics.generated();
+
+            if parent_node.get_parent_node().is_some_and(|grandparent_node| grandparent_node.get_state() == next_node.get_state()) {
+                self.statistics.pruned();
+                memory.list(index);
+                memory.back_up(index);
+                continue;
+            }
+
+            // a path through here needs a node for every step, so if the next one couldn't fit this can't be on one
+            let evaluation = if !next_node.get_state().is_goal_state() && next_node.get_depth() as usize >= max_nodes - 1 {
+                too_deep = true;
+                f64::INFINITY
+            } else {
+                f64::max(f64::max(next_node.eval(), forgotten_evaluation), remembered.evaluation)
+            };
+
+            let child = memory.insert(Remembered { node: Arc::new(next_node), evaluation, parent: Some(index), children: Vec::new(), unexplored: None, forgotten: Vec::new() });
+            memory.get_mut(index).children.push(child);
+            memory.list(index);
+            memory.back_up(index);
+
+            while memory.used > max_nodes {
+                memory.forget_worst();
+            }
+            self.statistics.frontier_size(memory.used);
+        }
+
+        if too_deep {
+            SearchOutcome::LimitReached(Limit::Memory, self.statistics.clone())
+        } else {
+            SearchOutcome::Exhausted(self.statistics.clone())
+        }
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        unreachable!()
+    }
+
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &self.heuristic
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{puzzles::fifteen_puzzle::{FifteenPuzzle, FifteenPuzzleAction}, suite::read_fifteen_puzzle_test_cases};
+
+    use super::*;
+
+    type Sma = SimplifiedMemoryBoundedAStarSearch<FifteenPuzzle, FifteenPuzzleAction>;
+
+    /// Says nothing but the goal can be reached, as if every other state were a dead end
+    #[derive(Default)]
+    struct DeadEnds;
+
+    impl Heuristic<FifteenPuzzle> for DeadEnds {
+        fn estimate(&self, state: &FifteenPuzzle) -> f64 {
+            if state.is_goal_state() { 0.0 } else { f64::INFINITY }
+        }
+    }
+
+    #[test]
+    fn recursive_best_first_gives_up_when_everything_is_a_dead_end() {
+        let start_state = FifteenPuzzle::default().perform_action(&FifteenPuzzleAction::new(1)).perform_action(&FifteenPuzzleAction::new(5));
+        let outcome = RecursiveBestFirstSearch::<FifteenPuzzle, FifteenPuzzleAction, DeadEnds>::new(f64::INFINITY).search(start_state);
+        assert!(matches!(outcome, SearchOutcome::Exhausted(_)));
+    }
+
+    #[test]
+    fn small_memory_budget_still_solves() {
+        let test_cases = read_fifteen_puzzle_test_cases("./tests/15_puzzle.txt").unwrap();
+        for test_case in test_cases.iter().filter(|test_case| test_case.get_depth_limit() <= 16) {
+            let limits = SearchLimits::none().with_max_memory(100);
+            match Sma::new(f64::INFINITY).search_with_limits(test_case.get_start_state().clone(), &limits) {
+                SearchOutcome::Solved(solution) => {
+                    assert_eq!(solution.path_length(), test_case.get_depth_limit());
+                    assert!(solution.get_statistics().get_max_frontier_size() <= 100);
+                },
+                _ => panic!("{} wasn't solved", test_case.get_start_state()),
+            }
+        }
+    }
+
+    #[test]
+    fn budget_smaller_than_the_path_runs_out_of_memory() {
+        let test_cases = read_fifteen_puzzle_test_cases("./tests/15_puzzle.txt").unwrap();
+        let test_case = test_cases.iter().find(|test_case| test_case.get_depth_limit() >= 10).unwrap();
+        let limits = SearchLimits::none().with_max_memory(test_case.get_depth_limit() as usize);
+        match Sma::new(f64::INFINITY).search_with_limits(test_case.get_start_state().clone(), &limits) {
+            SearchOutcome::LimitReached(limit, _) => assert_eq!(limit, Limit::Memory),
+            _ => panic!("{} didn't run out of memory", test_case.get_start_state()),
+        }
+    }
+}
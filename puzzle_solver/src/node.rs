@@ -1,41 +1,42 @@
 // Author: Harper Davis
 
-use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::{state::State, action::Action};
+use crate::{state::State, action::Action, heuristic::Heuristic};
 
 #[derive(Debug, Clone)]
 pub struct Node<S: State<Action = A>, A: Action> {
     state: S,
     action: Option<A>,
-    parent_node: Option<Rc<Self>>,
+    parent_node: Option<Arc<Self>>,
     path_cost: f64,
+    heuristic: f64,
     depth: u32,
 }
 
-impl <'a, S: State<Action = A>, A: Action> PartialEq for Node<S, A> {
+impl <S: State<Action = A>, A: Action> PartialEq for Node<S, A> {
     fn eq(&self, other: &Self) -> bool {
         self.state == other.state
     }
 }
 
-impl <'a, S: State<Action = A>, A: Action> Eq for Node<S, A> {}
+impl <S: State<Action = A>, A: Action> Eq for Node<S, A> {}
 
-impl <'a, S: State<Action = A>, A: Action> PartialOrd for Node<S, A> {
+impl <S: State<Action = A>, A: Action> PartialOrd for Node<S, A> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl <'a, S: State<Action = A>, A: Action> Ord for Node<S, A> {
+impl <S: State<Action = A>, A: Action> Ord for Node<S, A> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.eval().partial_cmp(&other.eval()).unwrap_or(std::cmp::Ordering::Equal).reverse()
     }
 }
 
-impl <'a, S: State<Action = A>, A: Action> Node<S, A> {
-    pub fn new(state: &S, path_cost: f64, depth: u32) -> Node<S, A> {
-        Node { state: state.clone(), parent_node: None, action: None, path_cost, depth }
+impl <S: State<Action = A>, A: Action> Node<S, A> {
+    pub fn new<H: Heuristic<S> + ?Sized>(state: &S, path_cost: f64, depth: u32, heuristic: &H) -> Node<S, A> {
+        Node { state: state.clone(), parent_node: None, action: None, path_cost, heuristic: heuristic.estimate(state), depth }
     }
 
     pub fn get_state(&self) -> &S {
@@ -51,26 +52,32 @@ impl <'a, S: State<Action = A>, A: Action> Node<S, A> {
     }
 
     pub fn get_possible_actions(&self) -> Vec<A> {
-        self.state.list_actions()
+        self.state.list_actions_after(self.action.as_ref())
     }
 
     pub fn get_path_cost(&self) -> f64 {
         self.path_cost
     }
 
+    /// The heuristic's estimate for this node's state, worked out once when the node was made
+    pub fn get_heuristic(&self) -> f64 {
+        self.heuristic
+    }
+
     pub fn get_depth(&self) -> u32 {
         self.depth
     }
 
-    pub fn next_node(parent_node: Rc<Self>, action: &A) -> Self {
+    pub fn next_node<H: Heuristic<S> + ?Sized>(parent_node: Arc<Self>, action: &A, heuristic: &H) -> Self {
         let new_cost = parent_node.path_cost + action.get_cost();
         let new_depth = parent_node.depth + 1;
         let new_state = parent_node.state.perform_action(action);
-        Node { state: new_state, action: Some(action.clone()), parent_node: Some(parent_node), path_cost: new_cost, depth: new_depth }
+        let new_heuristic = heuristic.estimate(&new_state);
+        Node { state: new_state, action: Some(action.clone()), parent_node: Some(parent_node), path_cost: new_cost, heuristic: new_heuristic, depth: new_depth }
     }
 
     pub fn eval(&self) -> f64 {
-        self.path_cost + self.state.heuristic()
+        self.path_cost + self.heuristic
     }
     
 }
\ No newline at end of file
@@ -0,0 +1,466 @@
+// Author: Harper Davis
+
+use std::{collections::{HashMap, hash_map::DefaultHasher}, fmt, hash::{Hash, Hasher}, marker::PhantomData, sync::{Arc, Mutex, atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering}, mpsc}, thread, time::Duration};
+
+use crate::{state::State, action::Action, frontier::{Frontier, PriorityQueueFrontier, StackFrontier}, node::Node, statistics::SearchStatistics, limits::{SearchLimits, Limit}, search::{Search, SearchOutcome, Solution, EvaluationLimitedSearch}, heuristic::{Heuristic, StateHeuristic}};
+
+/// How many threads to use when none are asked for
+fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+///////////////////////////////////////////////////////
+
+/// Hash distributed A* (Kishimoto, Fukunaga and Botea). Every state belongs to one thread, picked by its hash,
+/// and only that thread ever expands it or keeps track of its best path cost, so no locking is needed for
+/// duplicate detection. Nodes generated for a state that belongs to another thread are sent over to it.
+/// Threads keep going after the first goal until every remaining node is known to be no better, which
+/// keeps the solution length the same from run to run even though the path itself can differ
+pub struct HashDistributedAStarSearch<S: State<Action = A>, A: Action, H: Heuristic<S> = StateHeuristic> {
+    threads: usize,
+    heuristic: H,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> HashDistributedAStarSearch<S, A, H> {
+
+    pub fn with_threads(threads: usize, heuristic: H) -> HashDistributedAStarSearch<S, A, H> {
+        HashDistributedAStarSearch { threads: usize::max(threads, 1), heuristic, statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData }
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> fmt::Display for HashDistributedAStarSearch<S, A, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HashDistributedAStarSearch")
+    }
+}
+
+/// What the threads of a HashDistributedAStarSearch share. `outstanding` counts the nodes that are in a
+/// frontier or on their way to one, and a node is only taken off the count after its children have been
+/// added on, so it reaching 0 means all the work is done
+struct SharedSearch<S: State<Action = A>, A: Action> {
+    outstanding: AtomicUsize,
+    generated: AtomicU64,
+    best_cost: AtomicU64,
+    best_node: Mutex<Option<Node<S, A>>>,
+    stopped: AtomicBool,
+    limit: Mutex<Option<Limit>>,
+}
+
+impl<S: State<Action = A>, A: Action> SharedSearch<S, A> {
+
+    fn get_best_cost(&self) -> f64 {
+        f64::from_bits(self.best_cost.load(Ordering::Acquire))
+    }
+
+    fn offer_solution(&self, node: &Node<S, A>) {
+        let mut best_node = self.best_node.lock().unwrap();
+        if node.get_path_cost() < self.get_best_cost() {
+            self.best_cost.store(node.get_path_cost().to_bits(), Ordering::Release);
+            *best_node = Some(node.clone());
+        }
+    }
+
+    fn stop(&self, limit: Limit) {
+        self.limit.lock().unwrap().get_or_insert(limit);
+        self.stopped.store(true, Ordering::Release);
+    }
+
+}
+
+fn owner<S: Hash>(state: &S, threads: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    (hasher.finish() % threads as u64) as usize
+}
+
+/// One thread of a HashDistributedAStarSearch, looping until every thread runs out of work or the search is stopped
+fn distributed_worker<S, A, H>(id: usize, receiver: mpsc::Receiver<Node<S, A>>, senders: Vec<mpsc::Sender<Node<S, A>>>, shared: &SharedSearch<S, A>, heuristic: &H, limits: &SearchLimits) -> SearchStatistics
+where
+    S: State<Action = A> + Send + Sync,
+    A: Action + Send + Sync,
+    H: Heuristic<S>,
+{
+    let mut statistics = SearchStatistics::new();
+    let mut frontier = PriorityQueueFrontier::<S, A>::new();
+    let mut best_costs: HashMap<S, f64> = HashMap::new();
+
+    // takes the count of a node that's being dropped instead of expanded
+    let discard = || { shared.outstanding.fetch_sub(1, Ordering::AcqRel); };
+
+    while !shared.stopped.load(Ordering::Acquire) {
+        // only wait around for other threads when there's nothing to do here
+        let waited = if frontier.is_empty() {
+            if shared.outstanding.load(Ordering::Acquire) == 0 {
+                break;
+            }
+            receiver.recv_timeout(Duration::from_micros(100)).ok()
+        } else {
+            None
+        };
+        for node in waited.into_iter().chain(receiver.try_iter()) {
+            if best_costs.get(node.get_state()).is_some_and(|&cost| cost <= node.get_path_cost()) {
+                statistics.pruned();
+                discard();
+                continue;
+            }
+            best_costs.insert(node.get_state().clone(), node.get_path_cost());
+            frontier.insert(node);
+        }
+        statistics.frontier_size(frontier.size());
+
+        let Some(node) = frontier.pop() else {
+            continue;
+        };
+
+        if let Some(limit) = limits.check_counts(shared.generated.load(Ordering::Relaxed), shared.outstanding.load(Ordering::Relaxed)) {
+            shared.stop(limit);
+            break;
+        }
+
+        // stale, or can't lead to anything cheaper than the best solution so far
+        if best_costs.get(node.get_state()).is_some_and(|&cost| cost < node.get_path_cost()) || node.eval() >= shared.get_best_cost() {
+            discard();
+            continue;
+        }
+
+        if node.get_state().is_goal_state() {
+            shared.offer_solution(&node);
+            discard();
+            continue;
+        }
+
+        statistics.expanded();
+        let shared_node = Arc::new(node);
+        let actions = shared_node.get_possible_actions();
+        shared.generated.fetch_add(actions.len() as u64, Ordering::Relaxed);
+        for action in actions {
+            let parent_node = shared_node.get_parent_node();
+            let next_node = Node::<S, A>::next_node(shared_node.clone(), &action, heuristic);
+            statistics.generated();
+
+            if parent_node.is_some_and(|parent_node| parent_node.get_state() == next_node.get_state()) || next_node.eval() >= shared.get_best_cost() {
+                statistics.pruned();
+                continue;
+            }
+
+            let next_owner = owner(next_node.get_state(), senders.len());
+            if next_owner == id {
+                if best_costs.get(next_node.get_state()).is_some_and(|&cost| cost <= next_node.get_path_cost()) {
+                    statistics.pruned();
+                    continue;
+                }
+                best_costs.insert(next_node.get_state().clone(), next_node.get_path_cost());
+                shared.outstanding.fetch_add(1, Ordering::AcqRel);
+                frontier.insert(next_node);
+            } else {
+                shared.outstanding.fetch_add(1, Ordering::AcqRel);
+                senders[next_owner].send(next_node).unwrap();
+            }
+        }
+        discard();
+    }
+
+    statistics
+}
+
+impl<S, A, H> Search<S, A, PriorityQueueFrontier<S, A>> for HashDistributedAStarSearch<S, A, H>
+where
+    S: State<Action = A> + Send + Sync,
+    A: Action + Send + Sync,
+    H: Heuristic<S> + Default + Sync,
+{
+
+    fn new(_limit: f64) -> HashDistributedAStarSearch<S, A, H> {
+        HashDistributedAStarSearch::with_threads(default_threads(), H::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+        self.statistics.iteration();
+
+        let shared = SharedSearch {
+            outstanding: AtomicUsize::new(1),
+            generated: AtomicU64::new(0),
+            best_cost: AtomicU64::new(f64::INFINITY.to_bits()),
+            best_node: Mutex::new(None),
+            stopped: AtomicBool::new(false),
+            limit: Mutex::new(None),
+        };
+
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..self.threads).map(|_| mpsc::channel()).unzip();
+        senders[owner(&start_state, self.threads)].send(Node::new(&start_state, 0.0, 0, &self.heuristic)).unwrap();
+
+        let heuristic = &self.heuristic;
+        let shared = &shared;
+        let thread_statistics = thread::scope(|scope| {
+            let workers = receivers.into_iter().enumerate().map(|(id, receiver)| {
+                let senders = senders.clone();
+                scope.spawn(move || distributed_worker(id, receiver, senders, shared, heuristic, limits))
+            }).collect::<Vec<_>>();
+            workers.into_iter().map(|worker| worker.join().unwrap()).collect::<Vec<SearchStatistics>>()
+        });
+        for statistics in &thread_statistics {
+            self.statistics.merge_counts(statistics);
+        }
+
+        if let Some(limit) = *shared.limit.lock().unwrap() {
+            return SearchOutcome::LimitReached(limit, self.statistics.clone());
+        }
+        let best_node = shared.best_node.lock().unwrap().take();
+        match best_node {
+            Some(node) => SearchOutcome::Solved(Solution::from_node(start_state, &node, self.statistics.clone())),
+            None => SearchOutcome::Exhausted(self.statistics.clone()),
+        }
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        unreachable!()
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+///////////////////////////////////////////////////////
+
+/// IDA* with the top of the tree split up between threads. The first few levels are expanded breadth first
+/// until there are plenty of subtrees for every thread, and then each iteration hands the subtrees out to
+/// the threads as they finish their last one. A solution found under the current evaluation limit is as
+/// short as any, so the first one to turn up cancels the rest of the iteration
+pub struct ParallelIterativeDeepeningAStarSearch<S: State<Action = A>, A: Action, H: Heuristic<S> = StateHeuristic> {
+    threads: usize,
+    heuristic: H,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+}
+
+/// Roughly how many subtrees each thread should get, more evens out the work but repeats more of the top of the tree
+const SUBTREES_PER_THREAD: usize = 16;
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> ParallelIterativeDeepeningAStarSearch<S, A, H> {
+
+    pub fn with_threads(threads: usize, heuristic: H) -> ParallelIterativeDeepeningAStarSearch<S, A, H> {
+        ParallelIterativeDeepeningAStarSearch { threads: usize::max(threads, 1), heuristic, statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData }
+    }
+
+    /// Expands whole levels from the start until there are enough leaves to go around, or until the
+    /// levels stop getting any wider. Goals are never expanded, so one found this early ends up as a subtree of its own
+    fn split(&mut self, start_state: &S) -> Vec<Node<S, A>> {
+        let mut leaves = vec![Node::new(start_state, 0.0, 0, &self.heuristic)];
+        while leaves.len() < self.threads * SUBTREES_PER_THREAD {
+            let width = leaves.len();
+            let mut next_leaves = Vec::new();
+            for node in leaves {
+                if node.get_state().is_goal_state() {
+                    next_leaves.push(node);
+                    continue;
+                }
+                self.statistics.expanded();
+                let shared_node = Arc::new(node);
+                for action in shared_node.get_possible_actions() {
+                    let parent_node = shared_node.get_parent_node();
+                    let next_node = Node::<S, A>::next_node(shared_node.clone(), &action, &self.heuristic);
+                    self.statistics.generated();
+                    if parent_node.is_some_and(|parent_node| parent_node.get_state() == next_node.get_state()) {
+                        self.statistics.pruned();
+                        continue;
+                    }
+                    next_leaves.push(next_node);
+                }
+            }
+            leaves = next_leaves;
+            if leaves.len() <= width {
+                break;
+            }
+        }
+        leaves
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> fmt::Display for ParallelIterativeDeepeningAStarSearch<S, A, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ParallelIterativeDeepeningAStarSearch")
+    }
+}
+
+/// How one subtree went in one iteration
+enum SubtreeOutcome<S: State<Action = A>, A: Action> {
+    Solved(Vec<A>, S),
+    Exhausted(f64),
+    LimitReached(Limit),
+}
+
+impl<S, A, H> Search<S, A, StackFrontier<S, A>> for ParallelIterativeDeepeningAStarSearch<S, A, H>
+where
+    S: State<Action = A> + Send + Sync,
+    A: Action + Send + Sync,
+    H: Heuristic<S> + Default + Clone + Send + Sync,
+{
+
+    fn new(_limit: f64) -> ParallelIterativeDeepeningAStarSearch<S, A, H> {
+        ParallelIterativeDeepeningAStarSearch::with_threads(default_threads(), H::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+
+        let leaves = self.split(&start_state);
+        let mut evaluation_limit = self.heuristic.estimate(&start_state);
+        loop {
+            self.statistics.iteration();
+            let found = Arc::new(AtomicBool::new(false));
+            let next_leaf = AtomicUsize::new(0);
+            // the node budget is shared by counting up every finished subtree, so a running one can still go over a bit
+            let generated = AtomicU64::new(self.statistics.get_nodes_generated());
+
+            let (leaves, heuristic, next_leaf, found, generated) = (&leaves, &self.heuristic, &next_leaf, &found, &generated);
+            let results = thread::scope(|scope| {
+                let workers = (0..self.threads).map(|_| {
+                    scope.spawn(move || {
+                        let mut statistics = SearchStatistics::new();
+                        let mut outcomes = Vec::new();
+                        while let Some(leaf) = leaves.get(next_leaf.fetch_add(1, Ordering::Relaxed)) {
+                            if found.load(Ordering::Relaxed) {
+                                break;
+                            }
+                            if leaf.eval() > evaluation_limit {
+                                outcomes.push((leaf, SubtreeOutcome::Exhausted(leaf.eval())));
+                                continue;
+                            }
+
+                            let mut search = EvaluationLimitedSearch::<S, A, StackFrontier<S, A>, H>::with_heuristic(evaluation_limit - leaf.get_path_cost(), heuristic.clone());
+                            let subtree_limits = limits.remaining_after(generated.load(Ordering::Relaxed)).with_cancel_flag(found.clone());
+                            let outcome = search.search_with_limits(leaf.get_state().clone(), &subtree_limits);
+                            generated.fetch_add(search.statistics().get_nodes_generated(), Ordering::Relaxed);
+                            statistics.merge_counts(search.statistics());
+                            let outcome = match outcome {
+                                SearchOutcome::Solved(solution) => {
+                                    found.store(true, Ordering::Relaxed);
+                                    SubtreeOutcome::Solved(solution.get_path().clone(), solution.get_final_state().clone())
+                                }
+                                SearchOutcome::Exhausted(_) => SubtreeOutcome::Exhausted(leaf.get_path_cost() + search.get_lowest_evaluation_above_limit()),
+                                SearchOutcome::LimitReached(limit, _) => SubtreeOutcome::LimitReached(limit),
+                            };
+                            outcomes.push((leaf, outcome));
+                        }
+                        (statistics, outcomes)
+                    })
+                }).collect::<Vec<_>>();
+                workers.into_iter().map(|worker| worker.join().unwrap()).collect::<Vec<_>>()
+            });
+
+            let mut solution = None;
+            let mut limit_reached = None;
+            let mut next_evaluation_limit = f64::INFINITY;
+            for (statistics, outcomes) in results {
+                self.statistics.merge_counts(&statistics);
+                for (leaf, outcome) in outcomes {
+                    match outcome {
+                        SubtreeOutcome::Solved(path, final_state) => {
+                            let mut full_path = Solution::from_node(start_state.clone(), leaf, SearchStatistics::new()).get_path().clone();
+                            full_path.extend(path);
+                            solution.get_or_insert((full_path, final_state));
+                        }
+                        SubtreeOutcome::Exhausted(lowest) => next_evaluation_limit = f64::min(next_evaluation_limit, lowest),
+                        // the flag that's set when another subtree finds a solution
+                        SubtreeOutcome::LimitReached(Limit::Cancelled) if found.load(Ordering::Relaxed) => {}
+                        SubtreeOutcome::LimitReached(limit) => limit_reached = Some(limit),
+                    }
+                }
+            }
+
+            if let Some((path, final_state)) = solution {
+                return SearchOutcome::Solved(Solution::new(start_state, final_state, path, self.statistics.clone()));
+            }
+            if let Some(limit) = limit_reached {
+                return SearchOutcome::LimitReached(limit, self.statistics.clone());
+            }
+            // nothing was pruned by the evaluation limit, so raising it would not find anything new
+            if next_evaluation_limit == f64::INFINITY {
+                return SearchOutcome::Exhausted(self.statistics.clone());
+            }
+            evaluation_limit = next_evaluation_limit;
+        }
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        unreachable!()
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+
+    use crate::{generator::Generator, search::AStarGraphSearch, puzzles::sliding_puzzle::{EightPuzzle, SlidingPuzzleAction}};
+
+    use super::*;
+
+    type HdaStar = HashDistributedAStarSearch<EightPuzzle, SlidingPuzzleAction>;
+    type ParallelIdaStar = ParallelIterativeDeepeningAStarSearch<EightPuzzle, SlidingPuzzleAction>;
+
+    fn start_states(moves: std::ops::RangeInclusive<u32>, count: usize) -> Vec<EightPuzzle> {
+        Generator::<StdRng>::new(11).sliding_puzzle_walks::<3, 3>(moves, count).into_iter()
+            .map(|test_case| test_case.get_start_state().clone()).collect()
+    }
+
+    fn optimal_cost(start_state: &EightPuzzle) -> f64 {
+        match AStarGraphSearch::<EightPuzzle, SlidingPuzzleAction>::new(f64::INFINITY).search(start_state.clone()) {
+            SearchOutcome::Solved(solution) => solution.path_cost(),
+            _ => panic!("A* didn't solve {}", start_state),
+        }
+    }
+
+    /// A real path that costs as little as A*'s, however many threads there are
+    fn assert_optimal(outcome: SearchOutcome<EightPuzzle, SlidingPuzzleAction>, start_state: &EightPuzzle) {
+        match outcome {
+            SearchOutcome::Solved(solution) => assert_eq!(solution.verify(), Ok(optimal_cost(start_state)), "from {}", start_state),
+            _ => panic!("no solution from {}", start_state),
+        }
+    }
+
+    #[test]
+    fn as_cheap_as_a_star() {
+        for start_state in start_states(0..=24, 3) {
+            for threads in [1, 2, 4] {
+                assert_optimal(HdaStar::with_threads(threads, StateHeuristic).search(start_state.clone()), &start_state);
+                assert_optimal(ParallelIdaStar::with_threads(threads, StateHeuristic).search(start_state.clone()), &start_state);
+            }
+        }
+    }
+
+    #[test]
+    fn stops_at_the_node_limit() {
+        let limits = SearchLimits::none().with_max_nodes(100);
+        // far enough away that none of them could be solved in time
+        let far = start_states(28..=30, 4).into_iter().filter(|start_state| optimal_cost(start_state) >= 20.0).collect::<Vec<EightPuzzle>>();
+        assert!(!far.is_empty());
+        for start_state in far {
+            for threads in [1, 4] {
+                let outcome = HdaStar::with_threads(threads, StateHeuristic).search_with_limits(start_state.clone(), &limits);
+                assert!(matches!(outcome, SearchOutcome::LimitReached(Limit::Nodes, _)), "hdastar from {}", start_state);
+                let outcome = ParallelIdaStar::with_threads(threads, StateHeuristic).search_with_limits(start_state.clone(), &limits);
+                assert!(matches!(outcome, SearchOutcome::LimitReached(Limit::Nodes, _)), "parallel idastar from {}", start_state);
+            }
+        }
+    }
+}
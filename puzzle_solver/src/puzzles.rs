@@ -2,5 +2,16 @@
 
 pub mod silly_puzzle;
 pub mod fifteen_puzzle;
-// pub mod fifteen_puzzle_fast; // Unused
-pub mod rubiks_cube;
\ No newline at end of file
+pub mod fifteen_puzzle_heuristics;
+pub mod sliding_puzzle;
+pub mod pattern_database;
+pub mod fifteen_puzzle_fast;
+pub mod rubiks_cube;
+pub mod cube_notation;
+pub mod facelets;
+pub mod cubie_cube;
+pub mod rubiks_cube_heuristics;
+pub mod two_phase;
+pub mod n_cube;
+pub mod pocket_cube;
+pub mod reduction;
@@ -0,0 +1,485 @@
+// Author: Harper Davis
+use std::{fmt, iter::Peekable, str::CharIndices};
+
+use crate::state::State;
+
+use super::rubiks_cube::{CubeState, CubeAction};
+
+// Faces numbered the way CubeAction::face numbers them
+const U: usize = 0;
+const D: usize = 1;
+const R: usize = 2;
+const L: usize = 3;
+const F: usize = 4;
+const B: usize = 5;
+
+/// The most times a group can be repeated, and the most moves repeating one can come to, so that something like
+/// (R U)4000000000 is an error rather than running out of memory
+const MAX_REPEATS: u32 = 1000;
+const MAX_MOVES: usize = 100_000;
+
+fn opposite(face: usize) -> usize {
+    face ^ 1
+}
+
+/// What was wrong with an algorithm, and where in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotationError {
+    /// Counting characters from 1
+    position: usize,
+    message: String,
+}
+
+impl NotationError {
+
+    fn new(position: usize, message: String) -> NotationError {
+        NotationError { position, message }
+    }
+
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+}
+
+impl fmt::Display for NotationError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "character {}: {}", self.position, self.message)
+    }
+
+}
+
+///////////////////////////////////////////////////////
+
+/// One move the way it's written, before it becomes face turns
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Move {
+    Face(usize, u8),
+    /// The middle layer, turning the same way as the face (L for M, D for E and F for S)
+    Slice(usize, u8),
+    /// The face and the middle layer behind it
+    Wide(usize, u8),
+    /// The whole cube, turning the same way as the face (R for x, U for y and F for z)
+    Rotation(usize, u8),
+}
+
+impl Move {
+
+    fn inverse(self) -> Move {
+        let inverse = |quarter_turns: u8| (4 - quarter_turns) % 4;
+        match self {
+            Move::Face(face, quarter_turns) => Move::Face(face, inverse(quarter_turns)),
+            Move::Slice(face, quarter_turns) => Move::Slice(face, inverse(quarter_turns)),
+            Move::Wide(face, quarter_turns) => Move::Wide(face, inverse(quarter_turns)),
+            Move::Rotation(face, quarter_turns) => Move::Rotation(face, inverse(quarter_turns)),
+        }
+    }
+
+}
+
+fn inverse(moves: &[Move]) -> Vec<Move> {
+    moves.iter().rev().map(|m| m.inverse()).collect()
+}
+
+/// Turns the moves into face turns. CubeState keeps the centers where they are, so slices, wide moves and rotations
+/// are done as the face turns that leave the cube the same up to turning the whole thing (M is R L' x', r is L x
+/// and so on), and `orientation` keeps track of which face has ended up where for the moves after them
+fn to_actions(moves: &[Move]) -> Vec<CubeAction> {
+    // orientation[face] is the face of the cube that sits where `face` was to start with
+    let mut orientation = [U, D, R, L, F, B];
+    let mut actions = Vec::new();
+    let mut turn = |face: usize, quarter_turns: u8, orientation: &[usize; 6]| {
+        if let Some(action) = CubeAction::from_face(orientation[face], quarter_turns) {
+            actions.push(action);
+        }
+    };
+    for m in moves {
+        match *m {
+            Move::Face(face, quarter_turns) => turn(face, quarter_turns, &orientation),
+            Move::Slice(face, quarter_turns) => {
+                turn(face, 4 - quarter_turns, &orientation);
+                turn(opposite(face), quarter_turns, &orientation);
+                rotate(&mut orientation, face, quarter_turns);
+            }
+            Move::Wide(face, quarter_turns) => {
+                turn(opposite(face), quarter_turns, &orientation);
+                rotate(&mut orientation, face, quarter_turns);
+            }
+            Move::Rotation(face, quarter_turns) => rotate(&mut orientation, face, quarter_turns),
+        }
+    }
+    actions
+}
+
+/// Turns the whole cube the same way as the face turns
+fn rotate(orientation: &mut [usize; 6], face: usize, quarter_turns: u8) {
+    // where the faces around each axis go for a clockwise turn of R, U and F
+    let cycle = match face {
+        R | L => [F, U, B, D],
+        U | D => [F, L, B, R],
+        _ => [U, R, D, L],
+    };
+    let quarter_turns = if face == L || face == D || face == B { 4 - quarter_turns % 4 } else { quarter_turns % 4 };
+    for _ in 0..quarter_turns {
+        let old = *orientation;
+        for i in 0..4 {
+            orientation[cycle[(i + 1) % 4]] = old[cycle[i]];
+        }
+    }
+}
+
+///////////////////////////////////////////////////////
+
+/// Reads a whole algorithm in the usual notation, like "R U R' U' F2". Besides the face turns it takes
+///  - slices M, E and S, wide moves r or Rw (and the same for every face) and rotations x, y and z
+///  - 2 or ' after any of them, or any number of quarter turns like R3
+///  - groups, (R U R' U')3 repeats one and (R U)' inverts it
+///  - commutators [A, B] for A B A' B' and conjugates [A: B] for A B A', which can be nested
+///
+/// Moves don't need spaces between them
+pub fn parse_algorithm(notation: &str) -> Result<Vec<CubeAction>, NotationError> {
+    let mut parser = Parser { characters: notation.char_indices().peekable(), notation };
+    let moves = parser.sequence()?;
+    if let Some((position, c)) = parser.characters.next() {
+        return Err(parser.error(position, format!("there's no group for this {} to close", c)));
+    }
+    Ok(to_actions(&moves))
+}
+
+struct Parser<'a> {
+    characters: Peekable<CharIndices<'a>>,
+    notation: &'a str,
+}
+
+impl Parser<'_> {
+
+    /// Errors count characters rather than bytes
+    fn error(&self, position: usize, message: String) -> NotationError {
+        NotationError::new(self.notation[..position].chars().count() + 1, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.characters.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    /// Moves up to the end, or to whatever closes the group it's in
+    fn sequence(&mut self) -> Result<Vec<Move>, NotationError> {
+        let mut moves = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let Some(&(position, c)) = self.characters.peek() else {
+                return Ok(moves);
+            };
+            match c {
+                ')' | ']' | ',' | ':' => return Ok(moves),
+                '(' => {
+                    self.characters.next();
+                    let group = self.sequence()?;
+                    self.close(position, '(', ')')?;
+                    moves.extend(self.repeat(&group)?);
+                }
+                '[' => {
+                    self.characters.next();
+                    let first = self.sequence()?;
+                    self.skip_whitespace();
+                    let group = match self.characters.next() {
+                        Some((_, ',')) => {
+                            let second = self.sequence()?;
+                            [first.clone(), second.clone(), inverse(&first), inverse(&second)].concat()
+                        }
+                        Some((_, ':')) => {
+                            let second = self.sequence()?;
+                            [first.clone(), second, inverse(&first)].concat()
+                        }
+                        _ => return Err(self.error(position, String::from("this [ needs a , or : inside it"))),
+                    };
+                    self.close(position, '[', ']')?;
+                    moves.extend(self.repeat(&group)?);
+                }
+                _ => {
+                    self.characters.next();
+                    let m = self.single_move(position, c)?;
+                    moves.extend(self.repeat(&[m])?);
+                }
+            }
+        }
+    }
+
+    fn close(&mut self, position: usize, open: char, close: char) -> Result<(), NotationError> {
+        self.skip_whitespace();
+        match self.characters.next() {
+            Some((_, c)) if c == close => Ok(()),
+            _ => Err(self.error(position, format!("this {} is never closed with a {}", open, close))),
+        }
+    }
+
+    /// The letters of one move, with a w for wide moves
+    fn single_move(&mut self, position: usize, c: char) -> Result<Move, NotationError> {
+        let face = |c: char| match c.to_ascii_uppercase() {
+            'U' => Some(U),
+            'D' => Some(D),
+            'R' => Some(R),
+            'L' => Some(L),
+            'F' => Some(F),
+            'B' => Some(B),
+            _ => None,
+        };
+        match (c, face(c)) {
+            ('M', _) => Ok(Move::Slice(L, 1)),
+            ('E', _) => Ok(Move::Slice(D, 1)),
+            ('S', _) => Ok(Move::Slice(F, 1)),
+            ('x', _) => Ok(Move::Rotation(R, 1)),
+            ('y', _) => Ok(Move::Rotation(U, 1)),
+            ('z', _) => Ok(Move::Rotation(F, 1)),
+            (c, Some(face)) if c.is_ascii_lowercase() => Ok(Move::Wide(face, 1)),
+            (_, Some(face)) if self.characters.next_if(|&(_, c)| c == 'w').is_some() => Ok(Move::Wide(face, 1)),
+            (_, Some(face)) => Ok(Move::Face(face, 1)),
+            _ => Err(self.error(position, format!("{} isn't a move", c))),
+        }
+    }
+
+    /// The count and ' that can follow a move or a group, either way around (R2' and R'2 are both R2)
+    fn repeat(&mut self, moves: &[Move]) -> Result<Vec<Move>, NotationError> {
+        let mut count = 0_u32;
+        let mut digits = None;
+        let mut inverted = false;
+        while let Some(&(position, c)) = self.characters.peek() {
+            if let Some(digit) = c.to_digit(10) {
+                count = count.checked_mul(10).and_then(|count| count.checked_add(digit)).ok_or_else(|| self.error(position, String::from("that's too many repeats")))?;
+                digits.get_or_insert(position);
+            } else if c == '\'' && !inverted {
+                inverted = true;
+            } else {
+                break;
+            }
+            self.characters.next();
+        }
+        if digits.is_none() {
+            count = 1;
+        }
+
+        let moves = if inverted { inverse(moves) } else { moves.to_vec() };
+        // a single move only needs its quarter turns multiplied, and four of anything is nothing
+        if let [m] = moves[..] {
+            let times = (count % 4) as u8;
+            return Ok(vec![match m {
+                Move::Face(face, quarter_turns) => Move::Face(face, quarter_turns * times % 4),
+                Move::Slice(face, quarter_turns) => Move::Slice(face, quarter_turns * times % 4),
+                Move::Wide(face, quarter_turns) => Move::Wide(face, quarter_turns * times % 4),
+                Move::Rotation(face, quarter_turns) => Move::Rotation(face, quarter_turns * times % 4),
+            }]);
+        }
+        if let Some(position) = digits.filter(|_| count > MAX_REPEATS || moves.len() * count as usize > MAX_MOVES) {
+            return Err(self.error(position, format!("a group can only be repeated {} times, up to {} moves", MAX_REPEATS, MAX_MOVES)));
+        }
+        Ok(moves.repeat(count as usize))
+    }
+
+}
+
+///////////////////////////////////////////////////////
+
+/// Things to do with a whole sequence of moves
+pub trait Algorithm {
+
+    /// The moves that undo these ones
+    fn inverted(&self) -> Vec<CubeAction>;
+
+    /// The same moves with turns of the same face next to each other merged into one, or cancelled out
+    /// when they add up to nothing. Turns of opposite faces don't get in the way, since they commute
+    fn simplified(&self) -> Vec<CubeAction>;
+
+    /// Written out with a space between the moves, which parse_algorithm reads back
+    fn to_notation(&self) -> String;
+
+    fn apply_to(&self, cube: &CubeState) -> CubeState;
+
+}
+
+impl Algorithm for [CubeAction] {
+
+    fn inverted(&self) -> Vec<CubeAction> {
+        self.iter().rev().map(|action| CubeAction::from_face(action.face(), 4 - action.quarter_turns()).unwrap()).collect()
+    }
+
+    fn simplified(&self) -> Vec<CubeAction> {
+        let mut simplified: Vec<CubeAction> = Vec::new();
+        for action in self {
+            let face = action.face();
+            let length = simplified.len();
+            // the last move, or the one before it if the last one turns the opposite face
+            let merge_with = match simplified[..] {
+                [.., ref last] if last.face() == face => Some(length - 1),
+                [.., ref before, ref last] if last.face() == opposite(face) && before.face() == face => Some(length - 2),
+                _ => None,
+            };
+            match merge_with {
+                Some(i) => match CubeAction::from_face(face, simplified[i].quarter_turns() + action.quarter_turns()) {
+                    Some(merged) => simplified[i] = merged,
+                    None => {
+                        simplified.remove(i);
+                    }
+                },
+                None => simplified.push(action.clone()),
+            }
+        }
+        simplified
+    }
+
+    fn to_notation(&self) -> String {
+        self.iter().map(|action| action.to_string()).collect::<Vec<String>>().join(" ")
+    }
+
+    fn apply_to(&self, cube: &CubeState) -> CubeState {
+        self.iter().fold(cube.clone(), |cube, action| cube.perform_action(action))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    use crate::{action::InvertibleAction, puzzles::{facelets::STICKER_POSITIONS, n_cube::{NCube, NCubeAction, FACES}}};
+
+    use super::*;
+
+    fn parse(notation: &str) -> Vec<CubeAction> {
+        parse_algorithm(notation).unwrap()
+    }
+
+    fn assert_same(first: &str, second: &str) {
+        let solved = CubeState::default();
+        assert_eq!(parse(first).apply_to(&solved).faces, parse(second).apply_to(&solved).faces, "{} and {}", first, second);
+    }
+
+    #[test]
+    fn parses_face_turns() {
+        use CubeAction::*;
+        assert_eq!(parse("R U R' U' F2"), vec![R, U, RPrime, UPrime, F2]);
+        assert_eq!(parse("RUR'U'"), vec![R, U, RPrime, UPrime]);
+        assert_eq!(parse("R2' L'2 D3 B4 F5"), vec![R2, L2, DPrime, F]);
+        assert_eq!(parse("  "), vec![]);
+    }
+
+    #[test]
+    fn slices_wide_moves_and_rotations() {
+        assert_same("(R U R' U')6", "");
+        assert_same("r R'", "M'");
+        assert_same("Rw U", "r U");
+        assert_same("x U x'", "F");
+        assert_same("y R y'", "B");
+        assert_same("z U z'", "L");
+        assert_same("E", "U D' y'");
+        assert_same("S", "F' B z");
+        assert_same("M2 E2 S2", "R2 L2 U2 D2 F2 B2");
+        assert_same("x y z R U", "x2 y R U");
+        assert_same("u", "D y");
+        assert_same("[M, U]'", "U M U' M'");
+        assert_same("[M, U] [M, U]'", "");
+    }
+
+    #[test]
+    fn commutators_and_conjugates() {
+        assert_eq!(parse("[R, U]"), parse("R U R' U'"));
+        assert_eq!(parse("[R: U]"), parse("R U R'"));
+        assert_eq!(parse("[F: [R, U]]"), parse("F R U R' U' F'"));
+        assert_eq!(parse("[R U: F]2"), parse("R U F U' R' R U F U' R'"));
+        assert_eq!(parse("(R U)'"), parse("U' R'"));
+        assert_eq!(parse("[R, U]'"), parse("U R U' R'"));
+    }
+
+    #[test]
+    fn errors_say_where() {
+        assert_eq!(parse_algorithm("R U Q").unwrap_err().get_position(), 5);
+        assert_eq!(parse_algorithm("R (U R").unwrap_err().get_position(), 3);
+        assert_eq!(parse_algorithm("[R U]").unwrap_err().get_position(), 1);
+        assert_eq!(parse_algorithm("R U)").unwrap_err().get_position(), 4);
+        assert_eq!(parse_algorithm("R, U").unwrap_err().get_position(), 2);
+        assert_eq!(parse_algorithm("(R U)4000000000").unwrap_err().get_position(), 6);
+        assert_eq!(parse_algorithm("((R U)1000)1000").unwrap_err().get_position(), 12);
+        assert_eq!(parse_algorithm("R4000000000").map(|actions| actions.len()), Ok(0));
+    }
+
+    /// Slices, wide moves and rotations written out as layer turns of NCube<3>, which turns its middle layers for real
+    /// rather than working them out from face turns. CubeState turns the other way to a real cube, so each move it
+    /// makes is the inverse of the real one
+    #[test]
+    fn matches_a_real_cube() {
+        let mut rng = StdRng::seed_from_u64(23);
+        let turn = |letter: char, layer: usize, quarter_turns: u8| {
+            NCubeAction::<3>::new(FACES.iter().position(|&face| face == letter).unwrap(), layer, quarter_turns)
+        };
+        let opposite = |letter: char| match letter { 'U' => 'D', 'D' => 'U', 'R' => 'L', 'L' => 'R', 'F' => 'B', _ => 'F' };
+        for _ in 0..50 {
+            let mut notation = Vec::new();
+            let mut real = Vec::new();
+            for _ in 0..20 {
+                let quarter_turns = rng.gen_range(1..=3);
+                let suffix = ["", "2", "'"][quarter_turns as usize - 1];
+                let letter = *['U', 'D', 'R', 'L', 'F', 'B'].choose(&mut rng).unwrap();
+                match rng.gen_range(0..4) {
+                    0 => {
+                        notation.push(format!("{}{}", letter, suffix));
+                        real.push(turn(letter, 0, quarter_turns));
+                    }
+                    1 => {
+                        // M turns like L, E like D and S like F
+                        let (slice, face) = match letter { 'U' | 'D' => ('E', 'D'), 'R' | 'L' => ('M', 'L'), _ => ('S', 'F') };
+                        notation.push(format!("{}{}", slice, suffix));
+                        real.push(turn(face, 1, quarter_turns));
+                    }
+                    2 => {
+                        notation.push(format!("{}{}", letter.to_ascii_lowercase(), suffix));
+                        real.extend([turn(letter, 0, quarter_turns), turn(letter, 1, quarter_turns)]);
+                    }
+                    _ => {
+                        let (rotation, face) = match letter { 'U' | 'D' => ('y', 'U'), 'R' | 'L' => ('x', 'R'), _ => ('z', 'F') };
+                        notation.push(format!("{}{}", rotation, suffix));
+                        real.extend([turn(face, 0, quarter_turns), turn(face, 1, quarter_turns), turn(opposite(face), 0, 4 - quarter_turns)]);
+                    }
+                }
+            }
+            let notation = notation.join(" ");
+            let cube = real.iter().fold(NCube::<3>::default(), |cube, action| cube.perform_action(action));
+            // CubeState never moves its centers, so the real cube gets turned back around to match
+            let cube = cube.orientations().into_iter().find(|cube| (0..6).all(|face| cube.get_sticker_at(face, 1, 1) == face as u8)).unwrap();
+            let state = parse(&notation).iter().map(|action| action.inverse()).collect::<Vec<CubeAction>>().apply_to(&CubeState::default());
+            for face in 0..6 {
+                for (index, &(row, column)) in STICKER_POSITIONS.iter().enumerate() {
+                    assert_eq!(state.get_sticker(face, index), cube.get_sticker_at(face, row, column), "{}", notation);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn simplifies() {
+        assert_eq!(parse("R R").simplified().to_notation(), "R2");
+        assert_eq!(parse("R R'").simplified().to_notation(), "");
+        assert_eq!(parse("U D U'").simplified().to_notation(), "D");
+        assert_eq!(parse("R2 R").simplified().to_notation(), "R'");
+        assert_eq!(parse("F R U U' R' F'").simplified().to_notation(), "");
+        assert_eq!(parse("R L R U").simplified().to_notation(), "R2 L U");
+    }
+
+    #[test]
+    fn random_algorithms() {
+        let mut rng = StdRng::seed_from_u64(23);
+        for i in 0..200 {
+            let (cube, scramble) = CubeState::scrambled_cube_with(&mut rng, i % 40);
+            assert_eq!(scramble.inverted().apply_to(&cube).faces, CubeState::default().faces);
+            let simplified = scramble.simplified();
+            assert!(simplified.len() <= scramble.len());
+            assert_eq!(simplified.apply_to(&CubeState::default()).faces, cube.faces);
+            assert!(simplified.windows(2).all(|pair| pair[0].face() != pair[1].face()));
+            assert_eq!(parse(&scramble.to_notation()), scramble);
+        }
+    }
+
+}
@@ -0,0 +1,308 @@
+// Author: Harper Davis
+use crate::state::State;
+
+use super::rubiks_cube::{CubeAction, CubeState};
+
+/// The stickers that make up each corner slot, as (face, index) pairs into CubeState::faces.
+/// The slots are URF, UFL, ULB, UBR, DFR, DLF, DBL, DRB. The first sticker is always the one on
+/// the U or D face, and the other two follow it going clockwise around the corner
+pub const CORNER_FACELETS: [[(usize, usize); 3]; 8] = [
+    [(0, 6), (2, 0), (4, 2)],
+    [(0, 4), (5, 0), (2, 2)],
+    [(0, 2), (3, 0), (5, 2)],
+    [(0, 0), (4, 0), (3, 2)],
+    [(1, 0), (4, 4), (2, 6)],
+    [(1, 2), (2, 4), (5, 6)],
+    [(1, 4), (5, 4), (3, 6)],
+    [(1, 6), (3, 4), (4, 6)],
+];
+
+/// The stickers that make up each edge slot: UR, UF, UL, UB, DR, DF, DL, DB, FR, FL, BL, BR.
+/// The first sticker is the one on the U or D face, or on the F or B face for the middle layer
+pub const EDGE_FACELETS: [[(usize, usize); 2]; 12] = [
+    [(0, 7), (4, 1)],
+    [(0, 5), (2, 1)],
+    [(0, 3), (5, 1)],
+    [(0, 1), (3, 1)],
+    [(1, 7), (4, 5)],
+    [(1, 1), (2, 5)],
+    [(1, 3), (5, 5)],
+    [(1, 5), (3, 5)],
+    [(2, 7), (4, 3)],
+    [(2, 3), (5, 7)],
+    [(3, 7), (5, 3)],
+    [(3, 3), (4, 7)],
+];
+
+/// The cube described by where each corner and edge piece is and which way it is turned,
+/// instead of by sticker colors. Slot i holds piece permutation[i], and its orientation is how
+/// far around the slot its reference sticker (the U or D colored one, or F or B for middle edges) has moved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CubieCube {
+    pub corner_permutation: [u8; 8],
+    pub corner_orientation: [u8; 8],
+    pub edge_permutation: [u8; 12],
+    pub edge_orientation: [u8; 12],
+}
+
+impl Default for CubieCube {
+
+    fn default() -> Self {
+        CubieCube {
+            corner_permutation: [0, 1, 2, 3, 4, 5, 6, 7],
+            corner_orientation: [0; 8],
+            edge_permutation: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+            edge_orientation: [0; 12],
+        }
+    }
+
+}
+
+impl CubieCube {
+
+    /// Reads the pieces off of the stickers, or returns None if some slot holds a combination
+    /// of colors that no real piece has, or if the same piece shows up twice
+    pub fn from_state(state: &CubeState) -> Option<CubieCube> {
+        let mut cubies = CubieCube::default();
+
+        let mut corners_seen = 0_u8;
+        for (slot, facelets) in CORNER_FACELETS.iter().enumerate() {
+            let colors = facelets.map(|(face, index)| state.get_sticker(face, index));
+            let orientation = colors.iter().position(|&color| color == 0 || color == 1)?;
+            let corner = CORNER_FACELETS.iter().position(|piece| {
+                (0..3).all(|k| colors[(orientation + k) % 3] == piece[k].0 as u8)
+            })?;
+            if corners_seen & (1 << corner) != 0 {
+                return None;
+            }
+            corners_seen |= 1 << corner;
+            cubies.corner_permutation[slot] = corner as u8;
+            cubies.corner_orientation[slot] = orientation as u8;
+        }
+
+        let mut edges_seen = 0_u16;
+        for (slot, facelets) in EDGE_FACELETS.iter().enumerate() {
+            let colors = facelets.map(|(face, index)| state.get_sticker(face, index));
+            let (edge, orientation) = EDGE_FACELETS.iter().enumerate().find_map(|(edge, piece)| {
+                if colors[0] == piece[0].0 as u8 && colors[1] == piece[1].0 as u8 {
+                    Some((edge, 0))
+                } else if colors[0] == piece[1].0 as u8 && colors[1] == piece[0].0 as u8 {
+                    Some((edge, 1))
+                } else {
+                    None
+                }
+            })?;
+            if edges_seen & (1 << edge) != 0 {
+                return None;
+            }
+            edges_seen |= 1 << edge;
+            cubies.edge_permutation[slot] = edge as u8;
+            cubies.edge_orientation[slot] = orientation;
+        }
+
+        Some(cubies)
+    }
+
+    pub fn to_state(&self) -> CubeState {
+        let mut state = CubeState::default();
+        for slot in 0..8 {
+            let corner = CORNER_FACELETS[self.corner_permutation[slot] as usize];
+            let orientation = self.corner_orientation[slot] as usize;
+            for (k, &(color, _)) in corner.iter().enumerate() {
+                let (face, index) = CORNER_FACELETS[slot][(k + orientation) % 3];
+                state.set_sticker(face, index, color as u8);
+            }
+        }
+        for slot in 0..12 {
+            let edge = EDGE_FACELETS[self.edge_permutation[slot] as usize];
+            let orientation = self.edge_orientation[slot] as usize;
+            for (k, &(color, _)) in edge.iter().enumerate() {
+                let (face, index) = EDGE_FACELETS[slot][(k + orientation) % 2];
+                state.set_sticker(face, index, color as u8);
+            }
+        }
+        state
+    }
+
+    /// What a single move does to the pieces of a solved cube
+    pub fn from_action(action: &CubeAction) -> CubieCube {
+        let state = CubeState::default().perform_action(action);
+        CubieCube::from_state(&state).expect("a single move of a solved cube is always a valid cube")
+    }
+
+    /// The cube you get by doing `self` and then `other`
+    pub fn multiply(&self, other: &CubieCube) -> CubieCube {
+        let mut product = CubieCube::default();
+        for slot in 0..8 {
+            let from = other.corner_permutation[slot] as usize;
+            product.corner_permutation[slot] = self.corner_permutation[from];
+            product.corner_orientation[slot] = (self.corner_orientation[from] + other.corner_orientation[slot]) % 3;
+        }
+        for slot in 0..12 {
+            let from = other.edge_permutation[slot] as usize;
+            product.edge_permutation[slot] = self.edge_permutation[from];
+            product.edge_orientation[slot] = (self.edge_orientation[from] + other.edge_orientation[slot]) % 2;
+        }
+        product
+    }
+
+    /// The orientations of the first 7 corners read as a base 3 number, the last one is fixed by the others
+    pub fn twist(&self) -> usize {
+        self.corner_orientation[..7].iter().fold(0, |twist, &orientation| twist * 3 + orientation as usize)
+    }
+
+    pub fn set_twist(&mut self, mut twist: usize) {
+        let mut total = 0;
+        for slot in (0..7).rev() {
+            self.corner_orientation[slot] = (twist % 3) as u8;
+            total += self.corner_orientation[slot];
+            twist /= 3;
+        }
+        self.corner_orientation[7] = (3 - total % 3) % 3;
+    }
+
+    /// The orientations of the first 11 edges read as a binary number
+    pub fn flip(&self) -> usize {
+        self.edge_orientation[..11].iter().fold(0, |flip, &orientation| flip * 2 + orientation as usize)
+    }
+
+    pub fn set_flip(&mut self, mut flip: usize) {
+        let mut total = 0;
+        for slot in (0..11).rev() {
+            self.edge_orientation[slot] = (flip % 2) as u8;
+            total += self.edge_orientation[slot];
+            flip /= 2;
+        }
+        self.edge_orientation[11] = total % 2;
+    }
+
+    /// Which 4 slots the FR, FL, BL and BR edges are in (but not which is where), numbered from 0 to 494
+    pub fn slice(&self) -> usize {
+        let mut slice = 0;
+        let mut count = 0;
+        for slot in 0..12 {
+            if self.edge_permutation[slot] >= 8 {
+                count += 1;
+                slice += binomial(slot, count);
+            }
+        }
+        slice
+    }
+
+    /// Puts the middle layer edges in the slots given by `slice`, and the rest of the edges in order around them
+    pub fn set_slice(&mut self, mut slice: usize) {
+        let mut in_slice = [false; 12];
+        let mut count = 4;
+        for slot in (0..12).rev() {
+            if count > 0 && binomial(slot, count) <= slice {
+                slice -= binomial(slot, count);
+                in_slice[slot] = true;
+                count -= 1;
+            }
+        }
+
+        let (mut slice_edge, mut other_edge) = (8, 0);
+        for (edge, in_slice) in self.edge_permutation.iter_mut().zip(in_slice) {
+            if in_slice {
+                *edge = slice_edge;
+                slice_edge += 1;
+            } else {
+                *edge = other_edge;
+                other_edge += 1;
+            }
+        }
+    }
+
+    pub fn corner_permutation_rank(&self) -> usize {
+        permutation_rank(&self.corner_permutation)
+    }
+
+    pub fn set_corner_permutation_rank(&mut self, rank: usize) {
+        permutation_unrank(rank, &mut self.corner_permutation);
+    }
+
+    /// The order of the 8 edges of the U and D layers, only meaningful once none of them are in the middle layer
+    pub fn edge_permutation_rank(&self) -> usize {
+        permutation_rank(&self.edge_permutation[..8])
+    }
+
+    pub fn set_edge_permutation_rank(&mut self, rank: usize) {
+        permutation_unrank(rank, &mut self.edge_permutation[..8]);
+    }
+
+    /// The order of the 4 middle layer edges, only meaningful once they are all in the middle layer
+    pub fn slice_permutation_rank(&self) -> usize {
+        permutation_rank(&self.edge_permutation[8..])
+    }
+
+    pub fn set_slice_permutation_rank(&mut self, rank: usize) {
+        permutation_unrank(rank, &mut self.edge_permutation[8..]);
+        for edge in &mut self.edge_permutation[8..] {
+            *edge += 8;
+        }
+    }
+
+    pub fn corner_parity(&self) -> u8 {
+        permutation_parity(&self.corner_permutation)
+    }
+
+    pub fn edge_parity(&self) -> u8 {
+        permutation_parity(&self.edge_permutation)
+    }
+
+    /// A face turn always swaps the parity of both the corners and the edges together, and turns
+    /// the corners and flips the edges in a way that cancels out, so a cube taken apart and put
+    /// back together wrong can only be solved if all three of these still hold
+    pub fn is_solvable(&self) -> bool {
+        self.corner_parity() == self.edge_parity()
+            && self.corner_orientation.iter().sum::<u8>() % 3 == 0
+            && self.edge_orientation.iter().sum::<u8>() % 2 == 0
+    }
+
+}
+
+/// 0 if the permutation takes an even number of swaps, 1 if odd
+fn permutation_parity(permutation: &[u8]) -> u8 {
+    let mut parity = 0;
+    for i in 0..permutation.len() {
+        for j in (i + 1)..permutation.len() {
+            if permutation[j] < permutation[i] {
+                parity ^= 1;
+            }
+        }
+    }
+    parity
+}
+
+/// Numbers the orderings of a permutation from 0 to n! - 1, by counting for each element how many
+/// of the ones after it are smaller
+fn permutation_rank(permutation: &[u8]) -> usize {
+    let mut rank = 0;
+    for i in 0..permutation.len() {
+        let smaller = permutation[i + 1..].iter().filter(|&&other| other < permutation[i]).count();
+        rank = rank * (permutation.len() - i) + smaller;
+    }
+    rank
+}
+
+/// Fills `permutation` with the ordering of 0 to n - 1 that has the given rank
+fn permutation_unrank(mut rank: usize, permutation: &mut [u8]) {
+    let size = permutation.len();
+    let mut digits = vec![0; size];
+    for i in (0..size).rev() {
+        digits[i] = rank % (size - i);
+        rank /= size - i;
+    }
+
+    let mut remaining = (0..size as u8).collect::<Vec<u8>>();
+    for (i, digit) in digits.into_iter().enumerate() {
+        permutation[i] = remaining.remove(digit);
+    }
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |result, i| result * (n - i) / (i + 1))
+}
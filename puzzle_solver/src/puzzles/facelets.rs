@@ -0,0 +1,268 @@
+// Author: Harper Davis
+use std::fmt;
+
+use crate::action::InvertibleAction;
+
+use super::{cubie_cube::{CubieCube, CORNER_FACELETS, EDGE_FACELETS}, rubiks_cube::{CubeState, CubeAction}};
+
+/// The faces in the order the facelet string has them, and CubeState's number for each
+const ORDER: [char; 6] = ['U', 'R', 'F', 'D', 'L', 'B'];
+const FACES: [usize; 6] = [0, 4, 2, 1, 5, 3];
+/// Where each of CubeState's eight stickers on a face is, as a row and column of the face the way the facelet string
+/// reads it: U seen from above with B at the top, D from below with F at the top, and the rest with U at the top.
+/// CubeState is the mirror image of a real cube, so its stickers go around the other way and it turns the other way too
+pub(crate) const STICKER_POSITIONS: [(usize, usize); 8] = [(0, 2), (0, 1), (0, 0), (1, 0), (2, 0), (2, 1), (2, 2), (1, 2)];
+
+/// Why a facelet string isn't a cube. Stickers are named the usual way, by face and then 1 to 9 across the rows, so U1
+/// is the corner of U between B and L and F5 is the center of F
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaceletError {
+    /// There have to be 54 stickers, not counting spaces
+    Length(usize),
+    /// Centers that share a color with another center, or that aren't the letter of their own face when the colors are
+    /// given as face letters
+    Centers(Vec<String>),
+    /// Stickers with a color that isn't on any of the centers
+    Colors(Vec<String>),
+    /// Colors that aren't on exactly nine stickers, and how many they are on
+    Counts(Vec<(char, usize)>),
+    /// The stickers of each corner or edge that doesn't have the colors of a real piece, or has the same colors as
+    /// another one
+    Pieces(Vec<Vec<String>>),
+    /// Every piece is real, but one corner has been twisted in place
+    Twist,
+    /// The same for an edge that's been flipped
+    Flip,
+    /// The same for two pieces that have swapped places
+    Parity,
+}
+
+impl fmt::Display for FaceletError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FaceletError::Length(length) => write!(f, "expected 54 stickers, found {}", length),
+            FaceletError::Centers(stickers) => write!(f, "the centers {} don't match", stickers.join(", ")),
+            FaceletError::Colors(stickers) => write!(f, "{} aren't the color of any center", stickers.join(", ")),
+            FaceletError::Counts(counts) => {
+                let counts = counts.iter().map(|(color, count)| format!("{} is on {}", color, count)).collect::<Vec<String>>();
+                write!(f, "every color has to be on 9 stickers, but {}", counts.join(", "))
+            }
+            FaceletError::Pieces(pieces) => {
+                let pieces = pieces.iter().map(|stickers| stickers.join("-")).collect::<Vec<String>>();
+                write!(f, "the pieces at {} aren't real pieces or are there twice", pieces.join(", "))
+            }
+            FaceletError::Twist => write!(f, "a corner is twisted in place"),
+            FaceletError::Flip => write!(f, "an edge is flipped in place"),
+            FaceletError::Parity => write!(f, "two pieces have swapped places"),
+        }
+    }
+
+}
+
+/// The name of a sticker of CubeState, like U1 or F5
+fn name(face: usize, index: usize) -> String {
+    let order = FACES.iter().position(|&f| f == face).unwrap();
+    let (row, column) = STICKER_POSITIONS[index];
+    format!("{}{}", ORDER[order], row * 3 + column + 1)
+}
+
+impl CubeState {
+
+    /// Reads the 54 sticker facelet string, with the faces in the order U, R, F, D, L and B and each one read across
+    /// its rows (spaces in between are fine). Colors can be any six characters, whichever one is on a center being
+    /// that face's color, so both UUUUUUUUURRR... and WWWWWWWWWGGG... work. CubeState turns the opposite way to a
+    /// real cube, so a solution for the cube that comes back is done on the real one with every turn reversed
+    pub fn from_facelets(facelets: &str) -> Result<CubeState, FaceletError> {
+        let stickers = facelets.chars().filter(|c| !c.is_whitespace()).collect::<Vec<char>>();
+        if stickers.len() != 54 {
+            return Err(FaceletError::Length(stickers.len()));
+        }
+
+        // with face letters for colors every center has to be its own letter, otherwise they just have to differ
+        let centers = (0..6).map(|order| stickers[order * 9 + 4]).collect::<Vec<char>>();
+        let face_letters = stickers.iter().all(|sticker| ORDER.contains(sticker));
+        let wrong_centers = (0..6).filter(|&order| {
+            let center = centers[order];
+            if face_letters { center != ORDER[order] } else { centers.iter().filter(|&&other| other == center).count() > 1 }
+        }).map(|order| format!("{}5", ORDER[order])).collect::<Vec<String>>();
+        if !wrong_centers.is_empty() {
+            return Err(FaceletError::Centers(wrong_centers));
+        }
+
+        let unknown = (0..54).filter(|&i| !centers.contains(&stickers[i])).map(|i| format!("{}{}", ORDER[i / 9], i % 9 + 1)).collect::<Vec<String>>();
+        if !unknown.is_empty() {
+            return Err(FaceletError::Colors(unknown));
+        }
+        let counts = centers.iter().map(|&center| (center, stickers.iter().filter(|&&sticker| sticker == center).count())).filter(|&(_, count)| count != 9).collect::<Vec<(char, usize)>>();
+        if !counts.is_empty() {
+            return Err(FaceletError::Counts(counts));
+        }
+
+        let mut state = CubeState::new();
+        for (order, &face) in FACES.iter().enumerate() {
+            for (index, &(row, column)) in STICKER_POSITIONS.iter().enumerate() {
+                let color = centers.iter().position(|&center| center == stickers[order * 9 + row * 3 + column]).unwrap();
+                state.set_sticker(face, index, FACES[color] as u8);
+            }
+        }
+
+        let pieces = bad_pieces(&state, &CORNER_FACELETS).into_iter().chain(bad_pieces(&state, &EDGE_FACELETS)).collect::<Vec<Vec<String>>>();
+        if !pieces.is_empty() {
+            return Err(FaceletError::Pieces(pieces));
+        }
+        let cubies = CubieCube::from_state(&state).expect("every piece was checked");
+        if cubies.corner_orientation.iter().sum::<u8>() % 3 != 0 {
+            Err(FaceletError::Twist)
+        } else if cubies.edge_orientation.iter().sum::<u8>() % 2 != 0 {
+            Err(FaceletError::Flip)
+        } else if cubies.corner_parity() != cubies.edge_parity() {
+            Err(FaceletError::Parity)
+        } else {
+            Ok(state)
+        }
+    }
+
+    /// The facelet string from_facelets reads, with face letters for colors
+    pub fn to_facelets(&self) -> String {
+        let mut facelets = String::new();
+        for &face in &FACES {
+            for row in 0..3 {
+                for column in 0..3 {
+                    let color = match STICKER_POSITIONS.iter().position(|&position| position == (row, column)) {
+                        Some(index) => self.get_sticker(face, index) as usize,
+                        None => face,
+                    };
+                    facelets.push(ORDER[FACES.iter().position(|&f| f == color).unwrap()]);
+                }
+            }
+        }
+        facelets
+    }
+
+}
+
+/// The same moves done on a real cube, which turns every face the opposite way to CubeState, so that a solution for
+/// a cube read with from_facelets can be followed on the real one
+pub fn real_turns(actions: &[CubeAction]) -> Vec<CubeAction> {
+    actions.iter().map(|action| action.inverse()).collect()
+}
+
+/// The stickers of every slot whose colors aren't those of any piece turned any way it can be, or are the same as
+/// another slot's. A piece with S stickers can be turned S ways in its slot
+fn bad_pieces<const S: usize>(state: &CubeState, slots: &[[(usize, usize); S]]) -> Vec<Vec<String>> {
+    let pieces = slots.iter().map(|slot| {
+        let colors = slot.map(|(face, index)| state.get_sticker(face, index) as usize);
+        slots.iter().position(|piece| (0..S).any(|turn| (0..S).all(|k| colors[(turn + k) % S] == piece[k].0)))
+    }).collect::<Vec<Option<usize>>>();
+    slots.iter().zip(&pieces).filter(|(_, piece)| piece.is_none() || pieces.iter().filter(|other| other == piece).count() > 1)
+        .map(|(slot, _)| slot.iter().map(|&(face, index)| name(face, index)).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{state::State, puzzles::{n_cube::{NCube, NCubeAction}, two_phase::TwoPhaseSolver}};
+
+    use super::*;
+
+    const SOLVED: &str = "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+
+    #[test]
+    fn reads_the_solved_cube() {
+        assert!(CubeState::from_facelets(SOLVED) == Ok(CubeState::default()));
+        assert_eq!(CubeState::default().to_facelets(), SOLVED);
+        // the same in colors, split up by face
+        let colors = "WWWWWWWWW GGGGGGGGG RRRRRRRRR YYYYYYYYY BBBBBBBBB OOOOOOOOO";
+        assert!(CubeState::from_facelets(colors) == Ok(CubeState::default()));
+    }
+
+    /// A real R brings F up onto U, which is R' for CubeState
+    #[test]
+    fn reads_real_turns() {
+        let r = "UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB";
+        assert!(CubeState::from_facelets(r) == Ok(CubeState::default().perform_action(&CubeAction::RPrime)));
+        let u = "UUUUUUUUUBBBRRRRRRRRRFFFFFFDDDDDDDDDFFFLLLLLLLLLBBBBBB";
+        assert!(CubeState::from_facelets(u) == Ok(CubeState::default().perform_action(&CubeAction::UPrime)));
+    }
+
+    /// NCube<3> lays its stickers out like the facelet string and turns like a real cube
+    #[test]
+    fn matches_a_real_cube() {
+        let mut rng = StdRng::seed_from_u64(25);
+        for _ in 0..10 {
+            let (_, scramble) = NCube::<3>::scrambled_with(&mut rng, 25);
+            let outer = scramble.into_iter().filter(|action| action.get_layer() == 0).collect::<Vec<_>>();
+            let cube = outer.iter().fold(NCube::<3>::default(), |cube, action| cube.perform_action(action));
+            let facelets = FACES.iter().flat_map(|&face| (0..9).map(move |i| (face, i))).map(|(face, i)| {
+                let color = cube.get_sticker_at(face, i / 3, i % 3) as usize;
+                ORDER[FACES.iter().position(|&f| f == color).unwrap()]
+            }).collect::<String>();
+            // NCube numbers its faces like CubeState does, not like CubeAction
+            let state = outer.iter().fold(CubeState::default(), |state, action| {
+                let face = [0, 1, 4, 5, 2, 3][action.get_face()];
+                state.perform_action(&CubeAction::from_face(face, action.get_quarter_turns()).unwrap().inverse())
+            });
+            assert!(CubeState::from_facelets(&facelets) == Ok(state.clone()));
+            assert_eq!(state.to_facelets(), facelets);
+        }
+    }
+
+    /// A real cube read in and solved, with the real turns of the solution done on it
+    #[test]
+    fn solutions_work_on_a_real_cube() {
+        let solver = TwoPhaseSolver::new();
+        let mut rng = StdRng::seed_from_u64(25);
+        for _ in 0..5 {
+            let (_, scramble) = NCube::<3>::scrambled_with(&mut rng, 25);
+            let cube = scramble.into_iter().filter(|action| action.get_layer() == 0).fold(NCube::<3>::default(), |cube, action| cube.perform_action(&action));
+            let facelets = FACES.iter().flat_map(|&face| (0..9).map(move |i| (face, i))).map(|(face, i)| {
+                let color = cube.get_sticker_at(face, i / 3, i % 3) as usize;
+                ORDER[FACES.iter().position(|&f| f == color).unwrap()]
+            }).collect::<String>();
+            let solution = solver.solve(&CubeState::from_facelets(&facelets).unwrap(), 30).unwrap();
+            // CubeAction and NCubeAction write the face turns the same way
+            let cube = real_turns(&solution).iter().fold(cube, |cube, action| cube.perform_action(&action.to_string().parse::<NCubeAction<3>>().unwrap()));
+            assert!(cube.is_goal_state(), "{}", facelets);
+        }
+    }
+
+    #[test]
+    fn round_trips_scrambles() {
+        let mut rng = StdRng::seed_from_u64(25);
+        for _ in 0..20 {
+            let (state, _) = CubeState::scrambled_cube_with(&mut rng, 30);
+            assert!(CubeState::from_facelets(&state.to_facelets()) == Ok(state));
+        }
+    }
+
+    #[test]
+    fn reports_what_is_wrong() {
+        let with = |changes: &[(usize, char)]| {
+            let mut facelets = SOLVED.chars().collect::<Vec<char>>();
+            for &(i, sticker) in changes {
+                facelets[i] = sticker;
+            }
+            CubeState::from_facelets(&facelets.into_iter().collect::<String>())
+        };
+        assert!(CubeState::from_facelets("UUU") == Err(FaceletError::Length(3)));
+        // F5 and R5 swapped
+        assert!(with(&[(13, 'F'), (22, 'R')]) == Err(FaceletError::Centers(vec![String::from("R5"), String::from("F5")])));
+        assert!(with(&[(0, 'X')]) == Err(FaceletError::Colors(vec![String::from("U1")])));
+        assert!(with(&[(0, 'R')]) == Err(FaceletError::Counts(vec![('U', 8), ('R', 10)])));
+        // U9 and F1 swapped, so U9-R1-F3 and U7-L3-F1 both have two stickers of the same color
+        assert!(with(&[(8, 'F'), (18, 'U')]) == Err(FaceletError::Pieces(vec![
+            vec![String::from("U9"), String::from("F3"), String::from("R1")],
+            vec![String::from("U7"), String::from("L3"), String::from("F1")],
+        ])));
+        // a corner turned in place
+        assert!(with(&[(8, 'F'), (9, 'U'), (20, 'R')]) == Err(FaceletError::Twist));
+        // an edge flipped in place
+        assert!(with(&[(7, 'F'), (19, 'U')]) == Err(FaceletError::Flip));
+        // UF and UR swapped
+        assert!(with(&[(10, 'F'), (19, 'R')]) == Err(FaceletError::Parity));
+    }
+
+}
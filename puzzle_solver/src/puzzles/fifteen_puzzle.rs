@@ -1,8 +1,13 @@
 // Author: Harper Davis
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
-use crate::{state::State, action::Action};
-#[derive(PartialEq, Clone)]
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{state::State, action::{Action, InvertibleAction}, heuristic::Heuristic};
+
+use super::fifteen_puzzle_heuristics::ManhattanDistance;
+
+#[derive(PartialEq, Eq, Clone, Hash)]
 pub struct FifteenPuzzle {
     board: u64
 }
@@ -13,14 +18,42 @@ impl FifteenPuzzle {
         FifteenPuzzle { board }
     }
 
-    pub fn default() -> FifteenPuzzle {
-        FifteenPuzzle { board: Self::default_board() }
-    }
-
     fn default_board() -> u64 {
         0xfedcba9876543210
     }
 
+    /// A board picked evenly out of all the solvable ones
+    pub fn random_with<R: Rng + ?Sized>(rng: &mut R) -> FifteenPuzzle {
+        let mut pieces = (0..16).collect::<Vec<u64>>();
+        pieces.shuffle(rng);
+        // swapping two pieces flips the inversion parity, which turns exactly the unsolvable half into the solvable half
+        if !FifteenPuzzle::from_pieces(&pieces).is_solvable() {
+            let (first, second) = if pieces[0] == 0 || pieces[1] == 0 { (2, 3) } else { (0, 1) };
+            pieces.swap(first, second);
+        }
+        FifteenPuzzle::from_pieces(&pieces)
+    }
+
+    fn from_pieces(pieces: &[u64]) -> FifteenPuzzle {
+        FifteenPuzzle::new(pieces.iter().enumerate().fold(0, |board, (i, piece)| board | piece << (i * 4)))
+    }
+
+    /// The board after a random walk of `action_count` moves from the goal, never moving the same piece twice in a
+    /// row, along with the moves that undo it
+    pub fn scrambled_with<R: Rng + ?Sized>(rng: &mut R, action_count: u32) -> (FifteenPuzzle, Vec<FifteenPuzzleAction>) {
+        let mut puzzle = FifteenPuzzle::default();
+        let mut actions: Vec<FifteenPuzzleAction> = Vec::new();
+        for _ in 0..action_count {
+            let mut next_actions = puzzle.list_actions();
+            next_actions.retain(|action| Some(action) != actions.last());
+            let action = next_actions.choose(rng).unwrap().clone();
+            puzzle = puzzle.perform_action(&action);
+            actions.push(action);
+        }
+        actions.reverse();
+        (puzzle, actions)
+    }
+
     pub fn get_piece(&self, index: u8) -> u8 {
         ((self.board >> (index * 4)) % 16) as u8
     }
@@ -29,7 +62,37 @@ impl FifteenPuzzle {
         for i in 0..16 {
             if ((self.board >> (i * 4)) % 16) as u8 == piece { return i }
         }
-        return 0;
+        0
+    }
+
+    /// Every piece from 0 to 15 shows up exactly once
+    pub fn is_valid(&self) -> bool {
+        let mut seen = 0_u16;
+        for i in 0..16 {
+            seen |= 1 << self.get_piece(i);
+        }
+        seen == 0xffff
+    }
+
+    /// Parity of the number of pairs of pieces (not counting the blank) that are out of order
+    pub fn inversion_parity(&self) -> u8 {
+        let mut inversions = 0;
+        for i in 0..16 {
+            let piece = self.get_piece(i);
+            if piece == 0 { continue; }
+            for j in (i + 1)..16 {
+                let other = self.get_piece(j);
+                if other != 0 && other < piece {
+                    inversions += 1;
+                }
+            }
+        }
+        inversions % 2
+    }
+
+    /// Row of the blank, counted from the row it sits in when solved
+    pub fn blank_row(&self) -> u8 {
+        self.find_piece_index(0) / 4
     }
 
     pub fn move_piece(&self, piece: u8) -> u64 {
@@ -49,10 +112,31 @@ impl FifteenPuzzle {
 
 }
 
-impl ToString for FifteenPuzzle {
+impl fmt::Display for FifteenPuzzle {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#018x}", self.board)
+    }
+
+}
 
-    fn to_string(&self) -> String {
-        format!("{:#018x}", self.board)
+/// The same 0x… hex board that Display writes, anything that isn't a valid board is an error
+impl FromStr for FifteenPuzzle {
+
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let board = u64::from_str_radix(s.trim().trim_start_matches("0x"), 16).map_err(|_| ())?;
+        let puzzle = FifteenPuzzle::new(board);
+        if puzzle.is_valid() { Ok(puzzle) } else { Err(()) }
+    }
+
+}
+
+impl Default for FifteenPuzzle {
+
+    fn default() -> FifteenPuzzle {
+        FifteenPuzzle { board: Self::default_board() }
     }
 
 }
@@ -69,8 +153,7 @@ impl State for FifteenPuzzle {
                 if piece == 0 {
                     print!("   |");
                 } else {
-                    let piece_char = &format!("{:X}",piece)[2..];
-                    print!(" {} |", piece_char);
+                    print!(" {:X} |", piece);
                 }
             }
             println!();
@@ -107,21 +190,19 @@ impl State for FifteenPuzzle {
         self.board == Self::default_board()
     }
 
-    fn heuristic(&self) -> f64 {
-        let mut distance = 0.0;
-        for i in 0..16 {
-            let piece = self.get_piece(i);
-            if piece == 0 { continue; }
-
-            let x = i % 4;
-            let y = i / 4;
+    fn goal_states(&self) -> Vec<Self> {
+        vec![FifteenPuzzle::default()]
+    }
 
-            let goal_x = (piece) % 4;
-            let goal_y = (piece) / 4;
+    fn heuristic(&self) -> f64 {
+        ManhattanDistance.estimate(self)
+    }
 
-            distance += ((x as i8 - goal_x as i8).abs() + (y as i8 - goal_y as i8).abs()) as f64;
-        }
-        distance
+    /// Sliding a piece sideways never changes the order of the pieces, and sliding one vertically
+    /// moves it past three others, so the inversion parity flips exactly when the blank changes rows.
+    /// Their sum is therefore fixed, and it is even for the goal.
+    fn is_solvable(&self) -> bool {
+        self.is_valid() && self.inversion_parity() == self.blank_row() % 2
     }
 }
 
@@ -137,10 +218,10 @@ impl FifteenPuzzleAction {
     }
 }
 
-impl ToString for FifteenPuzzleAction {
+impl fmt::Display for FifteenPuzzleAction {
 
-    fn to_string(&self) -> String {
-        format!("{:#x}", self.piece)[2..].to_string()
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:x}", self.piece)
     }
 
 }
@@ -165,4 +246,99 @@ impl Action for FifteenPuzzleAction {
         1.0
     }
 
-}
\ No newline at end of file
+}
+
+impl InvertibleAction for FifteenPuzzleAction {
+
+    /// Sliding the same piece again puts it back where it was
+    fn inverse(&self) -> Self {
+        self.clone()
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, VecDeque};
+
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    use crate::puzzles::fifteen_puzzle_heuristics::{MisplacedTiles, LinearConflict, WalkingDistance};
+
+    use super::*;
+
+    /// The exact distance to the goal of every board within depth moves of it
+    fn distances_from_goal(depth: u32) -> HashMap<FifteenPuzzle, u32> {
+        let mut distances = HashMap::from([(FifteenPuzzle::default(), 0)]);
+        let mut queue = VecDeque::from([FifteenPuzzle::default()]);
+        while let Some(state) = queue.pop_front() {
+            let distance = distances[&state];
+            if distance == depth {
+                continue;
+            }
+            for action in state.list_actions() {
+                let next_state = state.perform_action(&action);
+                if !distances.contains_key(&next_state) {
+                    distances.insert(next_state.clone(), distance + 1);
+                    queue.push_back(next_state);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Checks that a move slid a piece next to the blank into it and left everything else alone
+    fn assert_legal_move(before: &FifteenPuzzle, action: &FifteenPuzzleAction, after: &FifteenPuzzle) {
+        let piece_index = before.find_piece_index(action.piece);
+        let open_index = before.find_piece_index(0);
+        let (piece_x, piece_y) = (piece_index % 4, piece_index / 4);
+        let (open_x, open_y) = (open_index % 4, open_index / 4);
+        assert_eq!(piece_x.abs_diff(open_x) + piece_y.abs_diff(open_y), 1, "{} isn't next to the blank in {}", action, before);
+
+        assert!(after.is_valid(), "{} after {}", after, action);
+        assert_eq!(after.get_piece(open_index), action.piece);
+        assert_eq!(after.get_piece(piece_index), 0);
+        for i in (0..16).filter(|&i| i != open_index && i != piece_index) {
+            assert_eq!(after.get_piece(i), before.get_piece(i), "{} moved more than {} in {}", action, action.piece, before);
+        }
+    }
+
+    #[test]
+    fn random_walks_only_make_legal_moves() {
+        let mut rng = StdRng::seed_from_u64(21);
+        for _ in 0..100 {
+            let mut state = FifteenPuzzle::default();
+            for _ in 0..100 {
+                let actions = state.list_actions();
+                assert!((2..=4).contains(&actions.len()), "{} moves from {}", actions.len(), state);
+                for action in &actions {
+                    let next_state = state.perform_action(action);
+                    assert_legal_move(&state, action, &next_state);
+                    assert!(next_state.perform_action(&action.inverse()) == state, "{} doesn't undo itself from {}", action, state);
+                    assert!(next_state.is_solvable(), "{} from {}", action, state);
+                }
+                state = state.perform_action(actions.choose(&mut rng).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn heuristic_is_admissible() {
+        for (state, distance) in distances_from_goal(14) {
+            assert!(state.heuristic() <= distance as f64, "{} for {}, which is {} moves away", state.heuristic(), state, distance);
+            assert_eq!(state.heuristic() == 0.0, distance == 0, "{}", state);
+        }
+    }
+
+    #[test]
+    fn other_heuristics_are_admissible() {
+        let heuristics: [(&str, &dyn Heuristic<FifteenPuzzle>); 3] = [("misplaced tiles", &MisplacedTiles), ("linear conflict", &LinearConflict), ("walking distance", &WalkingDistance)];
+        for (state, distance) in distances_from_goal(12) {
+            for (name, heuristic) in heuristics {
+                assert!(heuristic.estimate(&state) <= distance as f64, "{} {} for {}, which is {} moves away", name, heuristic.estimate(&state), state, distance);
+            }
+        }
+    }
+
+}
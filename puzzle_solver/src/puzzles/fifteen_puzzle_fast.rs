@@ -1,54 +1,122 @@
 // Author: Harper Davis
-// Unused
+use std::{fmt, str::FromStr};
+
 use crate::state::State;
 
-use super::fifteen_puzzle::FifteenPuzzleAction;
+use super::fifteen_puzzle::{FifteenPuzzle, FifteenPuzzleAction};
 
-#[derive(PartialEq, Clone)]
+/// The same puzzle as `FifteenPuzzle`, but it also keeps where every piece is and the Manhattan
+/// distance, so moves and the heuristic don't have to search the board
+#[derive(PartialEq, Eq, Clone, Hash)]
 pub struct FifteenPuzzleFast {
-    board_index: u64,
+    /// Nibble i is the piece at index i, the same as `FifteenPuzzle`
     board_pieces: u64,
+    /// Nibble i is the index of piece i
+    board_index: u64,
+    manhattan: u8,
 }
 
 impl FifteenPuzzleFast {
-    
-    pub fn new(board_index: u64, board_pieces: u64) -> Self {
-        FifteenPuzzleFast { board_index, board_pieces }
-    }
 
     pub fn from_pieces(board_pieces: u64) -> Self {
         let mut board_index = 0;
+        let mut manhattan = 0;
         for i in 0..16 {
             let piece = (board_pieces >> (i * 4)) & 0b1111;
             board_index |= i << (piece * 4);
+            if piece != 0 {
+                manhattan += Self::distance(i as u8, piece as u8);
+            }
         }
-        FifteenPuzzleFast { board_index, board_pieces }
-    }
-
-    pub fn default() -> Self {
-        FifteenPuzzleFast { board_index: Self::default_board(), board_pieces: Self::default_board() }
+        FifteenPuzzleFast { board_pieces, board_index, manhattan }
     }
 
     fn default_board() -> u64 {
         0xfedcba9876543210
     }
 
+    pub fn get_board(&self) -> u64 {
+        self.board_pieces
+    }
+
+    pub fn get_piece(&self, index: u8) -> u8 {
+        ((self.board_pieces >> (index * 4)) & 0b1111) as u8
+    }
+
+    pub fn find_piece_index(&self, piece: u8) -> u8 {
+        ((self.board_index >> (piece * 4)) & 0b1111) as u8
+    }
+
+    /// Where every piece is, indexed by piece
+    pub fn piece_indices(&self) -> [u8; 16] {
+        let mut piece_indices = [0_u8; 16];
+        for (piece, index) in piece_indices.iter_mut().enumerate() {
+            *index = self.find_piece_index(piece as u8);
+        }
+        piece_indices
+    }
+
+    pub fn get_manhattan_distance(&self) -> u8 {
+        self.manhattan
+    }
+
+    /// How far `piece` at `index` is from home
+    fn distance(index: u8, piece: u8) -> u8 {
+        (index % 4).abs_diff(piece % 4) + (index / 4).abs_diff(piece / 4)
+    }
+
+    /// Only the moved piece changes how far it is from home, so the distance is patched rather than added up again
     fn move_piece(&self, piece: u8) -> Self {
         let piece_index = (self.board_index >> (piece * 4)) & 0b1111;
         let open_index = (self.board_index) & 0b1111;
 
         let new_board_pieces = self.board_pieces & !(0b1111 << (piece_index * 4)) & !(0b1111 << (open_index * 4)) | (piece as u64) << (open_index * 4);
         let new_board_index = self.board_index & !(0b1111 << (piece * 4)) & !(0b1111) | open_index << (piece * 4) | piece_index;
+        let manhattan = self.manhattan + Self::distance(open_index as u8, piece) - Self::distance(piece_index as u8, piece);
+
+        FifteenPuzzleFast { board_pieces: new_board_pieces, board_index: new_board_index, manhattan }
+    }
 
-        FifteenPuzzleFast { board_index: new_board_index, board_pieces: new_board_pieces }
+}
+
+impl Default for FifteenPuzzleFast {
+
+    fn default() -> FifteenPuzzleFast {
+        FifteenPuzzleFast { board_pieces: Self::default_board(), board_index: Self::default_board(), manhattan: 0 }
+    }
+
+}
+
+impl From<&FifteenPuzzle> for FifteenPuzzleFast {
+
+    fn from(puzzle: &FifteenPuzzle) -> FifteenPuzzleFast {
+        FifteenPuzzleFast::from_pieces((0..16).fold(0, |board, i| board | (puzzle.get_piece(i) as u64) << (i * 4)))
+    }
+
+}
+
+impl From<&FifteenPuzzleFast> for FifteenPuzzle {
+
+    fn from(puzzle: &FifteenPuzzleFast) -> FifteenPuzzle {
+        FifteenPuzzle::new(puzzle.get_board())
     }
 
 }
 
-impl ToString for FifteenPuzzleFast {
+impl fmt::Display for FifteenPuzzleFast {
 
-    fn to_string(&self) -> String {
-        format!("{:#018x}/{:#018x}", self.board_pieces, self.board_index)
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#018x}", self.board_pieces)
+    }
+
+}
+
+impl FromStr for FifteenPuzzleFast {
+
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<FifteenPuzzle>().map(|puzzle| FifteenPuzzleFast::from(&puzzle))
     }
 
 }
@@ -61,12 +129,11 @@ impl State for FifteenPuzzleFast {
         for i in 0..4 {
             print!("|");
             for j in 0..4 {
-                let piece = self.board_pieces >> (i * 4 + j * 16) & 0b1111;
+                let piece = self.get_piece(15 - (i * 4 + j));
                 if piece == 0 {
                     print!("   |");
                 } else {
-                    let piece_char = &format!("{:X}", piece)[2..];
-                    print!(" {} |", piece_char);
+                    print!(" {:X} |", piece);
                 }
             }
             println!();
@@ -74,46 +141,168 @@ impl State for FifteenPuzzleFast {
         }
     }
 
+    /// In the same order as `FifteenPuzzle`, so that searches go through both the same way
     fn list_actions(&self) -> Vec<Self::Action> {
         let mut actions = Vec::new();
-        let open_index = (self.board_index) & 0b1111;
-        if open_index > 3 {
-            actions.push(FifteenPuzzleAction::new((self.board_pieces >> ((open_index - 4) * 4) & 0b1111) as u8));
+        let open_index = self.find_piece_index(0);
+        let x = open_index % 4;
+        if x > 0 {
+            actions.push(FifteenPuzzleAction::new(self.get_piece(open_index - 1)));
         }
-        if open_index < 12 {
-            actions.push(FifteenPuzzleAction::new((self.board_pieces >> ((open_index + 4) * 4) & 0b1111) as u8));
+        if x < 3 {
+            actions.push(FifteenPuzzleAction::new(self.get_piece(open_index + 1)));
         }
-        if open_index % 4 > 0 {
-            actions.push(FifteenPuzzleAction::new((self.board_pieces >> ((open_index - 1) * 4) & 0b1111) as u8));
+        if open_index > 3 {
+            actions.push(FifteenPuzzleAction::new(self.get_piece(open_index - 4)));
         }
-        if open_index % 4 < 3 {
-            actions.push(FifteenPuzzleAction::new((self.board_pieces >> ((open_index + 1) * 4) & 0b1111) as u8));
+        if open_index < 12 {
+            actions.push(FifteenPuzzleAction::new(self.get_piece(open_index + 4)));
         }
         actions
     }
 
     fn heuristic(&self) -> f64 {
-        let mut manhattan = 0.0;
-        for i in 0..16 {
-            let pos = ((self.board_index >> (i * 4)) & 0b1111) as u8;
-            
-            let goal_x = i % 4;
-            let goal_y = i / 4;
+        self.manhattan as f64
+    }
+
+    fn is_goal_state(&self) -> bool {
+        self.board_pieces == Self::default_board()
+    }
+
+    fn goal_states(&self) -> Vec<Self> {
+        vec![FifteenPuzzleFast::default()]
+    }
+
+    fn perform_action(&self, action: &Self::Action) -> Self {
+        self.move_piece(action.piece)
+    }
+
+    fn is_solvable(&self) -> bool {
+        FifteenPuzzle::from(self).is_solvable()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 
-            let x = pos % 4;
-            let y = pos / 4;
+    use crate::{search::{Search, SearchOutcome, IterativeDeepeningAStarSearch}, frontier::StackFrontier, limits::{SearchLimits, Limit}, statistics::SearchStatistics, puzzles::pattern_database::PatternDatabaseHeuristic, suite::read_fifteen_puzzle_test_cases};
 
-            manhattan += ((goal_x as i8 - x as i8).abs() + (goal_y as i8 - y as i8).abs()) as f64;
+    use super::*;
+
+    /// Depth, start and path of every case in tests/15_puzzle.txt
+    fn read_test_cases() -> Vec<(u32, FifteenPuzzle, Vec<FifteenPuzzleAction>)> {
+        read_fifteen_puzzle_test_cases("./tests/15_puzzle.txt").unwrap().into_iter().map(|test_case| {
+            (test_case.get_depth_limit(), test_case.get_start_state().clone(), test_case.get_correct_path().clone())
+        }).collect()
+    }
+
+    /// Both puzzles agree on everything a search can see, here and one move away
+    fn assert_same(slow: &FifteenPuzzle, fast: &FifteenPuzzleFast) {
+        assert_eq!(slow.to_string(), fast.to_string());
+        assert_eq!(slow.heuristic(), fast.heuristic(), "heuristic of {}", slow);
+        assert_eq!(slow.is_goal_state(), fast.is_goal_state());
+        assert_eq!(slow.is_solvable(), fast.is_solvable());
+        assert!(FifteenPuzzleFast::from(slow) == *fast);
+
+        let slow_actions = slow.list_actions();
+        let fast_actions = fast.list_actions();
+        assert_eq!(slow_actions.iter().map(|action| action.piece).collect::<Vec<u8>>(), fast_actions.iter().map(|action| action.piece).collect::<Vec<u8>>(), "actions of {}", slow);
+        for action in &slow_actions {
+            let (slow_next, fast_next) = (slow.perform_action(action), fast.perform_action(action));
+            assert_eq!(slow_next.to_string(), fast_next.to_string());
+            assert_eq!(slow_next.heuristic(), fast_next.heuristic());
         }
+    }
 
-        manhattan
+    fn solution_length<S: State<Action = FifteenPuzzleAction>, E: Search<S, FifteenPuzzleAction, StackFrontier<S, FifteenPuzzleAction>>>(start_state: S, depth: u32) -> u32 {
+        match E::new(depth as f64).search(start_state) {
+            SearchOutcome::Solved(solution) => solution.path_length(),
+            _ => panic!("no solution within depth {}", depth),
+        }
     }
 
-    fn is_goal_state(&self) -> bool {
-        self.board_pieces == Self::default_board()
+    #[test]
+    fn follows_every_path_like_fifteen_puzzle() {
+        for (depth, start_state, path) in read_test_cases() {
+            assert_eq!(path.len() as u32, depth);
+            let mut slow = start_state;
+            let mut fast = FifteenPuzzleFast::from(&slow);
+            for action in &path {
+                assert_same(&slow, &fast);
+                slow = slow.perform_action(action);
+                fast = fast.perform_action(action);
+            }
+            assert_same(&slow, &fast);
+            assert!(fast.is_goal_state());
+        }
     }
 
-    fn perform_action(&self, action: &Self::Action) -> Self {
-        self.move_piece(action.piece)    
+    #[test]
+    fn random_walks_keep_the_manhattan_distance() {
+        let mut rng = StdRng::seed_from_u64(16);
+        for _ in 0..200 {
+            let mut slow = FifteenPuzzle::default();
+            let mut fast = FifteenPuzzleFast::default();
+            for _ in 0..200 {
+                let action = slow.list_actions().choose(&mut rng).unwrap().clone();
+                slow = slow.perform_action(&action);
+                fast = fast.perform_action(&action);
+                assert_same(&slow, &fast);
+                assert!(fast == FifteenPuzzleFast::from_pieces(fast.get_board()));
+            }
+        }
+    }
+
+    #[test]
+    fn same_solution_lengths_on_shallow_cases() {
+        for (depth, start_state, _) in read_test_cases().into_iter().filter(|(depth, _, _)| *depth <= 24) {
+            let fast = FifteenPuzzleFast::from(&start_state);
+            let slow_length = solution_length::<FifteenPuzzle, IterativeDeepeningAStarSearch<FifteenPuzzle, FifteenPuzzleAction>>(start_state, depth);
+            let fast_length = solution_length::<FifteenPuzzleFast, IterativeDeepeningAStarSearch<FifteenPuzzleFast, FifteenPuzzleAction>>(fast, depth);
+            assert_eq!(slow_length, depth);
+            assert_eq!(fast_length, slow_length);
+        }
     }
-}
\ No newline at end of file
+
+    /// Solving every case takes minutes even with the pattern databases (see the ignored test below), so by default
+    /// both puzzles get the same budget on each one and have to go through exactly the same search in it
+    #[test]
+    fn same_searches_on_every_case() {
+        for (depth, start_state, _) in read_test_cases() {
+            let fast = FifteenPuzzleFast::from(&start_state);
+            let limits = SearchLimits::none().with_max_nodes(2000);
+            let slow_outcome = IterativeDeepeningAStarSearch::<FifteenPuzzle, FifteenPuzzleAction>::new(depth as f64).search_with_limits(start_state, &limits);
+            let fast_outcome = IterativeDeepeningAStarSearch::<FifteenPuzzleFast, FifteenPuzzleAction>::new(depth as f64).search_with_limits(fast, &limits);
+            match (slow_outcome, fast_outcome) {
+                (SearchOutcome::Solved(slow), SearchOutcome::Solved(fast)) => {
+                    assert_eq!(slow.path_length(), depth);
+                    assert_eq!(fast.path_length(), depth);
+                    assert_same_statistics(slow.get_statistics(), fast.get_statistics());
+                },
+                (SearchOutcome::LimitReached(Limit::Nodes, slow), SearchOutcome::LimitReached(Limit::Nodes, fast)) => assert_same_statistics(&slow, &fast),
+                _ => panic!("the searches ended differently at depth {}", depth),
+            }
+        }
+    }
+
+    /// Every case in the file solved all the way, which takes the pattern databases and a while even so.
+    /// Run it with `cargo test --release -- --ignored same_solution_lengths_on_every_case`
+    #[test]
+    #[ignore]
+    fn same_solution_lengths_on_every_case() {
+        for (depth, start_state, _) in read_test_cases() {
+            let fast = FifteenPuzzleFast::from(&start_state);
+            let slow_length = solution_length::<FifteenPuzzle, IterativeDeepeningAStarSearch<FifteenPuzzle, FifteenPuzzleAction, PatternDatabaseHeuristic>>(start_state, depth);
+            let fast_length = solution_length::<FifteenPuzzleFast, IterativeDeepeningAStarSearch<FifteenPuzzleFast, FifteenPuzzleAction, PatternDatabaseHeuristic>>(fast, depth);
+            assert_eq!(slow_length, depth);
+            assert_eq!(fast_length, slow_length);
+        }
+    }
+
+    fn assert_same_statistics(slow: &SearchStatistics, fast: &SearchStatistics) {
+        assert_eq!(slow.get_nodes_generated(), fast.get_nodes_generated());
+        assert_eq!(slow.get_nodes_expanded(), fast.get_nodes_expanded());
+        assert_eq!(slow.get_iterations(), fast.get_iterations());
+    }
+}
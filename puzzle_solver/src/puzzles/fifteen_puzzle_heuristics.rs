@@ -0,0 +1,183 @@
+// Author: Harper Davis
+use std::{collections::{HashMap, VecDeque, hash_map::Entry}, sync::OnceLock};
+
+use crate::heuristic::{Heuristic, PairwiseHeuristic};
+
+use super::fifteen_puzzle::FifteenPuzzle;
+
+/// Number of pieces (not counting the blank) that aren't where they belong
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MisplacedTiles;
+
+impl Heuristic<FifteenPuzzle> for MisplacedTiles {
+    fn estimate(&self, state: &FifteenPuzzle) -> f64 {
+        (0..16).filter(|&i| {
+            let piece = state.get_piece(i);
+            piece != 0 && piece != i
+        }).count() as f64
+    }
+}
+
+/// How far every piece is from its goal square, going only up, down, left and right
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ManhattanDistance;
+
+impl Heuristic<FifteenPuzzle> for ManhattanDistance {
+    fn estimate(&self, state: &FifteenPuzzle) -> f64 {
+        let mut distance = 0.0;
+        for i in 0..16 {
+            let piece = state.get_piece(i);
+            if piece == 0 { continue; }
+
+            let x = i % 4;
+            let y = i / 4;
+
+            let goal_x = piece % 4;
+            let goal_y = piece / 4;
+
+            distance += ((x as i8 - goal_x as i8).abs() + (y as i8 - goal_y as i8).abs()) as f64;
+        }
+        distance
+    }
+}
+
+impl PairwiseHeuristic<FifteenPuzzle> for ManhattanDistance {
+    fn estimate_between(&self, from: &FifteenPuzzle, to: &FifteenPuzzle) -> f64 {
+        let mut goal_indices = [0_u8; 16];
+        for i in 0..16 {
+            goal_indices[to.get_piece(i) as usize] = i;
+        }
+
+        let mut distance = 0.0;
+        for i in 0..16 {
+            let piece = from.get_piece(i);
+            if piece == 0 { continue; }
+
+            let goal = goal_indices[piece as usize];
+            distance += ((i % 4) as i8 - (goal % 4) as i8).abs() as f64 + ((i / 4) as i8 - (goal / 4) as i8).abs() as f64;
+        }
+        distance
+    }
+}
+
+/// Manhattan distance, plus two moves for every piece that has to get out of the way of another
+/// piece in its goal row or column. Within a line, all the pieces outside of the longest run that
+/// is already in the right order have to leave the line and come back
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LinearConflict;
+
+impl Heuristic<FifteenPuzzle> for LinearConflict {
+    fn estimate(&self, state: &FifteenPuzzle) -> f64 {
+        let mut conflicts = 0;
+        for line in 0..4 {
+            let mut row = Vec::new();
+            let mut column = Vec::new();
+            for k in 0..4 {
+                let piece = state.get_piece(line * 4 + k);
+                if piece != 0 && piece / 4 == line {
+                    row.push(piece % 4);
+                }
+                let piece = state.get_piece(k * 4 + line);
+                if piece != 0 && piece % 4 == line {
+                    column.push(piece / 4);
+                }
+            }
+            conflicts += row.len() - longest_increasing_run(&row);
+            conflicts += column.len() - longest_increasing_run(&column);
+        }
+        ManhattanDistance.estimate(state) + 2.0 * conflicts as f64
+    }
+}
+
+/// Length of the longest increasing subsequence, the lines are at most 4 long so this doesn't need to be clever
+fn longest_increasing_run(goals: &[u8]) -> usize {
+    let mut longest = vec![1; goals.len()];
+    for i in 0..goals.len() {
+        for j in 0..i {
+            if goals[j] < goals[i] {
+                longest[i] = usize::max(longest[i], longest[j] + 1);
+            }
+        }
+    }
+    longest.into_iter().max().unwrap_or(0)
+}
+
+/// Ken'ichiro Takahashi's walking distance. Only which row each piece belongs in is kept track of,
+/// and the fewest vertical moves needed to sort every piece into its row comes from a table built by
+/// searching back from the goal. The same is done for columns, and the two can be added since every
+/// move is either vertical or horizontal
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WalkingDistance;
+
+impl Heuristic<FifteenPuzzle> for WalkingDistance {
+    fn estimate(&self, state: &FifteenPuzzle) -> f64 {
+        let table = walking_distance_table();
+
+        let mut rows = [[0_u8; 4]; 4];
+        let mut columns = [[0_u8; 4]; 4];
+        let mut blank_row = 0;
+        let mut blank_column = 0;
+        for i in 0..16 {
+            let piece = state.get_piece(i);
+            if piece == 0 {
+                blank_row = i / 4;
+                blank_column = i % 4;
+                continue;
+            }
+            rows[(i / 4) as usize][(piece / 4) as usize] += 1;
+            columns[(i % 4) as usize][(piece % 4) as usize] += 1;
+        }
+
+        // the blank belongs in the first row and the first column, so the columns turned on their side look just like rows
+        let row_distance = table[&encode_walking_state(&rows, blank_row)];
+        let column_distance = table[&encode_walking_state(&columns, blank_column)];
+        (row_distance + column_distance) as f64
+    }
+}
+
+/// counts[line][goal] pieces sitting in `line` that belong in `goal`, 3 bits each, with the blank's line on top
+fn encode_walking_state(counts: &[[u8; 4]; 4], blank: u8) -> u64 {
+    let mut key = blank as u64;
+    for line in counts {
+        for &count in line {
+            key = (key << 3) | count as u64;
+        }
+    }
+    key
+}
+
+fn walking_distance_table() -> &'static HashMap<u64, u8> {
+    static TABLE: OnceLock<HashMap<u64, u8>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut goal = [[0_u8; 4]; 4];
+        for (line, counts) in goal.iter_mut().enumerate() {
+            counts[line] = if line == 0 { 3 } else { 4 };
+        }
+
+        let mut table = HashMap::new();
+        table.insert(encode_walking_state(&goal, 0), 0);
+        let mut queue = VecDeque::new();
+        queue.push_back((goal, 0_u8));
+
+        while let Some((counts, blank)) = queue.pop_front() {
+            let distance = table[&encode_walking_state(&counts, blank)];
+            let neighbours = [blank.checked_sub(1), (blank < 3).then_some(blank + 1)];
+            for next_blank in neighbours.into_iter().flatten() {
+                // any piece in the next line over can slide into the blank's line
+                for goal_line in 0..4 {
+                    if counts[next_blank as usize][goal_line] == 0 { continue; }
+                    let mut next = counts;
+                    next[next_blank as usize][goal_line] -= 1;
+                    next[blank as usize][goal_line] += 1;
+
+                    let key = encode_walking_state(&next, next_blank);
+                    if let Entry::Vacant(entry) = table.entry(key) {
+                        entry.insert(distance + 1);
+                        queue.push_back((next, next_blank));
+                    }
+                }
+            }
+        }
+        table
+    })
+}
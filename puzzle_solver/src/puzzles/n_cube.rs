@@ -0,0 +1,657 @@
+// Author: Harper Davis
+use std::{collections::{HashMap, HashSet}, fmt, hash::Hash, str::FromStr, sync::{OnceLock, RwLock}};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{state::State, action::{Action, InvertibleAction}};
+
+use super::rubiks_cube::Color;
+
+/// The faces in the same order as CubeState's, so sticker colors are numbered the same way too
+pub const FACES: [char; 6] = ['U', 'D', 'F', 'B', 'R', 'L'];
+
+/// Which way each face points, with x to the right, y up and z out of the front
+const NORMALS: [[i32; 3]; 6] = [[0, 1, 0], [0, -1, 0], [0, 0, 1], [0, 0, -1], [1, 0, 0], [-1, 0, 0]];
+/// Which ways a face's columns and rows run looking straight at it: U with B at the top, D with F at the top
+/// and the four around the middle with U at the top
+const RIGHTS: [[i32; 3]; 6] = [[1, 0, 0], [1, 0, 0], [1, 0, 0], [-1, 0, 0], [0, 0, -1], [0, 0, 1]];
+const DOWNS: [[i32; 3]; 6] = [[0, 0, 1], [0, 0, -1], [0, -1, 0], [0, -1, 0], [0, -1, 0], [0, -1, 0]];
+
+/// Sticker permutations for each size of cube, which a static inside a generic function can't be since they're shared
+/// by every N
+type Tables = OnceLock<RwLock<HashMap<usize, &'static [Vec<usize>]>>>;
+
+/// The table for cubes of size `n`, built the first time it's asked for and then kept for good
+fn cached(tables: &'static Tables, n: usize, build: impl FnOnce() -> Vec<Vec<usize>>) -> &'static [Vec<usize>] {
+    let tables = tables.get_or_init(Default::default);
+    if let Some(&table) = tables.read().unwrap().get(&n) {
+        return table;
+    }
+    tables.write().unwrap().entry(n).or_insert_with(|| Vec::leak(build()))
+}
+
+fn dot(a: [i32; 3], b: [i32; 3]) -> i32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+/// A quarter turn of `v` clockwise around `axis`, looking at it from the end `axis` points to
+fn rotate(v: [i32; 3], axis: [i32; 3]) -> [i32; 3] {
+    let along = dot(v, axis);
+    let across = cross(axis, v);
+    [axis[0] * along - across[0], axis[1] * along - across[1], axis[2] * along - across[2]]
+}
+
+/// Any size of cube from 2x2x2 up, kept as a sticker a byte. Stickers are numbered face by face in the order of
+/// FACES, then across the rows of each face as it's seen in display_pretty. Every layer can turn, including the
+/// middle one of an odd cube, so nothing stays put and a cube counts as solved whichever way around it ends up
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct NCube<const N: usize> {
+    stickers: Box<[u8]>,
+}
+
+pub type PocketCube = NCube<2>;
+pub type RevengeCube = NCube<4>;
+pub type ProfessorCube = NCube<5>;
+
+impl<const N: usize> NCube<N> {
+
+    const STICKERS: usize = 6 * N * N;
+    /// How many layers in from each face can turn, counting the middle one of an odd cube
+    pub const LAYERS: usize = N.div_ceil(2);
+
+    /// The cube with `stickers[i]` as the color of sticker i, or None unless there are N * N stickers of each color
+    pub fn from_stickers(stickers: &[u8]) -> Option<NCube<N>> {
+        if stickers.len() != Self::STICKERS || (0..6).any(|color| stickers.iter().filter(|&&sticker| sticker == color).count() != N * N) {
+            return None;
+        }
+        Some(NCube { stickers: stickers.into() })
+    }
+
+    pub fn get_sticker(&self, index: usize) -> u8 {
+        self.stickers[index]
+    }
+
+    pub fn get_sticker_at(&self, face: usize, row: usize, column: usize) -> u8 {
+        self.stickers[(face * N + row) * N + column]
+    }
+
+    pub fn scrambled(action_count: u32) -> (NCube<N>, Vec<NCubeAction<N>>) {
+        Self::scrambled_with(&mut rand::thread_rng(), action_count)
+    }
+
+    /// A solved cube after `action_count` random moves, along with the moves. Moves that only cancel or commute with
+    /// the one before are left out, the same as the searches leave them out
+    pub fn scrambled_with<R: Rng + ?Sized>(rng: &mut R, action_count: u32) -> (NCube<N>, Vec<NCubeAction<N>>) {
+        let mut cube = NCube::default();
+        let mut actions: Vec<NCubeAction<N>> = Vec::new();
+        for _ in 0..action_count {
+            let action = *cube.list_actions_after(actions.last()).choose(rng).unwrap();
+            cube = cube.perform_action(&action);
+            actions.push(action);
+        }
+        (cube, actions)
+    }
+
+    /// The same cube turned around as a whole, the same way `face` turns
+    pub fn rotated(&self, face: usize, quarter_turns: u8) -> NCube<N> {
+        self.permuted(&Self::permutation(face, None, quarter_turns))
+    }
+
+    /// The cube held each of the 24 ways it can be
+    pub fn orientations(&self) -> Vec<NCube<N>> {
+        Self::rotations().iter().map(|rotation| self.permuted(rotation)).collect()
+    }
+
+    /// Whether the corners are an odd permutation of where they belong. Only the outer layers move corners,
+    /// so inner layers never change it. None if the stickers don't make up the eight corners
+    pub fn corner_parity(&self) -> Option<bool> {
+        let slots = Self::corner_slots();
+        let homes = self.homes(&slots, |colors| {
+            let mut colors = colors.to_vec();
+            colors.sort();
+            colors
+        })?;
+        Some(is_odd(&homes))
+    }
+
+    /// How far the corners are twisted altogether, counting each one by where its U or D sticker is in
+    /// corner_slots (0 for on U or D, then clockwise). Turns keep it a multiple of 3, the same as on a 3x3x3.
+    /// None if a corner has no U or D sticker
+    pub fn corner_twist(&self) -> Option<usize> {
+        Self::corner_slots().iter().map(|slot| slot.iter().position(|&sticker| self.get_sticker(sticker) < 2)).sum()
+    }
+
+    /// The same for the edge pieces `layer` layers in from the corners. For wings a quarter turn of that inner layer
+    /// changes it without touching anything else that has a parity, which on a 4x4x4 is what's left over as OLL
+    /// parity when reduction gets to the last layer. Midges can be flipped, so for them it's only about which slot
+    /// each one is in. None if there are no edges there or they aren't all real edges
+    pub fn wing_parity(&self, layer: usize) -> Option<bool> {
+        let slots = Self::wing_slots(layer);
+        if slots.is_empty() {
+            return None;
+        }
+        let homes = if 2 * layer + 1 == N {
+            self.homes(&slots, |colors| {
+                let mut colors = colors.to_vec();
+                colors.sort();
+                colors
+            })?
+        } else {
+            self.homes(&slots, |colors| colors.to_vec())?
+        };
+        Some(is_odd(&homes))
+    }
+
+    /// For each slot, which slot the piece in it belongs in. Pieces are told apart by `key` of their colors
+    fn homes<K: Eq + Hash, const S: usize>(&self, slots: &[[usize; S]], key: impl Fn(&[u8]) -> K) -> Option<Vec<usize>> {
+        let solved = slots.iter().enumerate().map(|(i, slot)| (key(&slot.map(|sticker| (sticker / (N * N)) as u8)), i)).collect::<HashMap<K, usize>>();
+        let homes = slots.iter().map(|slot| solved.get(&key(&slot.map(|sticker| self.stickers[sticker]))).copied()).collect::<Option<Vec<usize>>>()?;
+        // every piece has to show up once
+        let unique = homes.iter().collect::<HashSet<&usize>>();
+        (unique.len() == homes.len()).then_some(homes)
+    }
+
+    pub(crate) fn permuted(&self, permutation: &[usize]) -> NCube<N> {
+        let mut stickers = self.stickers.clone();
+        for (from, &to) in permutation.iter().enumerate() {
+            stickers[to] = self.stickers[from];
+        }
+        NCube { stickers }
+    }
+
+    ///////////////////////////////////////////////////////
+
+    /// Where the middle of a sticker is. Coordinates are doubled so they stay whole numbers, the cube
+    /// goes from -N to N along each axis
+    pub(crate) fn position(index: usize) -> [i32; 3] {
+        let (face, row, column) = (index / (N * N), (index / N) % N, index % N);
+        let n = N as i32;
+        let (row, column) = (2 * row as i32 - (n - 1), 2 * column as i32 - (n - 1));
+        [0, 1, 2].map(|axis| NORMALS[face][axis] * n + RIGHTS[face][axis] * column + DOWNS[face][axis] * row)
+    }
+
+    pub(crate) fn normal(index: usize) -> [i32; 3] {
+        NORMALS[index / (N * N)]
+    }
+
+    pub(crate) fn index_at(position: [i32; 3]) -> usize {
+        let n = N as i32;
+        let face = (0..6).find(|&face| dot(position, NORMALS[face]) == n).unwrap();
+        let column = (dot(position, RIGHTS[face]) + n - 1) / 2;
+        let row = (dot(position, DOWNS[face]) + n - 1) / 2;
+        (face * N + row as usize) * N + column as usize
+    }
+
+    /// The permutation for a move, looked up in a table of every move the cube has
+    fn move_permutation(action: &NCubeAction<N>) -> &'static [usize] {
+        static MOVES: Tables = OnceLock::new();
+        let moves = cached(&MOVES, N, || {
+            (0..6).flat_map(|face| (0..Self::LAYERS).flat_map(move |layer| {
+                (0..4).map(move |quarter_turns| Self::permutation(face, Some(layer), quarter_turns))
+            })).collect()
+        });
+        &moves[(action.get_face() * Self::LAYERS + action.get_layer()) * 4 + action.get_quarter_turns() as usize]
+    }
+
+    /// Where every sticker goes when the layer `layer` in from `face` turns, or the whole cube does for None
+    pub(crate) fn permutation(face: usize, layer: Option<usize>, quarter_turns: u8) -> Vec<usize> {
+        let axis = NORMALS[face];
+        let n = N as i32;
+        (0..Self::STICKERS).map(|index| {
+            let mut position = Self::position(index);
+            let depth = dot(position, axis);
+            let turns = match layer {
+                None => true,
+                Some(layer) => {
+                    let layer = n - 1 - 2 * layer as i32;
+                    depth == layer || (layer == n - 1 && depth == n) || (layer == 1 - n && depth == -n)
+                }
+            };
+            if turns {
+                for _ in 0..quarter_turns % 4 {
+                    position = rotate(position, axis);
+                }
+            }
+            Self::index_at(position)
+        }).collect()
+    }
+
+    /// Where every sticker goes for each of the 24 ways of turning the whole cube, starting with not turning it
+    pub(crate) fn rotations() -> &'static [Vec<usize>] {
+        static ROTATIONS: Tables = OnceLock::new();
+        cached(&ROTATIONS, N, Self::build_rotations)
+    }
+
+    fn build_rotations() -> Vec<Vec<usize>> {
+        let turns = [0, 2, 4].map(|face| Self::permutation(face, None, 1));
+        let mut rotations = vec![(0..Self::STICKERS).collect::<Vec<usize>>()];
+        let mut seen = HashSet::from([rotations[0].clone()]);
+        let mut i = 0;
+        while i < rotations.len() {
+            for turn in &turns {
+                let rotation = rotations[i].iter().map(|&to| turn[to]).collect::<Vec<usize>>();
+                if seen.insert(rotation.clone()) {
+                    rotations.push(rotation);
+                }
+            }
+            i += 1;
+        }
+        rotations
+    }
+
+    /// The stickers of every piece, each piece being everything at the same spot just inside the stickers
+    fn pieces() -> Vec<Vec<usize>> {
+        let mut pieces: Vec<Vec<usize>> = Vec::new();
+        let mut spots = HashMap::new();
+        for index in 0..Self::STICKERS {
+            let (position, normal) = (Self::position(index), Self::normal(index));
+            let spot = [0, 1, 2].map(|axis| position[axis] - normal[axis]);
+            let piece = *spots.entry(spot).or_insert_with(|| {
+                pieces.push(Vec::new());
+                pieces.len() - 1
+            });
+            pieces[piece].push(index);
+        }
+        pieces
+    }
+
+    /// The stickers of each corner, starting with the one on U or D and going around clockwise
+    pub(crate) fn corner_slots() -> Vec<[usize; 3]> {
+        Self::pieces().into_iter().filter(|piece| piece.len() == 3).map(|piece| {
+            let first = *piece.iter().find(|&&sticker| Self::normal(sticker)[1] != 0).unwrap();
+            let others = piece.iter().copied().filter(|&sticker| sticker != first).collect::<Vec<usize>>();
+            let (a, b) = (others[0], others[1]);
+            if dot(Self::normal(first), cross(Self::normal(a), Self::normal(b))) > 0 { [first, b, a] } else { [first, a, b] }
+        }).collect()
+    }
+
+    /// The stickers of each edge piece `layer` layers in from the corners, which is a midge for the middle layer of an
+    /// odd cube and a wing otherwise. A wing can't be flipped in its spot, so the two stickers are put in whichever
+    /// order keeps it the same way around wherever it goes, which makes wings just a permutation
+    pub(crate) fn wing_slots(layer: usize) -> Vec<[usize; 2]> {
+        if layer == 0 || 2 * layer + 1 > N {
+            return Vec::new();
+        }
+        let offset = N as i32 - 1 - 2 * layer as i32;
+        Self::pieces().into_iter().filter(|piece| piece.len() == 2).filter_map(|piece| {
+            let (a, b) = (piece[0], piece[1]);
+            let (position, normal) = (Self::position(a), Self::normal(a));
+            let spot = [0, 1, 2].map(|axis| position[axis] - normal[axis]);
+            let along = dot(spot, cross(Self::normal(a), Self::normal(b)));
+            (along.abs() == offset).then_some(if along < 0 { [b, a] } else { [a, b] })
+        }).collect()
+    }
+
+    /// The center stickers, split into the groups that can swap places with each other. Those are told apart by how
+    /// far from the middle of the face they are across and down, which is everything up to 5x5x5 (bigger cubes
+    /// have obliques that this would lump together with their mirror images)
+    pub(crate) fn center_slots() -> Vec<Vec<usize>> {
+        let mut orbits: Vec<((i32, i32), Vec<usize>)> = Vec::new();
+        for piece in Self::pieces().into_iter().filter(|piece| piece.len() == 1) {
+            let sticker = piece[0];
+            let face = sticker / (N * N);
+            let position = Self::position(sticker);
+            let (across, down) = (dot(position, RIGHTS[face]).abs(), dot(position, DOWNS[face]).abs());
+            let key = (across.min(down), across.max(down));
+            match orbits.iter_mut().find(|(orbit, _)| *orbit == key) {
+                Some((_, stickers)) => stickers.push(sticker),
+                None => orbits.push((key, vec![sticker])),
+            }
+        }
+        orbits.sort();
+        orbits.into_iter().map(|(_, stickers)| stickers).collect()
+    }
+
+}
+
+/// A cycle of length k is k - 1 swaps, so it's odd when the length and the number of cycles differ by an odd number
+pub(crate) fn is_odd(permutation: &[usize]) -> bool {
+    let mut seen = vec![false; permutation.len()];
+    let mut cycles = 0;
+    for start in 0..permutation.len() {
+        if seen[start] {
+            continue;
+        }
+        cycles += 1;
+        let mut i = start;
+        while !seen[i] {
+            seen[i] = true;
+            i = permutation[i];
+        }
+    }
+    (permutation.len() - cycles) % 2 == 1
+}
+
+impl<const N: usize> Default for NCube<N> {
+
+    fn default() -> NCube<N> {
+        NCube { stickers: (0..Self::STICKERS).map(|index| (index / (N * N)) as u8).collect() }
+    }
+
+}
+
+/// The six faces in the order of FACES separated by commas, each one a digit a sticker
+impl<const N: usize> fmt::Display for NCube<N> {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let faces = self.stickers.chunks(N * N).map(|face| face.iter().map(|sticker| sticker.to_string()).collect::<String>()).collect::<Vec<String>>();
+        write!(f, "{}", faces.join(","))
+    }
+
+}
+
+impl<const N: usize> FromStr for NCube<N> {
+
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let faces = s.trim().split(',').map(|face| face.trim()).collect::<Vec<&str>>();
+        if faces.len() != 6 || faces.iter().any(|face| face.len() != N * N) {
+            return Err(());
+        }
+        let stickers = faces.concat().chars().map(|c| c.to_digit(6).map(|digit| digit as u8)).collect::<Option<Vec<u8>>>().ok_or(())?;
+        NCube::from_stickers(&stickers).ok_or(())
+    }
+
+}
+
+impl<const N: usize> State for NCube<N> {
+    type Action = NCubeAction<N>;
+
+    /// Unfolded with U on top, L, F, R and B across the middle and D at the bottom
+    fn display_pretty(&self) {
+        let row = |face: usize, row: usize| (0..N).map(|column| Color::from_index(self.get_sticker_at(face, row, column)).to_colored("▓")).collect::<String>();
+        let padding = " ".repeat(N);
+        for r in 0..N {
+            println!("{}{}", padding, row(0, r));
+        }
+        for r in 0..N {
+            println!("{}{}{}{}", row(5, r), row(2, r), row(4, r), row(3, r));
+        }
+        for r in 0..N {
+            println!("{}{}", padding, row(1, r));
+        }
+    }
+
+    fn list_actions(&self) -> Vec<Self::Action> {
+        let mut actions = Vec::new();
+        for face in 0..6 {
+            for layer in 0..Self::LAYERS {
+                // the middle layer turned from D, B or L is the same as from U, F or R the other way
+                if 2 * layer + 1 == N && face % 2 == 1 {
+                    continue;
+                }
+                for quarter_turns in [1, 3, 2] {
+                    actions.push(NCubeAction::new(face, layer, quarter_turns));
+                }
+            }
+        }
+        actions
+    }
+
+    fn list_actions_after(&self, previous: Option<&Self::Action>) -> Vec<Self::Action> {
+        let mut actions = self.list_actions();
+        if let Some(previous) = previous {
+            actions.retain(|action| action.can_follow(previous));
+        }
+        actions
+    }
+
+    fn perform_action(&self, action: &Self::Action) -> Self {
+        if action.get_layer() < Self::LAYERS {
+            self.permuted(Self::move_permutation(action))
+        } else {
+            self.permuted(&Self::permutation(action.get_face(), Some(action.get_layer()), action.get_quarter_turns()))
+        }
+    }
+
+    fn is_goal_state(&self) -> bool {
+        self.stickers.chunks(N * N).all(|face| face.iter().all(|&sticker| sticker == face[0]))
+    }
+
+    fn goal_states(&self) -> Vec<Self> {
+        NCube::default().orientations()
+    }
+
+    /// Turning a layer only moves 4N stickers onto a different face (the ones on the face that turns stay on it), and
+    /// each of them changes how many stickers on its face don't match the most common color there by at most one.
+    /// That adds up to nothing for a solved cube, whichever way around it is
+    fn heuristic(&self) -> f64 {
+        let mismatched = self.stickers.chunks(N * N).map(|face| {
+            let mut counts = [0; 6];
+            for &sticker in face {
+                counts[sticker as usize] += 1;
+            }
+            N * N - counts.iter().max().unwrap()
+        }).sum::<usize>();
+        mismatched.div_ceil(4 * N) as f64
+    }
+
+    /// Only checks that the pieces are all real pieces, that the corners and wings could have got where they are
+    /// by turning (the same can't be said for the centers, which swap places with ones of the same color unseen) and
+    /// that the corners aren't twisted. Midge flips aren't checked
+    fn is_solvable(&self) -> bool {
+        if self.corner_parity().is_none() || self.corner_twist().is_none_or(|twist| twist % 3 != 0) {
+            return false;
+        }
+        (1..Self::LAYERS).all(|layer| self.wing_parity(layer).is_some())
+    }
+
+}
+
+/// Turns the layer `layer` in from a face (0 is the face itself) clockwise by `quarter_turns`. Written like 2R' for
+/// the layer just inside R, the way most notation for big cubes writes inner layers. N is the size of the cube, so
+/// that layers past the middle can be turned away when reading one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NCubeAction<const N: usize> {
+    face: u8,
+    layer: u8,
+    quarter_turns: u8,
+}
+
+impl<const N: usize> NCubeAction<N> {
+
+    /// Face numbered like FACES, and 1, 2 or 3 quarter turns
+    pub fn new(face: usize, layer: usize, quarter_turns: u8) -> NCubeAction<N> {
+        NCubeAction { face: face as u8, layer: layer as u8, quarter_turns: quarter_turns % 4 }
+    }
+
+    pub fn get_face(&self) -> usize {
+        self.face as usize
+    }
+
+    pub fn get_layer(&self) -> usize {
+        self.layer as usize
+    }
+
+    pub fn get_quarter_turns(&self) -> u8 {
+        self.quarter_turns
+    }
+
+    /// Turns of layers along the same axis all commute, so only one order of them is needed, and the same layer twice
+    /// in a row is never needed at all
+    pub fn can_follow(&self, previous: &NCubeAction<N>) -> bool {
+        self.face / 2 != previous.face / 2 || (self.face, self.layer) > (previous.face, previous.layer)
+    }
+
+}
+
+impl<const N: usize> fmt::Display for NCubeAction<N> {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.layer > 0 {
+            write!(f, "{}", self.layer + 1)?;
+        }
+        let suffix = match self.quarter_turns {
+            1 => "",
+            2 => "2",
+            _ => "'",
+        };
+        write!(f, "{}{}", FACES[self.face as usize], suffix)
+    }
+
+}
+
+impl<const N: usize> FromStr for NCubeAction<N> {
+
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.chars().take_while(|c| c.is_ascii_digit()).count();
+        let layer = if digits == 0 { 1 } else { s[..digits].parse::<usize>().map_err(|_| ())? };
+        let mut rest = s[digits..].chars();
+        let face = rest.next().and_then(|c| FACES.iter().position(|&face| face == c)).ok_or(())?;
+        let quarter_turns = match rest.as_str() {
+            "" => 1,
+            "2" => 2,
+            "'" => 3,
+            _ => return Err(()),
+        };
+        // there are only LAYERS layers in from each face, so 3R on a 3x3x3 would turn nothing
+        if layer == 0 || layer > NCube::<N>::LAYERS {
+            return Err(());
+        }
+        Ok(NCubeAction::new(face, layer - 1, quarter_turns))
+    }
+
+}
+
+impl<const N: usize> Action for NCubeAction<N> {
+
+    fn get_cost(&self) -> f64 {
+        1.0
+    }
+
+}
+
+impl<const N: usize> InvertibleAction for NCubeAction<N> {
+
+    fn inverse(&self) -> Self {
+        NCubeAction::new(self.get_face(), self.get_layer(), 4 - self.quarter_turns)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn perform_all<const N: usize>(cube: NCube<N>, actions: &[NCubeAction<N>]) -> NCube<N> {
+        actions.iter().fold(cube, |cube, action| cube.perform_action(action))
+    }
+
+    #[test]
+    fn turns_add_up() {
+        let (cube, _) = NCube::<5>::scrambled_with(&mut StdRng::seed_from_u64(24), 20);
+        for action in cube.list_actions() {
+            assert!(perform_all(cube.clone(), &[action, action.inverse()]) == cube);
+            let quarter = NCubeAction::new(action.get_face(), action.get_layer(), 1);
+            assert!(perform_all(cube.clone(), &[quarter; 4]) == cube);
+            assert!(perform_all(cube.clone(), &vec![quarter; action.get_quarter_turns() as usize]) == cube.perform_action(&action));
+        }
+        // every layer of a face turned the same way is the whole cube turned
+        let layers = (0..5).map(|layer| NCubeAction::new(2, layer, 1)).collect::<Vec<NCubeAction<5>>>();
+        assert!(perform_all(cube.clone(), &layers) == cube.rotated(2, 1));
+    }
+
+    /// The order of R U is the same 105 as on any other cube
+    #[test]
+    fn r_u_has_order_105() {
+        let sequence = [NCubeAction::new(4, 0, 1), NCubeAction::new(0, 0, 1)];
+        let mut cube = NCube::<3>::default();
+        for order in 1..=105 {
+            cube = perform_all(cube, &sequence);
+            assert_eq!(cube.is_goal_state(), order == 105);
+        }
+    }
+
+    #[test]
+    fn parity_changes_with_quarter_turns() {
+        let cube = RevengeCube::default();
+        assert_eq!((cube.corner_parity(), cube.wing_parity(1)), (Some(false), Some(false)));
+        let inner = cube.perform_action(&"2R".parse().unwrap());
+        assert_eq!((inner.corner_parity(), inner.wing_parity(1)), (Some(false), Some(true)));
+        let outer = cube.perform_action(&"R".parse().unwrap());
+        assert_eq!((outer.corner_parity(), outer.wing_parity(1)), (Some(true), Some(false)));
+        let half = cube.perform_action(&"2R2".parse().unwrap());
+        assert_eq!(half.wing_parity(1), Some(false));
+
+        // midges go along with the corners for outer turns, but not for the middle layer
+        let cube = ProfessorCube::default();
+        let outer = cube.perform_action(&"U".parse().unwrap());
+        assert_eq!((outer.corner_parity(), outer.wing_parity(1), outer.wing_parity(2)), (Some(true), Some(false), Some(true)));
+        let middle = cube.perform_action(&"3R".parse().unwrap());
+        assert_eq!((middle.corner_parity(), middle.wing_parity(1), middle.wing_parity(2)), (Some(false), Some(false), Some(true)));
+    }
+
+    #[test]
+    fn solved_every_way_around() {
+        let orientations = NCube::<4>::default().orientations();
+        assert_eq!(orientations.iter().collect::<HashSet<&NCube<4>>>().len(), 24);
+        assert!(orientations.iter().all(|cube| cube.is_goal_state() && cube.heuristic() == 0.0));
+        assert!(!NCube::<4>::default().perform_action(&"2F".parse().unwrap()).is_goal_state());
+    }
+
+    #[test]
+    fn heuristic_is_admissible() {
+        let mut rng = StdRng::seed_from_u64(24);
+        for length in 0..6 {
+            let (cube, _) = NCube::<4>::scrambled_with(&mut rng, length);
+            assert!(cube.heuristic() <= length as f64);
+            assert!(cube.is_solvable());
+        }
+    }
+
+    #[test]
+    fn twisted_corners_cant_be_solved() {
+        let mut rng = StdRng::seed_from_u64(24);
+        for _ in 0..10 {
+            let (cube, _) = NCube::<4>::scrambled_with(&mut rng, 30);
+            assert_eq!(cube.corner_twist().map(|twist| twist % 3), Some(0));
+            assert!(cube.is_solvable());
+
+            for twists in 1..3 {
+                let mut stickers = cube.stickers.to_vec();
+                let [a, b, c] = NCube::<4>::corner_slots()[0];
+                for _ in 0..twists {
+                    (stickers[a], stickers[b], stickers[c]) = (stickers[c], stickers[a], stickers[b]);
+                }
+                let twisted = NCube::<4>::from_stickers(&stickers).unwrap();
+                assert_eq!(twisted.corner_parity(), cube.corner_parity());
+                assert!(!twisted.is_solvable());
+            }
+        }
+    }
+
+    #[test]
+    fn strings_round_trip() {
+        let (cube, actions) = NCube::<4>::scrambled_with(&mut StdRng::seed_from_u64(24), 30);
+        assert!(cube.to_string().parse::<NCube<4>>() == Ok(cube.clone()));
+        for action in actions {
+            assert_eq!(action.to_string().parse(), Ok(action));
+        }
+        assert_eq!("3U'".parse(), Ok(NCubeAction::<5>::new(0, 2, 3)));
+        assert_eq!("F2".parse(), Ok(NCubeAction::<3>::new(2, 0, 2)));
+        assert_eq!("0F".parse::<NCubeAction<3>>(), Err(()));
+        assert_eq!("R3".parse::<NCubeAction<3>>(), Err(()));
+    }
+
+    #[test]
+    fn layers_past_the_middle_are_rejected() {
+        assert_eq!("2R".parse(), Ok(NCubeAction::<3>::new(4, 1, 1)));
+        assert_eq!("3R".parse::<NCubeAction<3>>(), Err(()));
+        assert_eq!("9R".parse::<NCubeAction<3>>(), Err(()));
+        assert_eq!("3R".parse::<NCubeAction<4>>(), Err(()));
+        assert_eq!("3R".parse(), Ok(NCubeAction::<5>::new(4, 2, 1)));
+        assert_eq!("2F".parse::<NCubeAction<2>>(), Err(()));
+        assert!("0000,1111,2222,3333,4444,5555".parse::<NCube<2>>() == Ok(NCube::default()));
+        assert!("0000,1111,2222,3333,4444,5550".parse::<NCube<2>>().is_err());
+    }
+
+}
@@ -0,0 +1,353 @@
+// Author: Harper Davis
+use std::{collections::VecDeque, fs, io::{self, Read, Write}, path::Path, sync::{Arc, OnceLock}};
+
+use crate::heuristic::Heuristic;
+
+use super::{fifteen_puzzle::FifteenPuzzle, fifteen_puzzle_fast::FifteenPuzzleFast};
+
+const MAGIC: &[u8; 4] = b"PDB1";
+const UNVISITED: u8 = u8::MAX;
+
+/// The fewest moves of a group of tiles needed to get them all home, for every way those tiles
+/// could be placed on the board. Moves of tiles outside of the group are free, so the values of
+/// disjoint groups can be added together without ever overestimating
+pub struct PatternDatabase {
+    tiles: Vec<u8>,
+    distances: Vec<u8>,
+}
+
+impl PatternDatabase {
+
+    /// Fills in the database with a breadth first search backwards from the goal. The blank is
+    /// part of the search state so that only legal moves are counted, but sliding it over a tile
+    /// outside the pattern costs nothing. Afterwards the blank is forgotten by keeping the
+    /// smallest distance over all of its positions
+    pub fn build(tiles: &[u8]) -> PatternDatabase {
+        let size = tiles.len();
+        let mut full = vec![UNVISITED; permutation_count(size + 1)];
+
+        let mut goal = tiles.to_vec();
+        goal.push(0);
+        let goal_index = rank(&goal);
+        full[goal_index] = 0;
+
+        let mut queue = VecDeque::new();
+        queue.push_back(goal_index as u32);
+
+        let mut positions = vec![0_u8; size + 1];
+        while let Some(index) = queue.pop_front() {
+            let distance = full[index as usize];
+            unrank(index as usize, &mut positions);
+            let blank = positions[size];
+
+            for next_blank in neighbours(blank) {
+                let moved_tile = positions[..size].iter().position(|&position| position == next_blank);
+                let mut next = positions.clone();
+                next[size] = next_blank;
+                if let Some(tile) = moved_tile {
+                    next[tile] = blank;
+                }
+
+                let next_index = rank(&next);
+                let next_distance = if moved_tile.is_some() { distance + 1 } else { distance };
+                if full[next_index] <= next_distance {
+                    continue;
+                }
+                full[next_index] = next_distance;
+
+                // free moves go to the front so the queue stays sorted by distance
+                if moved_tile.is_some() {
+                    queue.push_back(next_index as u32);
+                } else {
+                    queue.push_front(next_index as u32);
+                }
+            }
+        }
+
+        let mut distances = vec![UNVISITED; permutation_count(size)];
+        for (index, &distance) in full.iter().enumerate() {
+            unrank(index, &mut positions);
+            let pattern_index = rank(&positions[..size]);
+            distances[pattern_index] = u8::min(distances[pattern_index], distance);
+        }
+
+        PatternDatabase { tiles: tiles.to_vec(), distances }
+    }
+
+    pub fn get_tiles(&self) -> &[u8] {
+        &self.tiles
+    }
+
+    /// Looks up the distance given the board index of every piece
+    pub fn lookup(&self, piece_indices: &[u8; 16]) -> u8 {
+        let mut positions = [0_u8; 16];
+        for (i, &tile) in self.tiles.iter().enumerate() {
+            positions[i] = piece_indices[tile as usize];
+        }
+        self.distances[rank(&positions[..self.tiles.len()])]
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        file.write_all(MAGIC)?;
+        file.write_all(&[self.tiles.len() as u8])?;
+        file.write_all(&self.tiles)?;
+        file.write_all(&self.distances)
+    }
+
+    pub fn load(path: &Path) -> io::Result<PatternDatabase> {
+        let mut bytes = Vec::new();
+        fs::File::open(path)?.read_to_end(&mut bytes)?;
+
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), message));
+        if bytes.len() < 5 || &bytes[..4] != MAGIC {
+            return Err(invalid("not a pattern database"));
+        }
+        let size = bytes[4] as usize;
+        if bytes.len() != 5 + size + permutation_count(size) {
+            return Err(invalid("wrong length"));
+        }
+
+        Ok(PatternDatabase { tiles: bytes[5..5 + size].to_vec(), distances: bytes[5 + size..].to_vec() })
+    }
+
+    /// Loads the database from the file if it's there, and otherwise builds it and saves it there for next time.
+    /// Not being able to save it only means building it again next time, so that is reported rather than failing
+    pub fn load_or_build(tiles: &[u8], path: &Path) -> PatternDatabase {
+        if let Ok(database) = PatternDatabase::load(path) {
+            if database.tiles == tiles {
+                return database;
+            }
+        }
+        let database = PatternDatabase::build(tiles);
+        if let Err(error) = database.save(path) {
+            eprintln!("couldn't save the pattern database to {}: {}", path.display(), error);
+        }
+        database
+    }
+
+}
+
+/// Several pattern databases over disjoint groups of tiles, whose values are summed
+pub struct AdditivePatternDatabase {
+    databases: Vec<PatternDatabase>,
+}
+
+impl AdditivePatternDatabase {
+
+    /// Korf and Felner's 6-6-3 split
+    pub const SIX_SIX_THREE: [&'static [u8]; 3] = [&[1, 5, 6, 9, 10, 13], &[7, 8, 11, 12, 14, 15], &[2, 3, 4]];
+    /// Smaller and much quicker to build, but not as well informed
+    pub const FIVE_FIVE_FIVE: [&'static [u8]; 3] = [&[1, 5, 6, 9, 13], &[2, 3, 4, 7, 8], &[10, 11, 12, 14, 15]];
+
+    pub fn new(databases: Vec<PatternDatabase>) -> AdditivePatternDatabase {
+        AdditivePatternDatabase { databases }
+    }
+
+    /// Loads every group from `directory`, building and saving the ones that are missing
+    pub fn load_or_build(partition: &[&[u8]], directory: &Path) -> AdditivePatternDatabase {
+        if let Err(error) = fs::create_dir_all(directory) {
+            eprintln!("couldn't make {} for the pattern databases: {}", directory.display(), error);
+        }
+        let mut databases = Vec::new();
+        for tiles in partition {
+            let name = tiles.iter().map(|tile| tile.to_string()).collect::<Vec<String>>().join("-");
+            databases.push(PatternDatabase::load_or_build(tiles, &directory.join(format!("{}.pdb", name))));
+        }
+        AdditivePatternDatabase::new(databases)
+    }
+
+    pub fn estimate(&self, puzzle: &FifteenPuzzle) -> u32 {
+        let mut piece_indices = [0_u8; 16];
+        for i in 0..16 {
+            piece_indices[puzzle.get_piece(i) as usize] = i;
+        }
+        self.estimate_indices(&piece_indices)
+    }
+
+    /// The same as `estimate`, given the board index of every piece
+    pub fn estimate_indices(&self, piece_indices: &[u8; 16]) -> u32 {
+        self.databases.iter().map(|database| database.lookup(piece_indices) as u32).sum()
+    }
+
+}
+
+/// The summed pattern databases as a heuristic. The default loads (or builds) the 6-6-3 split
+/// from ./pattern_databases once, and every search after that shares it
+#[derive(Clone)]
+pub struct PatternDatabaseHeuristic {
+    databases: Arc<AdditivePatternDatabase>,
+}
+
+impl PatternDatabaseHeuristic {
+
+    pub fn new(databases: Arc<AdditivePatternDatabase>) -> PatternDatabaseHeuristic {
+        PatternDatabaseHeuristic { databases }
+    }
+
+}
+
+impl Default for PatternDatabaseHeuristic {
+
+    fn default() -> Self {
+        static SHARED: OnceLock<Arc<AdditivePatternDatabase>> = OnceLock::new();
+        let databases = SHARED.get_or_init(|| {
+            Arc::new(AdditivePatternDatabase::load_or_build(&AdditivePatternDatabase::SIX_SIX_THREE, Path::new("./pattern_databases")))
+        });
+        PatternDatabaseHeuristic::new(databases.clone())
+    }
+
+}
+
+impl Heuristic<FifteenPuzzle> for PatternDatabaseHeuristic {
+    fn estimate(&self, state: &FifteenPuzzle) -> f64 {
+        self.databases.estimate(state) as f64
+    }
+}
+
+impl Heuristic<FifteenPuzzleFast> for PatternDatabaseHeuristic {
+    fn estimate(&self, state: &FifteenPuzzleFast) -> f64 {
+        self.databases.estimate_indices(&state.piece_indices()) as f64
+    }
+}
+
+fn neighbours(index: u8) -> impl Iterator<Item = u8> {
+    let (x, y) = (index % 4, index / 4);
+    [
+        (x > 0).then(|| index - 1),
+        (x < 3).then(|| index + 1),
+        (y > 0).then(|| index - 4),
+        (y < 3).then(|| index + 4),
+    ].into_iter().flatten()
+}
+
+/// Number of ways to place `size` distinct tiles on the 16 squares
+fn permutation_count(size: usize) -> usize {
+    (16 - size + 1..=16).product()
+}
+
+/// Numbers the ways of placing distinct tiles on the board from 0 up to permutation_count,
+/// each position is counted only among the squares not already taken by the tiles before it
+fn rank(positions: &[u8]) -> usize {
+    let mut index = 0;
+    let mut taken = 0_u16;
+    for (i, &position) in positions.iter().enumerate() {
+        let smaller_taken = (taken & ((1 << position) - 1)).count_ones() as usize;
+        index = index * (16 - i) + position as usize - smaller_taken;
+        taken |= 1 << position;
+    }
+    index
+}
+
+fn unrank(mut index: usize, positions: &mut [u8]) {
+    let size = positions.len();
+    let mut digits = [0_usize; 16];
+    for i in (0..size).rev() {
+        digits[i] = index % (16 - i);
+        index /= 16 - i;
+    }
+
+    let mut taken = 0_u16;
+    for i in 0..size {
+        // the digit'th square that isn't taken yet
+        let mut remaining = digits[i];
+        let mut position = 0;
+        loop {
+            if taken & (1 << position) == 0 {
+                if remaining == 0 { break; }
+                remaining -= 1;
+            }
+            position += 1;
+        }
+        positions[i] = position as u8;
+        taken |= 1 << position;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    use crate::{state::State, suite::read_fifteen_puzzle_test_cases};
+
+    use super::*;
+
+    /// Small enough to build in a moment even without optimizations
+    const THREES: [&[u8]; 5] = [&[1, 2, 3], &[4, 5, 6], &[7, 8, 9], &[10, 11, 12], &[13, 14, 15]];
+
+    fn threes() -> AdditivePatternDatabase {
+        AdditivePatternDatabase::new(THREES.iter().map(|tiles| PatternDatabase::build(tiles)).collect())
+    }
+
+    /// Somewhere to put files that nothing else is using
+    fn scratch_directory(name: &str) -> std::path::PathBuf {
+        let directory = std::env::temp_dir().join(format!("puzzle_solver_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn never_more_than_the_shortest_path() {
+        let databases = threes();
+        assert_eq!(databases.estimate(&FifteenPuzzle::default()), 0);
+        for test_case in read_fifteen_puzzle_test_cases("./tests/15_puzzle.txt").unwrap() {
+            let start_state = test_case.get_start_state();
+            let estimate = databases.estimate(start_state);
+            assert!(estimate <= test_case.get_depth_limit(), "{} is {} moves away but estimated at {}", start_state, test_case.get_depth_limit(), estimate);
+            // every tile in a group still has to make its own moves
+            assert!(estimate as f64 >= start_state.heuristic());
+            assert_eq!(databases.estimate_indices(&FifteenPuzzleFast::from(start_state).piece_indices()), estimate);
+        }
+    }
+
+    #[test]
+    fn never_more_than_a_random_walk() {
+        let databases = threes();
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..100 {
+            let mut puzzle = FifteenPuzzle::default();
+            for moves in 1..=40 {
+                let action = puzzle.list_actions().choose(&mut rng).unwrap().clone();
+                puzzle = puzzle.perform_action(&action);
+                assert!(databases.estimate(&puzzle) <= moves);
+            }
+        }
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let directory = scratch_directory("pattern_database");
+        let path = directory.join("1-2-3.pdb");
+        let database = PatternDatabase::build(&[1, 2, 3]);
+        database.save(&path).unwrap();
+
+        let loaded = PatternDatabase::load(&path).unwrap();
+        assert_eq!(loaded.get_tiles(), &[1, 2, 3]);
+        assert!(loaded.distances == database.distances);
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(&bytes[..5], b"PDB1\x03");
+        let broken = directory.join("broken.pdb");
+        fs::write(&broken, [b"PDB2", &bytes[4..]].concat()).unwrap();
+        assert!(PatternDatabase::load(&broken).is_err_and(|error| error.to_string().contains("not a pattern database")));
+        fs::write(&broken, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(PatternDatabase::load(&broken).is_err_and(|error| error.to_string().contains("wrong length")));
+        fs::write(&broken, b"PDB").unwrap();
+        assert!(PatternDatabase::load(&broken).is_err());
+
+        // a file for other tiles gets built over
+        assert_eq!(PatternDatabase::load_or_build(&[4, 5, 6], &path).get_tiles(), &[4, 5, 6]);
+        assert_eq!(PatternDatabase::load(&path).unwrap().get_tiles(), &[4, 5, 6]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn builds_even_when_it_cant_save() {
+        let directory = scratch_directory("pattern_database_unsaved");
+        let file = directory.join("not_a_directory");
+        fs::write(&file, b"").unwrap();
+        let databases = AdditivePatternDatabase::load_or_build(&THREES[..1], &file.join("pattern_databases"));
+        assert_eq!(databases.estimate(&FifteenPuzzle::default()), 0);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
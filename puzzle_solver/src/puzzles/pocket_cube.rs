@@ -0,0 +1,281 @@
+// Author: Harper Davis
+use std::sync::OnceLock;
+
+use crate::heuristic::Heuristic;
+
+use super::n_cube::{NCubeAction, PocketCube};
+
+const PERMUTATIONS: usize = 5040;
+const TWISTS: usize = 729;
+/// U, R and F, which never move the corner between D, B and L. Turning D, B or L instead is the same as turning
+/// the opposite face and then the whole cube, so leaving them out doesn't make anything longer
+const FACES: [usize; 3] = [0, 4, 2];
+
+/// The exact number of moves a 2x2x2 is from solved, looked up in a table of every position. There are only
+/// 3674160 once the cube is held by the corner between D, B and L, so the whole table fits in a few megabytes
+/// and takes a couple of seconds to fill in the first time it's needed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PocketCubeDistance;
+
+impl PocketCubeDistance {
+
+    /// None if the cube can't be solved
+    pub fn distance(&self, cube: &PocketCube) -> Option<u8> {
+        let table = distance_table();
+        let (_, permutation, twist) = table.coordinates(cube)?;
+        Some(table.distances[permutation * TWISTS + twist])
+    }
+
+    /// One of the shortest solutions, found by always taking a move that gets one closer. None if the cube
+    /// can't be solved
+    pub fn solve(&self, cube: &PocketCube) -> Option<Vec<NCubeAction<2>>> {
+        let table = distance_table();
+        let (faces, mut permutation, mut twist) = table.coordinates(cube)?;
+        let mut actions = Vec::new();
+        let mut distance = table.distances[permutation * TWISTS + twist];
+        while distance > 0 {
+            let m = (0..table.actions.len()).find(|&m| {
+                table.distances[table.permutation_moves[permutation * 9 + m] as usize * TWISTS + table.twist_moves[twist * 9 + m] as usize] < distance
+            }).unwrap();
+            permutation = table.permutation_moves[permutation * 9 + m] as usize;
+            twist = table.twist_moves[twist * 9 + m] as usize;
+            distance -= 1;
+            // the table's moves are for the cube turned around, so they're turns of different faces of the one given
+            let action = table.actions[m];
+            actions.push(NCubeAction::new(faces[action.get_face()], 0, action.get_quarter_turns()));
+        }
+        Some(actions)
+    }
+
+}
+
+impl Heuristic<PocketCube> for PocketCubeDistance {
+    fn estimate(&self, state: &PocketCube) -> f64 {
+        self.distance(state).map_or(0.0, |distance| distance as f64)
+    }
+}
+
+/// distances[permutation * TWISTS + twist] is how far the position is from solved. The permutation is which of the
+/// seven corners that move is in each of their slots, and the twist is which way around each of the first six is,
+/// since the seventh has to make the total come out as a whole number of turns
+struct DistanceTable {
+    /// The stickers of the seven slots that move, each starting with the one on U or D
+    slots: Vec<[usize; 3]>,
+    /// The same for the DBL slot
+    fixed: [usize; 3],
+    actions: Vec<NCubeAction<2>>,
+    permutation_moves: Vec<u16>,
+    twist_moves: Vec<u16>,
+    distances: Vec<u8>,
+}
+
+impl DistanceTable {
+
+    /// For each face of the cube turned around to be held by the DBL corner, which face of `cube` that is, along
+    /// with the permutation and twist there. None if there's no such corner or the pieces don't add up
+    fn coordinates(&self, cube: &PocketCube) -> Option<([usize; 6], usize, usize)> {
+        let home = |slot: &[usize; 3]| slot.map(|sticker| (sticker / 4) as u8);
+        let (rotation, turned) = PocketCube::rotations().iter()
+            .map(|rotation| { let turned = cube.permuted(rotation); (rotation, turned) })
+            .find(|(_, turned)| self.fixed.map(|sticker| turned.get_sticker(sticker)) == home(&self.fixed))?;
+
+        let mut pieces = [0; 7];
+        let mut twists = [0; 7];
+        for (slot, stickers) in self.slots.iter().enumerate() {
+            let mut colors = stickers.map(|sticker| turned.get_sticker(sticker));
+            twists[slot] = colors.iter().position(|&color| color < 2)?;
+            colors.sort();
+            pieces[slot] = self.slots.iter().position(|home_slot| {
+                let mut home = home(home_slot);
+                home.sort();
+                home == colors
+            })?;
+        }
+        if (0..7).any(|piece| !pieces.contains(&piece)) || twists.iter().sum::<usize>() % 3 != 0 {
+            return None;
+        }
+
+        let mut faces = [0; 6];
+        for (face, to) in faces.iter_mut().enumerate() {
+            *to = rotation[face * 4] / 4;
+        }
+        let mut from = [0; 6];
+        for (face, &to) in faces.iter().enumerate() {
+            from[to] = face;
+        }
+        Some((from, rank(&pieces), encode_twists(&twists)))
+    }
+
+}
+
+fn distance_table() -> &'static DistanceTable {
+    static TABLE: OnceLock<DistanceTable> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut slots = PocketCube::corner_slots();
+        let fixed_slot = slots.iter().position(|slot| {
+            let mut faces = slot.map(|sticker| sticker / 4);
+            faces.sort();
+            faces == [1, 3, 5]
+        }).unwrap();
+        let fixed = slots.remove(fixed_slot);
+
+        // where each slot's pieces get carried to by each move, and how much they get twisted on the way
+        let actions = FACES.iter().flat_map(|&face| [1, 2, 3].map(|quarter_turns| NCubeAction::new(face, 0, quarter_turns))).collect::<Vec<NCubeAction<2>>>();
+        let moves = actions.iter().map(|action| {
+            let permutation = PocketCube::permutation(action.get_face(), Some(0), action.get_quarter_turns());
+            let mut to = [0; 7];
+            let mut twist = [0; 7];
+            for (slot, stickers) in slots.iter().enumerate() {
+                let first = permutation[stickers[0]];
+                to[slot] = slots.iter().position(|other| other.contains(&first)).unwrap();
+                twist[slot] = slots[to[slot]].iter().position(|&sticker| sticker == first).unwrap();
+            }
+            (to, twist)
+        }).collect::<Vec<([usize; 7], [usize; 7])>>();
+
+        let mut permutation_moves = vec![0; PERMUTATIONS * moves.len()];
+        for permutation in 0..PERMUTATIONS {
+            let pieces = unrank(permutation);
+            for (m, (to, _)) in moves.iter().enumerate() {
+                let mut moved = [0; 7];
+                for slot in 0..7 {
+                    moved[to[slot]] = pieces[slot];
+                }
+                permutation_moves[permutation * moves.len() + m] = rank(&moved) as u16;
+            }
+        }
+        let mut twist_moves = vec![0; TWISTS * moves.len()];
+        for twist in 0..TWISTS {
+            let twists = decode_twists(twist);
+            for (m, (to, turn)) in moves.iter().enumerate() {
+                let mut moved = [0; 7];
+                for slot in 0..7 {
+                    moved[to[slot]] = (twists[slot] + turn[slot]) % 3;
+                }
+                twist_moves[twist * moves.len() + m] = encode_twists(&moved) as u16;
+            }
+        }
+
+        // breadth first out from solved, which is the same distance as back since every move's inverse is a move
+        let mut distances = vec![u8::MAX; PERMUTATIONS * TWISTS];
+        distances[0] = 0;
+        let mut queue = vec![0_u32];
+        let mut i = 0;
+        while i < queue.len() {
+            let index = queue[i] as usize;
+            let (permutation, twist) = (index / TWISTS, index % TWISTS);
+            for m in 0..moves.len() {
+                let next = permutation_moves[permutation * moves.len() + m] as usize * TWISTS + twist_moves[twist * moves.len() + m] as usize;
+                if distances[next] == u8::MAX {
+                    distances[next] = distances[index] + 1;
+                    queue.push(next as u32);
+                }
+            }
+            i += 1;
+        }
+
+        DistanceTable { slots, fixed, actions, permutation_moves, twist_moves, distances }
+    })
+}
+
+/// The position of the permutation of 0..7 in lexicographic order
+fn rank(pieces: &[usize; 7]) -> usize {
+    (0..7).fold(0, |rank, i| rank * (7 - i) + pieces[i + 1..].iter().filter(|&&piece| piece < pieces[i]).count())
+}
+
+fn unrank(mut rank: usize) -> [usize; 7] {
+    let mut digits = [0; 7];
+    for i in (0..7).rev() {
+        digits[i] = rank % (7 - i);
+        rank /= 7 - i;
+    }
+    let mut left = (0..7).collect::<Vec<usize>>();
+    digits.map(|digit| left.remove(digit))
+}
+
+fn encode_twists(twists: &[usize; 7]) -> usize {
+    twists[..6].iter().fold(0, |code, &twist| code * 3 + twist)
+}
+
+fn decode_twists(mut code: usize) -> [usize; 7] {
+    let mut twists = [0; 7];
+    for i in (0..6).rev() {
+        twists[i] = code % 3;
+        code /= 3;
+    }
+    twists[6] = (3 - twists[..6].iter().sum::<usize>() % 3) % 3;
+    twists
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, VecDeque};
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::state::State;
+
+    use super::*;
+
+    #[test]
+    fn ranks_round_trip() {
+        for permutation in [0, 1, 719, 2500, PERMUTATIONS - 1] {
+            assert_eq!(rank(&unrank(permutation)), permutation);
+        }
+        for twist in [0, 1, 200, TWISTS - 1] {
+            assert_eq!(encode_twists(&decode_twists(twist)), twist);
+        }
+    }
+
+    /// Every cube within a few moves, with its distance found the slow way
+    #[test]
+    fn distances_match_a_plain_search() {
+        let mut distances = HashMap::from([(PocketCube::default(), 0)]);
+        let mut queue = VecDeque::from([PocketCube::default()]);
+        while let Some(cube) = queue.pop_front() {
+            let distance = distances[&cube];
+            if distance == 3 {
+                continue;
+            }
+            for action in cube.list_actions() {
+                let next = cube.perform_action(&action);
+                if !distances.contains_key(&next) {
+                    distances.insert(next.clone(), distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        // the plain search tells apart cubes that are only turned around, which can only make its distances longer
+        for (cube, distance) in distances {
+            assert!(PocketCubeDistance.distance(&cube).unwrap() <= distance);
+            assert_eq!(PocketCubeDistance.distance(&cube).unwrap() == 0, cube.is_goal_state());
+        }
+    }
+
+    #[test]
+    fn solves_scrambles_in_the_fewest_moves() {
+        let mut rng = StdRng::seed_from_u64(24);
+        for _ in 0..20 {
+            let (cube, scramble) = PocketCube::scrambled_with(&mut rng, 25);
+            let solution = PocketCubeDistance.solve(&cube).unwrap();
+            assert!(solution.len() <= 11 && solution.len() <= scramble.len());
+            assert_eq!(solution.len(), PocketCubeDistance.distance(&cube).unwrap() as usize);
+            let solved = solution.iter().fold(cube, |cube, action| cube.perform_action(action));
+            assert!(solved.is_goal_state());
+        }
+    }
+
+    #[test]
+    fn twisted_corner_is_unsolvable() {
+        let mut stickers = (0..24).map(|sticker| PocketCube::default().get_sticker(sticker)).collect::<Vec<u8>>();
+        let corner = PocketCube::corner_slots()[0];
+        let colors = corner.map(|sticker| stickers[sticker]);
+        for i in 0..3 {
+            stickers[corner[i]] = colors[(i + 1) % 3];
+        }
+        let cube = PocketCube::from_stickers(&stickers).unwrap();
+        assert_eq!(PocketCubeDistance.distance(&cube), None);
+        assert_eq!(PocketCubeDistance.solve(&cube), None);
+    }
+
+}
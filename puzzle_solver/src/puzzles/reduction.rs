@@ -0,0 +1,495 @@
+// Author: Harper Davis
+use std::{any::Any, collections::{HashMap, VecDeque}, fmt, marker::PhantomData, sync::{Mutex, OnceLock}};
+
+use crate::{action::InvertibleAction, frontier::StackFrontier, limits::SearchLimits, node::Node, search::{Search, SearchOutcome, Solution}, state::State, statistics::SearchStatistics};
+
+use super::{facelets::STICKER_POSITIONS, n_cube::{is_odd, NCube, NCubeAction}, rubiks_cube::CubeState, two_phase::TwoPhaseSolver};
+
+/// NCube's number for each face numbered like CubeAction::face
+const ACTION_FACES: [usize; 6] = [0, 1, 4, 5, 2, 3];
+/// The longest setup that goes around a commutator
+const SETUP_LENGTH: usize = 2;
+
+/// Solves bigger cubes the way most people do, by reducing them to a 3x3x3. First every wing (and on odd cubes, the
+/// middle centers and midges) gets its parity fixed with a single turn, so that everything left over can be done with
+/// 3-cycles. Then the centers and wings are put where they go with commutators that cycle three pieces and touch
+/// nothing else, and what's left is a 3x3x3 made of bigger pieces for the two phase solver. The solutions are long,
+/// a few hundred moves on a 5x5x5, but they're found in no time
+pub struct ReductionSolver<const N: usize> {
+    two_phase: TwoPhaseSolver,
+    centers: Vec<Cycles<N>>,
+    wings: Vec<(usize, Cycles<N>)>,
+    rotations: &'static [Vec<usize>],
+}
+
+impl<const N: usize> ReductionSolver<N> {
+
+    /// Works out the commutators for every 3-cycle, and builds the two phase solver's tables, so like that one
+    /// keep it around to solve more than one cube. Only 3x3x3 up to 5x5x5 have commutators for every kind of piece
+    pub fn new() -> ReductionSolver<N> {
+        assert!((3..=5).contains(&N), "only 3x3x3 to 5x5x5 cubes can be solved by reduction");
+        let mut centers = NCube::<N>::center_slots().into_iter()
+            .filter(|orbit| orbit.len() > 6)
+            .map(|orbit| Cycles::new(orbit.into_iter().map(|sticker| vec![sticker]).collect()))
+            .collect::<Vec<Cycles<N>>>();
+        let mut wings = (1..NCube::<N>::LAYERS).filter(|&layer| 2 * layer + 1 != N)
+            .map(|layer| (layer, Cycles::new(NCube::<N>::wing_slots(layer).into_iter().map(|slot| slot.to_vec()).collect())))
+            .collect::<Vec<(usize, Cycles<N>)>>();
+
+        let primitives = primitives::<N>();
+        let mut tables = centers.iter_mut().chain(wings.iter_mut().map(|(_, cycles)| cycles)).collect::<Vec<&mut Cycles<N>>>();
+        for table in tables.iter_mut() {
+            table.primitives = primitives.iter().filter_map(|primitive| {
+                table.cycled(&positions_after::<N>(primitive)).map(|cycle| (cycle, primitive.clone()))
+            }).collect();
+            assert!(!table.primitives.is_empty(), "no commutator cycles these pieces");
+        }
+        let moves = NCube::<N>::default().list_actions();
+        let permutations = moves.iter().map(|action| NCube::<N>::permutation(action.get_face(), Some(action.get_layer()), action.get_quarter_turns())).collect::<Vec<Vec<usize>>>();
+        for length in 0..=SETUP_LENGTH {
+            let mut setup = Vec::new();
+            let mut positions = (0..6 * N * N).collect::<Vec<usize>>();
+            add_setups(&mut tables, &moves, &permutations, &mut setup, &mut positions, length);
+        }
+
+        ReductionSolver { two_phase: TwoPhaseSolver::new(), centers, wings, rotations: NCube::<N>::rotations() }
+    }
+
+    /// One solver for each size, built the first time it's needed and kept from then on. Statics are shared by every
+    /// N, so they're told apart by N and downcast back
+    pub fn shared() -> &'static ReductionSolver<N> {
+        static SOLVERS: OnceLock<Mutex<HashMap<usize, &'static (dyn Any + Send + Sync)>>> = OnceLock::new();
+        let mut solvers = SOLVERS.get_or_init(Default::default).lock().unwrap();
+        let solver = *solvers.entry(N).or_insert_with(|| Box::leak(Box::new(ReductionSolver::<N>::new())));
+        solver.downcast_ref().unwrap()
+    }
+
+    /// A solution, or None if the cube can't be solved
+    pub fn solve(&self, cube: &NCube<N>) -> Option<Vec<NCubeAction<N>>> {
+        if !cube.is_solvable() {
+            return None;
+        }
+        let mut cube = cube.clone();
+        let mut actions = Vec::new();
+
+        // the middle centers only move with the middle layers, and once they're back where they were the midges have
+        // the same parity as the corners again, like they have to on a 3x3x3
+        if N % 2 == 1 {
+            let turns = Self::middle_centers(&cube)?;
+            perform(&mut cube, &mut actions, &turns);
+        } else if cube.corner_parity()? {
+            perform(&mut cube, &mut actions, &[NCubeAction::new(0, 0, 1)]);
+        }
+        for (layer, cycles) in &self.wings {
+            if is_odd(&self.wing_targets(&cube, cycles)?) {
+                perform(&mut cube, &mut actions, &[NCubeAction::new(4, *layer, 1)]);
+            }
+        }
+
+        for cycles in &self.centers {
+            let mut colors = cycles.slots.iter().map(|slot| cube.get_sticker(slot[0]) as usize).collect::<Vec<usize>>();
+            let faces = cycles.slots.iter().map(|slot| slot[0] / (N * N)).collect::<Vec<usize>>();
+            let cycled = cycles.place(&mut colors, &faces)?;
+            perform(&mut cube, &mut actions, &cycled);
+        }
+        for (_, cycles) in &self.wings {
+            let mut targets = self.wing_targets(&cube, cycles)?;
+            let cycled = cycles.place(&mut targets, &(0..cycles.slots.len()).collect::<Vec<usize>>())?;
+            perform(&mut cube, &mut actions, &cycled);
+        }
+
+        let solution = self.two_phase.solve(&reduced(&cube), 22)?;
+        let outer = solution.iter().map(|action| NCubeAction::new(ACTION_FACES[action.face()], 0, 4 - action.quarter_turns())).collect::<Vec<NCubeAction<N>>>();
+        perform(&mut cube, &mut actions, &outer);
+        debug_assert!(cube.is_goal_state());
+        Some(actions)
+    }
+
+    /// The fewest turns of middle layers that put the middle centers back the way they started
+    fn middle_centers(cube: &NCube<N>) -> Option<Vec<NCubeAction<N>>> {
+        let middle = N / 2;
+        let moves = [0, 2, 4].into_iter().flat_map(|face| [1, 2, 3].map(|quarter_turns| NCubeAction::new(face, middle, quarter_turns))).collect::<Vec<NCubeAction<N>>>();
+        let centers = |cube: &NCube<N>| (0..6).map(|face| cube.get_sticker_at(face, middle, middle)).collect::<Vec<u8>>();
+        let mut paths = HashMap::from([(centers(cube), Vec::new())]);
+        let mut queue = VecDeque::from([cube.clone()]);
+        while let Some(cube) = queue.pop_front() {
+            let path = paths[&centers(&cube)].clone();
+            if centers(&cube) == [0, 1, 2, 3, 4, 5] {
+                return Some(path);
+            }
+            for action in &moves {
+                let next = cube.perform_action(action);
+                paths.entry(centers(&next)).or_insert_with(|| {
+                    queue.push_back(next);
+                    let mut path = path.clone();
+                    path.push(*action);
+                    path
+                });
+            }
+        }
+        None
+    }
+
+    /// For each wing slot, the slot that the wing in it has to go to. That's where it started on an even cube, and on
+    /// an odd one it's next to the midge it started next to, on the same side of it
+    fn wing_targets(&self, cube: &NCube<N>, cycles: &Cycles<N>) -> Option<Vec<usize>> {
+        let homes = cycles.slots.iter().enumerate().map(|(i, slot)| ((slot[0] / (N * N), slot[1] / (N * N)), i)).collect::<HashMap<(usize, usize), usize>>();
+        let midges = NCube::<N>::wing_slots(N / 2);
+        cycles.slots.iter().map(|slot| {
+            let home = homes.get(&(cube.get_sticker(slot[0]) as usize, cube.get_sticker(slot[1]) as usize)).copied()?;
+            if N.is_multiple_of(2) {
+                return Some(home);
+            }
+            // the whole cube turned so that the midge's home goes to where the midge is now carries the wing's
+            // home to where it has to be
+            let faces = [cycles.slots[home][0] / (N * N), cycles.slots[home][1] / (N * N)];
+            let midge = midges.iter().find(|midge| faces.contains(&(midge[0] / (N * N))) && faces.contains(&(midge[1] / (N * N))))?;
+            let now = midges.iter().find_map(|stickers| {
+                let colors = stickers.map(|sticker| cube.get_sticker(sticker) as usize);
+                if colors == midge.map(|sticker| sticker / (N * N)) {
+                    Some(*stickers)
+                } else if colors == [midge[1] / (N * N), midge[0] / (N * N)] {
+                    Some([stickers[1], stickers[0]])
+                } else {
+                    None
+                }
+            })?;
+            let rotation = self.rotations.iter().find(|rotation| rotation[midge[0]] == now[0] && rotation[midge[1]] == now[1])?;
+            cycles.slots.iter().position(|slot| slot[0] == rotation[cycles.slots[home][0]])
+        }).collect()
+    }
+
+}
+
+impl<const N: usize> Default for ReductionSolver<N> {
+
+    fn default() -> ReductionSolver<N> {
+        ReductionSolver::new()
+    }
+
+}
+
+/// 3-cycles of one kind of piece
+struct Cycles<const N: usize> {
+    /// The stickers of each slot. Moves carry the first one of a slot to the first one of another
+    slots: Vec<Vec<usize>>,
+    /// Commutators that cycle three of the slots and leave everything else alone
+    primitives: Vec<([usize; 3], Vec<NCubeAction<N>>)>,
+    /// The moves that carry the piece in the first slot to the second, that one to the third and that one to the
+    /// first, keyed by the slots starting with the smallest
+    algorithms: HashMap<[usize; 3], Vec<NCubeAction<N>>>,
+}
+
+impl<const N: usize> Cycles<N> {
+
+    fn new(slots: Vec<Vec<usize>>) -> Cycles<N> {
+        Cycles { slots, primitives: Vec::new(), algorithms: HashMap::new() }
+    }
+
+    fn slot_of(&self, sticker: usize) -> Option<usize> {
+        self.slots.iter().position(|slot| slot[0] == sticker)
+    }
+
+    /// The three slots some moves cycle, given where they take every sticker, if they cycle three of these and leave
+    /// every other sticker where it is
+    fn cycled(&self, positions: &[usize]) -> Option<[usize; 3]> {
+        let moved = (0..positions.len()).filter(|&sticker| positions[sticker] != sticker).collect::<Vec<usize>>();
+        let in_slots = |sticker: &usize| self.slots.iter().any(|slot| slot.contains(sticker));
+        if !moved.iter().all(in_slots) || moved.len() != 3 * self.slots[0].len() {
+            return None;
+        }
+        let first = self.slot_of(*moved.iter().find(|&&sticker| self.slot_of(sticker).is_some())?)?;
+        let second = self.slot_of(positions[self.slots[first][0]])?;
+        let third = self.slot_of(positions[self.slots[second][0]])?;
+        (self.slot_of(positions[self.slots[third][0]])? == first).then_some([first, second, third])
+    }
+
+    /// The moves for a 3-cycle. Not every one has a commutator with a short enough setup, but cycling a, b and some
+    /// other slot d and then d, c and a comes to the same thing as cycling a, b and c
+    fn algorithm(&self, cycle: [usize; 3]) -> Option<Vec<NCubeAction<N>>> {
+        if let Some(algorithm) = self.algorithms.get(&key(cycle)) {
+            return Some(algorithm.clone());
+        }
+        let [a, b, c] = cycle;
+        (0..self.slots.len()).filter(|d| !cycle.contains(d)).find_map(|d| {
+            let first = self.algorithms.get(&key([a, b, d]))?;
+            let second = self.algorithms.get(&key([d, c, a]))?;
+            Some([first.as_slice(), second].concat())
+        })
+    }
+
+    /// Moves pieces around until `have[slot] == want[slot]` for every slot, keeping track of them in `have`. Each cycle
+    /// puts at least one more piece in place, and picks the third slot to do the most good. None if all that's left
+    /// is two pieces that need to swap, which the parity turns at the start are there to rule out
+    fn place(&self, have: &mut [usize], want: &[usize]) -> Option<Vec<NCubeAction<N>>> {
+        let mut actions = Vec::new();
+        while let Some(from) = (0..have.len()).find(|&slot| have[slot] != want[slot]) {
+            let unsolved = |slot: usize, have: &[usize]| have[slot] != want[slot];
+            let (to, third) = (0..have.len())
+                .filter(|&to| unsolved(to, have) && want[to] == have[from])
+                .flat_map(|to| (0..have.len()).map(move |third| (to, third)))
+                .filter(|&(to, third)| third != from && third != to && (unsolved(third, have) || want[third] == have[to]))
+                .max_by_key(|&(to, third)| {
+                    let placed = (want[third] == have[to]) as u8 + (want[from] == have[third]) as u8;
+                    (placed, self.algorithms.contains_key(&key([from, to, third])))
+                })?;
+            actions.extend(self.algorithm([from, to, third])?);
+            let last = have[third];
+            have[third] = have[to];
+            have[to] = have[from];
+            have[from] = last;
+        }
+        Some(actions)
+    }
+
+}
+
+/// Every 3-cycle the same way around starts with its smallest slot
+fn key(cycle: [usize; 3]) -> [usize; 3] {
+    let smallest = (0..3).min_by_key(|&i| cycle[i]).unwrap();
+    [cycle[smallest], cycle[(smallest + 1) % 3], cycle[(smallest + 2) % 3]]
+}
+
+/// Every commutator [U aF U', bX] that could cycle centers or wings, along with the other way around, where X is
+/// F or B and b is an inner layer, held each of the 24 ways. Whichever of them only cycle three pieces of a kind get
+/// used for those
+fn primitives<const N: usize>() -> Vec<Vec<NCubeAction<N>>> {
+    let mut primitives = Vec::new();
+    for a in 0..NCube::<N>::LAYERS {
+        for (face, b) in [2, 3].into_iter().flat_map(|face| (1..NCube::<N>::LAYERS).map(move |b| (face, b))) {
+            for quarter_turns in [1, 3] {
+                let first: [NCubeAction<N>; 3] = [NCubeAction::new(0, 0, 1), NCubeAction::new(2, a, 1), NCubeAction::new(0, 0, 3)];
+                let second = [NCubeAction::new(face, b, quarter_turns)];
+                primitives.push(commutator(&first, &second));
+                primitives.push(commutator(&second, &first));
+            }
+        }
+    }
+    // turning the whole cube around first just turns different faces
+    NCube::<N>::rotations().iter().flat_map(|rotation| {
+        let faces = (0..6).map(|face| rotation[face * N * N] / (N * N)).collect::<Vec<usize>>();
+        primitives.iter().map(move |primitive| {
+            primitive.iter().map(|action| NCubeAction::new(faces[action.get_face()], action.get_layer(), action.get_quarter_turns())).collect()
+        })
+    }).collect()
+}
+
+/// A B A' B'
+fn commutator<const N: usize>(a: &[NCubeAction<N>], b: &[NCubeAction<N>]) -> Vec<NCubeAction<N>> {
+    let mut actions = a.to_vec();
+    actions.extend_from_slice(b);
+    actions.extend(inverse(a));
+    actions.extend(inverse(b));
+    actions
+}
+
+fn inverse<const N: usize>(actions: &[NCubeAction<N>]) -> Vec<NCubeAction<N>> {
+    actions.iter().rev().map(|action| action.inverse()).collect()
+}
+
+/// Where each sticker ends up after the moves
+fn positions_after<const N: usize>(actions: &[NCubeAction<N>]) -> Vec<usize> {
+    let mut positions = (0..6 * N * N).collect::<Vec<usize>>();
+    for action in actions {
+        let permutation = NCube::<N>::permutation(action.get_face(), Some(action.get_layer()), action.get_quarter_turns());
+        for position in positions.iter_mut() {
+            *position = permutation[*position];
+        }
+    }
+    positions
+}
+
+/// Tries every setup of exactly `length` more moves. A setup that takes slots p to q turns a commutator cycling p
+/// into one cycling q: undo the setup, cycle, then set up again
+fn add_setups<const N: usize>(tables: &mut [&mut Cycles<N>], moves: &[NCubeAction<N>], permutations: &[Vec<usize>], setup: &mut Vec<NCubeAction<N>>, positions: &mut Vec<usize>, length: usize) {
+    if length == 0 {
+        for table in tables.iter_mut() {
+            for (cycle, primitive) in &table.primitives {
+                let moved = cycle.map(|slot| positions[table.slots[slot][0]]);
+                let Some(moved) = moved.iter().map(|&sticker| table.slot_of(sticker)).collect::<Option<Vec<usize>>>() else { continue };
+                table.algorithms.entry(key([moved[0], moved[1], moved[2]])).or_insert_with(|| {
+                    let mut algorithm = inverse(setup);
+                    algorithm.extend_from_slice(primitive);
+                    algorithm.extend_from_slice(setup);
+                    algorithm
+                });
+            }
+        }
+        return;
+    }
+    for (action, permutation) in moves.iter().zip(permutations) {
+        if setup.last().is_some_and(|previous| !action.can_follow(previous)) {
+            continue;
+        }
+        let before = positions.clone();
+        for position in positions.iter_mut() {
+            *position = permutation[*position];
+        }
+        setup.push(*action);
+        add_setups(tables, moves, permutations, setup, positions, length - 1);
+        setup.pop();
+        *positions = before;
+    }
+}
+
+/// Does the moves and adds them on, cancelling any that undo the ones before
+fn perform<const N: usize>(cube: &mut NCube<N>, actions: &mut Vec<NCubeAction<N>>, new: &[NCubeAction<N>]) {
+    for action in new {
+        *cube = cube.perform_action(action);
+        // the middle layer of an odd cube is turned from U, F or R, the same as list_actions has it
+        let action = &if 2 * action.get_layer() + 1 == N && action.get_face() % 2 == 1 {
+            NCubeAction::new(action.get_face() - 1, action.get_layer(), 4 - action.get_quarter_turns())
+        } else {
+            *action
+        };
+        match actions.last() {
+            Some(last) if (last.get_face(), last.get_layer()) == (action.get_face(), action.get_layer()) => {
+                let quarter_turns = last.get_quarter_turns() + action.get_quarter_turns();
+                actions.pop();
+                if quarter_turns % 4 != 0 {
+                    actions.push(NCubeAction::new(action.get_face(), action.get_layer(), quarter_turns));
+                }
+            }
+            _ => actions.push(*action),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////
+
+/// ReductionSolver as a search, so it can be picked by name like the others. It doesn't look at the limit (its
+/// solutions are far longer than any limit a search would be given) and takes a few milliseconds a cube once the
+/// solver has been built, so the search limits don't apply either
+pub struct ReductionSearch<const N: usize> {
+    statistics: SearchStatistics,
+    phantom: PhantomData<NCube<N>>,
+}
+
+impl<const N: usize> fmt::Display for ReductionSearch<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ReductionSearch")
+    }
+}
+
+impl<const N: usize> Search<NCube<N>, NCubeAction<N>, StackFrontier<NCube<N>, NCubeAction<N>>> for ReductionSearch<N> {
+
+    fn new(_limit: f64) -> ReductionSearch<N> {
+        ReductionSearch { statistics: SearchStatistics::new(), phantom: PhantomData }
+    }
+
+    fn search_with_limits(&mut self, start_state: NCube<N>, _limits: &SearchLimits) -> SearchOutcome<NCube<N>, NCubeAction<N>> {
+        self.statistics = SearchStatistics::new();
+        self.statistics.iteration();
+        match ReductionSolver::<N>::shared().solve(&start_state) {
+            Some(path) => {
+                let final_state = path.iter().fold(start_state.clone(), |cube, action| cube.perform_action(action));
+                SearchOutcome::Solved(Solution::new(start_state, final_state, path, self.statistics.clone()))
+            }
+            None => SearchOutcome::Exhausted(self.statistics.clone()),
+        }
+    }
+
+    fn prune(&mut self, _node: &Node<NCube<N>, NCubeAction<N>>) -> bool {
+        false
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+/// The outer stickers of a cube that's been reduced, as a 3x3x3
+fn reduced<const N: usize>(cube: &NCube<N>) -> CubeState {
+    let mut state = CubeState::new();
+    let scale = |i: usize| if i == 2 { N - 1 } else { i };
+    for face in 0..6 {
+        for (i, &(row, column)) in STICKER_POSITIONS.iter().enumerate() {
+            state.set_sticker(face, i, cube.get_sticker_at(face, scale(row), scale(column)));
+        }
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::puzzles::rubiks_cube::CubeAction;
+
+    use super::*;
+
+    fn five() -> &'static ReductionSolver<5> {
+        static SOLVER: OnceLock<ReductionSolver<5>> = OnceLock::new();
+        SOLVER.get_or_init(ReductionSolver::new)
+    }
+
+    /// Turning an NCube<3> is the same as turning a CubeState the other way
+    #[test]
+    fn reduced_cube_matches_cube_state() {
+        let mut rng = StdRng::seed_from_u64(24);
+        let (cube, scramble) = NCube::<3>::scrambled_with(&mut rng, 30);
+        let mut state = CubeState::default();
+        for action in scramble.iter().filter(|action| action.get_layer() == 0) {
+            let face = ACTION_FACES.iter().position(|&face| face == action.get_face()).unwrap();
+            state = state.perform_action(&CubeAction::from_face(face, 4 - action.get_quarter_turns()).unwrap());
+        }
+        let outer = scramble.iter().filter(|action| action.get_layer() == 0).fold(NCube::<3>::default(), |cube, action| cube.perform_action(action));
+        assert!(reduced(&outer) == state);
+        assert!(scramble.iter().any(|action| action.get_layer() == 1) && reduced(&cube) != state);
+    }
+
+    #[test]
+    fn every_cycle_can_be_done() {
+        let solver = five();
+        for cycles in solver.centers.iter().chain(solver.wings.iter().map(|(_, cycles)| cycles)) {
+            for (cycle, algorithm) in cycles.algorithms.iter().take(50) {
+                assert_eq!(cycles.cycled(&positions_after::<5>(algorithm)).map(key), Some(*cycle));
+            }
+            let slots = cycles.slots.len();
+            for cycle in [[0, 1, 2], [2, 1, 0], [0, slots / 2, slots - 1], [slots - 1, slots / 2, 0]] {
+                assert_eq!(cycles.cycled(&positions_after::<5>(&cycles.algorithm(cycle).unwrap())).map(key), Some(key(cycle)));
+            }
+        }
+    }
+
+    fn assert_solves<const N: usize>(solver: &ReductionSolver<N>, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..3 {
+            let (cube, _) = NCube::<N>::scrambled_with(&mut rng, 60);
+            let solution = solver.solve(&cube).unwrap();
+            assert!(solution.iter().all(|action| cube.list_actions().contains(action)));
+            let solved = solution.iter().fold(cube, |cube, action| cube.perform_action(action));
+            assert!(solved.is_goal_state());
+        }
+    }
+
+    #[test]
+    fn solves_scrambled_cubes() {
+        assert_solves(&ReductionSolver::<3>::new(), 3);
+        assert_solves(&ReductionSolver::<4>::new(), 4);
+        assert_solves(five(), 5);
+    }
+
+    /// Swapping two wings is only parity, but twisting a corner can't be done by turning
+    #[test]
+    fn twisted_corner_is_unsolvable() {
+        let solver = ReductionSolver::<4>::new();
+        let mut stickers = (0..96).map(|sticker| NCube::<4>::default().get_sticker(sticker)).collect::<Vec<u8>>();
+        let wings = NCube::<4>::wing_slots(1);
+        for (&first, &second) in wings[0].iter().zip(&wings[1]) {
+            stickers.swap(first, second);
+        }
+        assert!(solver.solve(&NCube::<4>::from_stickers(&stickers).unwrap()).is_some());
+
+        let corner = NCube::<4>::corner_slots()[0];
+        let colors = corner.map(|sticker| stickers[sticker]);
+        for i in 0..3 {
+            stickers[corner[i]] = colors[(i + 1) % 3];
+        }
+        assert_eq!(solver.solve(&NCube::<4>::from_stickers(&stickers).unwrap()), None);
+    }
+
+}
@@ -1,12 +1,15 @@
 // Author: Harper Davis
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
-use rand::{rngs::ThreadRng, Rng};
+use rand::Rng;
 
-use crate::{state::State, action::Action};
+use crate::{state::State, action::{Action, InvertibleAction}, heuristic::Heuristic};
 
+use super::{cubie_cube::CubieCube, rubiks_cube_heuristics::CubieManhattanDistance};
+
+/// The colors of the stickers, numbered by the face they belong on when solved
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-enum Color {
+pub(crate) enum Color {
     White = 0,
     Yellow = 1,
     Red = 2,
@@ -19,8 +22,8 @@ impl Color {
 
     fn to_face(colors: [Color; 8]) -> u32 {
         let mut face = 0_u32;
-        for i in 0..8 {
-            face |= (colors[i] as u32) << (i * 4);
+        for (i, color) in colors.iter().enumerate() {
+            face |= (*color as u32) << (i * 4);
         }
         face
     }
@@ -64,7 +67,7 @@ impl Color {
     //     }
     // }
 
-    fn from_index(index: u8) -> Color {
+    pub(crate) fn from_index(index: u8) -> Color {
         match index & 0b0111 {
             0 => Color::White,
             1 => Color::Yellow,
@@ -87,7 +90,7 @@ impl Color {
     //     }
     // }
 
-    fn to_colored(&self, string: &str) -> String {
+    pub(crate) fn to_colored(self, string: &str) -> String {
         match self {
             Color::White => format!("\x1b[37m{}\x1b[0m", string),
             Color::Yellow => format!("\x1b[33m{}\x1b[0m", string),
@@ -99,7 +102,7 @@ impl Color {
     }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct CubeState {
     pub faces: [u32; 6],
 }
@@ -111,7 +114,7 @@ const ROTATIONS: [[[u8; 4]; 2]; 6] = [
     [[3,5,2,4],[0,0,0,0]], // White
     [[2,5,3,4],[4,4,4,4]],
     [[0,5,1,4],[4,6,0,2]],
-    [[0,4,1,5],[0,6,0,2]],
+    [[0,4,1,5],[0,6,4,2]],
     [[0,2,1,3],[6,6,6,2]],
     [[0,3,1,2],[2,6,2,2]],
 ];
@@ -120,16 +123,12 @@ impl CubeState {
 
     pub fn new() -> Self {
         let mut faces = [0_u32; 6];
-        for i in 0..6 {
-            faces[i] = Color::to_face([Color::from_index(i as u8); 8]);
+        for (i, face) in faces.iter_mut().enumerate() {
+            *face = Color::to_face([Color::from_index(i as u8); 8]);
         }
         Self { faces }
     }
 
-    pub fn default() -> Self {
-        Self { faces: Self::default_cube() }
-    }
-
     pub fn default_cube() -> [u32; 6] {
         [
             Color::to_face([Color::White; 8]), 
@@ -141,12 +140,25 @@ impl CubeState {
         ]
     }
 
+    /// The color of one sticker, given as the index of the face that color belongs to when solved
+    pub fn get_sticker(&self, face: usize, index: usize) -> u8 {
+        Color::get_color_at(self.faces[face], index) as u8
+    }
+
+    pub fn set_sticker(&mut self, face: usize, index: usize, color: u8) {
+        self.faces[face] = Color::set_color_at(self.faces[face], index, Color::from_index(color));
+    }
+
     pub fn scrambled_cube(action_count: u32) -> (Self, Vec<CubeAction>) {
-        let mut rng = rand::thread_rng();
+        Self::scrambled_cube_with(&mut rand::thread_rng(), action_count)
+    }
+
+    /// Same as scrambled_cube, but with the moves picked by the given rng so a seeded one gives the same scramble every time
+    pub fn scrambled_cube_with<R: Rng + ?Sized>(rng: &mut R, action_count: u32) -> (Self, Vec<CubeAction>) {
         let mut cube = CubeState::default();
         let mut actions = Vec::new();
         for _ in 0..action_count {
-            let action = CubeAction::random_move(&mut rng);
+            let action = CubeAction::random_move(rng);
             cube = cube.perform_action(&action);
             actions.push(action);
         }
@@ -154,7 +166,7 @@ impl CubeState {
     }
 
     pub fn rotate_cw(faces: [u32; 6], face: u8) -> [u32; 6] {
-        let mut new_faces = faces.clone();
+        let mut new_faces = faces;
         let face_index = face as usize;
 
         new_faces[face_index] = faces[face_index].rotate_left(8);
@@ -172,7 +184,7 @@ impl CubeState {
     }
 
     pub fn rotate_ccw(faces: [u32; 6], face: u8) -> [u32; 6] {
-        let mut new_faces = faces.clone();
+        let mut new_faces = faces;
         let face_index = face as usize;
 
         new_faces[face_index] = faces[face_index].rotate_right(8);
@@ -190,7 +202,7 @@ impl CubeState {
     }
 
     pub fn rotate_180(faces: [u32; 6], face: u8) -> [u32; 6] {
-        let mut new_faces = faces.clone();
+        let mut new_faces = faces;
         let face_index = face as usize;
 
         new_faces[face_index] = faces[face_index].rotate_left(16);
@@ -209,6 +221,14 @@ impl CubeState {
     
 }
 
+impl Default for CubeState {
+
+    fn default() -> Self {
+        Self { faces: Self::default_cube() }
+    }
+
+}
+
 fn fcol(state: &CubeState, color: Color, index: usize) -> String {
     Color::get_color_at(state.faces[color as usize], index).to_colored("▓")
 }
@@ -217,10 +237,26 @@ fn ccol(color: Color) -> String {
     color.to_colored("▓")
 }
 
-impl ToString for CubeState {
+impl fmt::Display for CubeState {
 
-    fn to_string(&self) -> String {
-        format!("{:#010x},{:#010x},{:#010x},{:#010x},{:#010x},{:#010x}", self.faces[0], self.faces[1], self.faces[2], self.faces[3], self.faces[4], self.faces[5])
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#010x},{:#010x},{:#010x},{:#010x},{:#010x},{:#010x}", self.faces[0], self.faces[1], self.faces[2], self.faces[3], self.faces[4], self.faces[5])
+    }
+
+}
+
+/// The six comma separated faces that Display writes, every sticker has to be one of the six colors
+impl FromStr for CubeState {
+
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let faces = s.trim().split(',').map(|face| u32::from_str_radix(face.trim().trim_start_matches("0x"), 16)).collect::<Result<Vec<u32>, _>>().map_err(|_| ())?;
+        let faces: [u32; 6] = faces.try_into().map_err(|_| ())?;
+        if faces.iter().any(|face| (0..8).any(|i| (face >> (i * 4)) & 0b1111 > Color::Blue as u32)) {
+            return Err(());
+        }
+        Ok(CubeState { faces })
     }
 
 }
@@ -232,7 +268,7 @@ impl State for CubeState {
         println!("   {}{}{}      \n   {}{}{}      \n   {}{}{}      ", 
             fcol(self, Color::Orange, 4), fcol(self, Color::Orange, 5), fcol(self, Color::Orange, 6),
             fcol(self, Color::Orange, 3), ccol(Color::Orange), fcol(self, Color::Orange, 7),
-            fcol(self, Color::Orange, 2), fcol(self, Color::Orange, 1), fcol(self, Color::Orange, 1)
+            fcol(self, Color::Orange, 2), fcol(self, Color::Orange, 1), fcol(self, Color::Orange, 0)
         );
         println!("{}{}{}{}{}{}{}{}{}{}{}{}", 
             fcol(self, Color::Green, 6), fcol(self, Color::Green, 7), fcol(self, Color::Green, 0), 
@@ -263,31 +299,23 @@ impl State for CubeState {
         self.faces == CubeState::default_cube()
     }
 
+    fn goal_states(&self) -> Vec<Self> {
+        vec![CubeState::default()]
+    }
+
     fn list_actions(&self) -> Vec<Self::Action> {
-        vec![
-            CubeAction::U,
-            CubeAction::UPrime,
-            CubeAction::U2,
-            CubeAction::D,
-            CubeAction::DPrime,
-            CubeAction::D2,
-            CubeAction::R,
-            CubeAction::RPrime,
-            CubeAction::R2,
-            CubeAction::L,
-            CubeAction::LPrime,
-            CubeAction::L2,
-            CubeAction::F,
-            CubeAction::FPrime,
-            CubeAction::F2,
-            CubeAction::B,
-            CubeAction::BPrime,
-            CubeAction::B2,
-        ]
+        CubeAction::ALL.to_vec()
+    }
+
+    fn list_actions_after(&self, previous: Option<&Self::Action>) -> Vec<Self::Action> {
+        match previous {
+            Some(previous) => CubeAction::ALL.iter().filter(|action| action.can_follow(previous)).cloned().collect(),
+            None => self.list_actions(),
+        }
     }
 
     fn perform_action(&self, action: &Self::Action) -> Self {
-        let mut new_faces = self.faces.clone();
+        let mut new_faces = self.faces;
         match action {
             CubeAction::U => new_faces = CubeState::rotate_cw(new_faces, 0),
             CubeAction::UPrime => new_faces = CubeState::rotate_ccw(new_faces, 0),
@@ -312,8 +340,11 @@ impl State for CubeState {
     }
 
     fn heuristic(&self) -> f64 {
-        // 3d manhattan
-        0.0
+        CubieManhattanDistance.estimate(self)
+    }
+
+    fn is_solvable(&self) -> bool {
+        CubieCube::from_state(self).is_some_and(|cubies| cubies.is_solvable())
     }
 
 }
@@ -328,29 +359,30 @@ pub enum CubeAction {
     B, BPrime, B2,
 }
 
-impl ToString for CubeAction {
-
-    fn to_string(&self) -> String {
-        match self {
-            CubeAction::U => "U".to_string(),
-            CubeAction::UPrime => "U'".to_string(),
-            CubeAction::U2 => "U2".to_string(),
-            CubeAction::D => "D".to_string(),
-            CubeAction::DPrime => "D'".to_string(),
-            CubeAction::D2 => "D2".to_string(),
-            CubeAction::R => "R".to_string(),
-            CubeAction::RPrime => "R'".to_string(),
-            CubeAction::R2 => "R2".to_string(),
-            CubeAction::L => "L".to_string(),
-            CubeAction::LPrime => "L'".to_string(),
-            CubeAction::L2 => "L2".to_string(),
-            CubeAction::F => "F".to_string(),
-            CubeAction::FPrime => "F'".to_string(),
-            CubeAction::F2 => "F2".to_string(),
-            CubeAction::B => "B".to_string(),
-            CubeAction::BPrime => "B'".to_string(),
-            CubeAction::B2 => "B2".to_string(),
-        }
+impl fmt::Display for CubeAction {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let notation = match self {
+            CubeAction::U => "U",
+            CubeAction::UPrime => "U'",
+            CubeAction::U2 => "U2",
+            CubeAction::D => "D",
+            CubeAction::DPrime => "D'",
+            CubeAction::D2 => "D2",
+            CubeAction::R => "R",
+            CubeAction::RPrime => "R'",
+            CubeAction::R2 => "R2",
+            CubeAction::L => "L",
+            CubeAction::LPrime => "L'",
+            CubeAction::L2 => "L2",
+            CubeAction::F => "F",
+            CubeAction::FPrime => "F'",
+            CubeAction::F2 => "F2",
+            CubeAction::B => "B",
+            CubeAction::BPrime => "B'",
+            CubeAction::B2 => "B2",
+        };
+        write!(f, "{}", notation)
     }
 
 }
@@ -363,16 +395,22 @@ impl FromStr for CubeAction {
         match s {
             "U" => Ok(CubeAction::U),
             "U'" => Ok(CubeAction::UPrime),
+            "U2" => Ok(CubeAction::U2),
             "D" => Ok(CubeAction::D),
             "D'" => Ok(CubeAction::DPrime),
+            "D2" => Ok(CubeAction::D2),
             "R" => Ok(CubeAction::R),
             "R'" => Ok(CubeAction::RPrime),
+            "R2" => Ok(CubeAction::R2),
             "L" => Ok(CubeAction::L),
             "L'" => Ok(CubeAction::LPrime),
+            "L2" => Ok(CubeAction::L2),
             "F" => Ok(CubeAction::F),
             "F'" => Ok(CubeAction::FPrime),
+            "F2" => Ok(CubeAction::F2),
             "B" => Ok(CubeAction::B),
             "B'" => Ok(CubeAction::BPrime),
+            "B2" => Ok(CubeAction::B2),
             _ => Err(()),
         }
     }
@@ -387,9 +425,79 @@ impl Action for CubeAction {
 
 }
 
+impl InvertibleAction for CubeAction {
+
+    fn inverse(&self) -> Self {
+        match self {
+            CubeAction::U => CubeAction::UPrime,
+            CubeAction::UPrime => CubeAction::U,
+            CubeAction::D => CubeAction::DPrime,
+            CubeAction::DPrime => CubeAction::D,
+            CubeAction::R => CubeAction::RPrime,
+            CubeAction::RPrime => CubeAction::R,
+            CubeAction::L => CubeAction::LPrime,
+            CubeAction::LPrime => CubeAction::L,
+            CubeAction::F => CubeAction::FPrime,
+            CubeAction::FPrime => CubeAction::F,
+            CubeAction::B => CubeAction::BPrime,
+            CubeAction::BPrime => CubeAction::B,
+            half_turn => half_turn.clone(),
+        }
+    }
+
+}
+
 impl CubeAction {
 
-    fn random_move(rng: &mut ThreadRng) -> CubeAction {
+    /// Every move, grouped by face in the order U, D, R, L, F, B, and as clockwise, counterclockwise and half turn within each face
+    pub const ALL: [CubeAction; 18] = [
+        CubeAction::U, CubeAction::UPrime, CubeAction::U2,
+        CubeAction::D, CubeAction::DPrime, CubeAction::D2,
+        CubeAction::R, CubeAction::RPrime, CubeAction::R2,
+        CubeAction::L, CubeAction::LPrime, CubeAction::L2,
+        CubeAction::F, CubeAction::FPrime, CubeAction::F2,
+        CubeAction::B, CubeAction::BPrime, CubeAction::B2,
+    ];
+
+    /// Which face the move turns, numbered in the same order as ALL, so that opposite faces are 2k and 2k + 1
+    pub fn face(&self) -> usize {
+        match self {
+            CubeAction::U | CubeAction::UPrime | CubeAction::U2 => 0,
+            CubeAction::D | CubeAction::DPrime | CubeAction::D2 => 1,
+            CubeAction::R | CubeAction::RPrime | CubeAction::R2 => 2,
+            CubeAction::L | CubeAction::LPrime | CubeAction::L2 => 3,
+            CubeAction::F | CubeAction::FPrime | CubeAction::F2 => 4,
+            CubeAction::B | CubeAction::BPrime | CubeAction::B2 => 5,
+        }
+    }
+
+    /// How many clockwise quarter turns the move is, 1, 2 or 3
+    pub fn quarter_turns(&self) -> u8 {
+        match CubeAction::ALL.iter().position(|action| action == self).unwrap() % 3 {
+            0 => 1,
+            1 => 3,
+            _ => 2,
+        }
+    }
+
+    /// The move that turns a face (numbered like face()) clockwise by that many quarter turns, None for a multiple of 4
+    pub fn from_face(face: usize, quarter_turns: u8) -> Option<CubeAction> {
+        match quarter_turns % 4 {
+            0 => None,
+            1 => Some(CubeAction::ALL[face * 3].clone()),
+            2 => Some(CubeAction::ALL[face * 3 + 2].clone()),
+            _ => Some(CubeAction::ALL[face * 3 + 1].clone()),
+        }
+    }
+
+    /// Turning the same face twice in a row can always be done in one move (or none), and turns of opposite
+    /// faces commute, so only one of their two orders is needed. That takes the branching factor from 18 to about 13.35
+    pub fn can_follow(&self, previous: &CubeAction) -> bool {
+        let (previous_face, face) = (previous.face(), self.face());
+        previous_face != face && !(previous_face / 2 == face / 2 && previous_face > face)
+    }
+
+    fn random_move<R: Rng + ?Sized>(rng: &mut R) -> CubeAction {
         let random = rng.gen_range(0..18);
         match random {
             0 => CubeAction::U,
@@ -414,4 +522,83 @@ impl CubeAction {
         }
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    /// The same hundred cubes every run, scrambled by up to 30 moves
+    fn scrambled_cubes() -> Vec<CubeState> {
+        let mut rng = StdRng::seed_from_u64(21);
+        (0..100).map(|i| CubeState::scrambled_cube_with(&mut rng, i % 31).0).collect()
+    }
+
+    #[test]
+    fn clockwise_then_counterclockwise_is_nothing() {
+        for cube in scrambled_cubes() {
+            for face in 0..6 {
+                assert_eq!(CubeState::rotate_ccw(CubeState::rotate_cw(cube.faces, face), face), cube.faces, "face {} of {}", face, cube);
+                assert_eq!(CubeState::rotate_cw(CubeState::rotate_ccw(cube.faces, face), face), cube.faces, "face {} of {}", face, cube);
+            }
+        }
+    }
+
+    #[test]
+    fn four_quarter_turns_are_nothing() {
+        for cube in scrambled_cubes() {
+            for face in 0..6 {
+                let faces = (0..4).fold(cube.faces, |faces, _| CubeState::rotate_cw(faces, face));
+                assert_eq!(faces, cube.faces, "face {} of {}", face, cube);
+                let faces = (0..4).fold(cube.faces, |faces, _| CubeState::rotate_ccw(faces, face));
+                assert_eq!(faces, cube.faces, "face {} of {}", face, cube);
+            }
+        }
+    }
+
+    #[test]
+    fn half_turn_is_two_quarter_turns() {
+        for cube in scrambled_cubes() {
+            for face in 0..6 {
+                let half_turn = CubeState::rotate_180(cube.faces, face);
+                assert_eq!(half_turn, CubeState::rotate_cw(CubeState::rotate_cw(cube.faces, face), face), "face {} of {}", face, cube);
+                assert_eq!(half_turn, CubeState::rotate_ccw(CubeState::rotate_ccw(cube.faces, face), face), "face {} of {}", face, cube);
+            }
+        }
+    }
+
+    #[test]
+    fn every_move_changes_a_solved_cube() {
+        let cube = CubeState::default();
+        for action in CubeAction::ALL {
+            assert!(cube.perform_action(&action) != cube, "{}", action);
+        }
+    }
+
+    #[test]
+    fn every_move_is_undone_by_its_inverse() {
+        for cube in scrambled_cubes() {
+            for action in CubeAction::ALL {
+                assert!(cube.perform_action(&action).perform_action(&action.inverse()) == cube, "{} on {}", action, cube);
+            }
+        }
+    }
+
+    #[test]
+    fn moves_keep_the_stickers_and_solvability() {
+        for cube in scrambled_cubes() {
+            let mut counts = [0; 6];
+            for face in 0..6 {
+                for index in 0..8 {
+                    counts[cube.get_sticker(face, index) as usize] += 1;
+                }
+            }
+            assert_eq!(counts, [8; 6], "{}", cube);
+            assert!(cube.is_solvable(), "{}", cube);
+            assert!(cube.to_string().parse::<CubeState>() == Ok(cube.clone()));
+        }
+    }
+
+}
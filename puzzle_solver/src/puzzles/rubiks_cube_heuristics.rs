@@ -0,0 +1,115 @@
+// Author: Harper Davis
+use std::{collections::VecDeque, sync::OnceLock};
+
+use crate::{heuristic::Heuristic, state::State};
+
+use super::{cubie_cube::CubieCube, rubiks_cube::CubeState};
+
+/// The 3D manhattan distance. Every corner and edge piece on its own needs some number of moves to
+/// get back into its slot the right way around, and since one move only ever carries 4 corners and
+/// 4 edges, the corner total over 4 and the edge total over 4 are both lower bounds
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CubieManhattanDistance;
+
+impl CubieManhattanDistance {
+
+    pub fn estimate_cubies(&self, cubies: &CubieCube) -> f64 {
+        let tables = distance_tables();
+
+        let mut corner_total = 0;
+        for slot in 0..8 {
+            let corner = cubies.corner_permutation[slot] as usize;
+            corner_total += tables.corners[corner][slot * 3 + cubies.corner_orientation[slot] as usize] as u32;
+        }
+        let mut edge_total = 0;
+        for slot in 0..12 {
+            let edge = cubies.edge_permutation[slot] as usize;
+            edge_total += tables.edges[edge][slot * 2 + cubies.edge_orientation[slot] as usize] as u32;
+        }
+
+        u32::max(corner_total.div_ceil(4), edge_total.div_ceil(4)) as f64
+    }
+
+}
+
+impl Heuristic<CubeState> for CubieManhattanDistance {
+    fn estimate(&self, state: &CubeState) -> f64 {
+        CubieCube::from_state(state).map_or(0.0, |cubies| self.estimate_cubies(&cubies))
+    }
+}
+
+/// tables.corners[piece][slot * 3 + orientation] is the fewest moves that bring that piece home from there
+struct DistanceTables {
+    corners: [[u8; 24]; 8],
+    edges: [[u8; 24]; 12],
+}
+
+fn distance_tables() -> &'static DistanceTables {
+    static TABLES: OnceLock<DistanceTables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let moves = CubeState::default().list_actions().iter().map(CubieCube::from_action).collect::<Vec<CubieCube>>();
+
+        let mut corners = [[0; 24]; 8];
+        for (corner, distances) in corners.iter_mut().enumerate() {
+            *distances = single_piece_distances(corner, 3, |position, cube| {
+                // the slot that the piece gets carried to, and how much it gets twisted on the way
+                let (slot, orientation) = (position / 3, position % 3);
+                let to = cube.corner_permutation.iter().position(|&from| from as usize == slot).unwrap();
+                to * 3 + (orientation + cube.corner_orientation[to] as usize) % 3
+            }, &moves);
+        }
+
+        let mut edges = [[0; 24]; 12];
+        for (edge, distances) in edges.iter_mut().enumerate() {
+            *distances = single_piece_distances(edge, 2, |position, cube| {
+                let (slot, orientation) = (position / 2, position % 2);
+                let to = cube.edge_permutation.iter().position(|&from| from as usize == slot).unwrap();
+                to * 2 + (orientation + cube.edge_orientation[to] as usize) % 2
+            }, &moves);
+        }
+
+        DistanceTables { corners, edges }
+    })
+}
+
+/// Breadth first search from the piece's home over just where it is and which way it faces.
+/// Every move's inverse is also a move, so the distance out is the same as the distance back
+fn single_piece_distances(home: usize, orientations: usize, apply: impl Fn(usize, &CubieCube) -> usize, moves: &[CubieCube]) -> [u8; 24] {
+    let mut distances = [u8::MAX; 24];
+    distances[home * orientations] = 0;
+    let mut queue = VecDeque::from([home * orientations]);
+    while let Some(position) = queue.pop_front() {
+        for cube in moves {
+            let next = apply(position, cube);
+            if distances[next] == u8::MAX {
+                distances[next] = distances[position] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn heuristic_is_admissible() {
+        assert_eq!(CubieManhattanDistance.estimate(&CubeState::default()), 0.0);
+        for action in CubeState::default().list_actions() {
+            assert_eq!(CubieManhattanDistance.estimate(&CubeState::default().perform_action(&action)), 1.0);
+        }
+        let mut rng = StdRng::seed_from_u64(7);
+        for length in 0..30 {
+            let (cube, _) = CubeState::scrambled_cube_with(&mut rng, length);
+            let estimate = CubieManhattanDistance.estimate(&cube);
+            assert!(estimate <= length as f64, "{} for a scramble of {} moves", estimate, length);
+            assert_eq!(estimate == 0.0, cube.is_goal_state());
+            assert_eq!(CubieCube::from_state(&cube).map(|cubies| CubieManhattanDistance.estimate_cubies(&cubies)), Some(estimate));
+        }
+    }
+
+}
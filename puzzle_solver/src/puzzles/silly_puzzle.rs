@@ -1,9 +1,10 @@
 // Author: Harper Davis
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use colored::Colorize;
+use rand::Rng;
 
-use crate::{state::State, action::Action};
+use crate::{state::State, action::{Action, InvertibleAction}};
 
 #[derive(PartialEq, Eq, Clone, Hash)]
 pub struct SillyPuzzle {
@@ -14,16 +15,36 @@ impl SillyPuzzle {
     pub fn new(n: i32) -> SillyPuzzle {
         SillyPuzzle { n }
     }
+
+    /// Anywhere on the line that display_pretty draws
+    pub fn random_with<R: Rng + ?Sized>(rng: &mut R) -> SillyPuzzle {
+        SillyPuzzle::new(rng.gen_range(0..100))
+    }
+
+    /// The shortest way to 69, which is just walking straight there
+    pub fn path_to_goal(&self) -> Vec<SillyPuzzleAction> {
+        vec![SillyPuzzleAction::new((69 - self.n).signum()); (69 - self.n).unsigned_abs() as usize]
+    }
 }
 
-impl ToString for SillyPuzzle {
+impl fmt::Display for SillyPuzzle {
     
-    fn to_string(&self) -> String {
-        self.n.to_string()
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.n)
     }
     
 }
 
+impl FromStr for SillyPuzzle {
+
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim().parse::<i32>().map(SillyPuzzle::new).map_err(|_| ())
+    }
+
+}
+
 impl State for SillyPuzzle {
     type Action = SillyPuzzleAction;
 
@@ -47,6 +68,10 @@ impl State for SillyPuzzle {
         self.n == 69
     }
 
+    fn goal_states(&self) -> Vec<Self> {
+        vec![SillyPuzzle::new(69)]
+    }
+
     fn heuristic(&self) -> f64 {
         f64::abs((69 - self.n).into())
     }
@@ -64,13 +89,13 @@ impl SillyPuzzleAction {
     }
 }
 
-impl ToString for SillyPuzzleAction {
+impl fmt::Display for SillyPuzzleAction {
     
-    fn to_string(&self) -> String {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.change == 1 {
-            "+".to_string()
+            write!(f, "+")
         } else {
-            "-".to_string()
+            write!(f, "-")
         }
     }
 }
@@ -97,4 +122,12 @@ impl Action for SillyPuzzleAction {
         1.0
     }
 
-}
\ No newline at end of file
+}
+
+impl InvertibleAction for SillyPuzzleAction {
+
+    fn inverse(&self) -> Self {
+        SillyPuzzleAction::new(-self.change)
+    }
+
+}
@@ -0,0 +1,425 @@
+// Author: Harper Davis
+use std::{fmt, str::FromStr};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{state::State, action::{Action, InvertibleAction}};
+
+/// Where the tiles are kept. Boards of up to 25 cells fit in a u128 (4 bits a tile up to 16 cells, 5 after that), anything bigger gets a byte a tile
+#[derive(PartialEq, Eq, Clone, Hash)]
+enum Tiles {
+    Packed(u128),
+    Unpacked(Box<[u8]>),
+}
+
+/// Any W wide and H high sliding tile puzzle, from 2 by 2 up to 256 cells. Cells are numbered across the rows starting
+/// at the top left, and it's solved when every tile is in the cell with its own number, so the blank (0) ends up in
+/// the top left, the same as the boards in tests/15_puzzle.txt
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct SlidingPuzzle<const W: usize, const H: usize> {
+    tiles: Tiles,
+    blank: u8,
+}
+
+pub type EightPuzzle = SlidingPuzzle<3, 3>;
+pub type TwentyFourPuzzle = SlidingPuzzle<5, 5>;
+
+/// Every tile in its own cell, packed `bits` to a tile
+const fn packed_goal(cells: usize, bits: usize) -> u128 {
+    let mut board = 0;
+    let mut i = 0;
+    while i < cells {
+        board |= (i as u128) << (i * bits);
+        i += 1;
+    }
+    board
+}
+
+impl<const W: usize, const H: usize> SlidingPuzzle<W, H> {
+
+    /// A single row or column can't be solved the way is_solvable works out, and a tile has to fit in a byte
+    const CELLS: usize = {
+        assert!(W >= 2 && H >= 2 && W * H <= 256, "sliding puzzles go from 2 by 2 up to 256 cells");
+        W * H
+    };
+    const BITS: usize = if Self::CELLS <= 16 { 4 } else { 5 };
+    const PACKED: bool = Self::CELLS * Self::BITS <= 128;
+    /// Only worked out for packed boards, the others don't fit in a u128
+    const GOAL: u128 = if Self::PACKED { packed_goal(Self::CELLS, Self::BITS) } else { 0 };
+    /// Hex digits a tile takes up when written out
+    const DIGITS: usize = if Self::CELLS <= 16 { 1 } else { 2 };
+
+    /// The board with `tiles[i]` in cell i, or None unless every number from 0 to W * H - 1 shows up exactly once
+    pub fn from_tiles(tiles: &[u8]) -> Option<SlidingPuzzle<W, H>> {
+        let mut seen = vec![false; Self::CELLS];
+        if tiles.len() != Self::CELLS {
+            return None;
+        }
+        for &tile in tiles {
+            if tile as usize >= Self::CELLS || seen[tile as usize] {
+                return None;
+            }
+            seen[tile as usize] = true;
+        }
+
+        let blank = tiles.iter().position(|&tile| tile == 0).unwrap() as u8;
+        let tiles = if Self::PACKED {
+            Tiles::Packed(tiles.iter().enumerate().fold(0, |board, (i, &tile)| board | (tile as u128) << (i * Self::BITS)))
+        } else {
+            Tiles::Unpacked(tiles.into())
+        };
+        Some(SlidingPuzzle { tiles, blank })
+    }
+
+    /// A board picked evenly out of all the solvable ones
+    pub fn random() -> SlidingPuzzle<W, H> {
+        Self::random_with(&mut rand::thread_rng())
+    }
+
+    pub fn random_with<R: Rng + ?Sized>(rng: &mut R) -> SlidingPuzzle<W, H> {
+        let mut tiles = (0..Self::CELLS).map(|i| i as u8).collect::<Vec<u8>>();
+        tiles.shuffle(rng);
+        let mut puzzle = Self::from_tiles(&tiles).unwrap();
+        // swapping two tiles flips the inversion parity, which turns exactly the unsolvable half into the solvable half
+        if !puzzle.is_solvable() {
+            let mut tiles = (0..Self::CELLS).filter(|&i| i != puzzle.get_blank_index());
+            let (first, second) = (tiles.next().unwrap(), tiles.next().unwrap());
+            let first_tile = puzzle.get_tile(first);
+            puzzle.set_tile(first, puzzle.get_tile(second));
+            puzzle.set_tile(second, first_tile);
+        }
+        puzzle
+    }
+
+    /// The board after a random walk of `action_count` slides from the goal, never sliding the same tile twice in a
+    /// row, along with the slides that undo it
+    pub fn scrambled(action_count: u32) -> (SlidingPuzzle<W, H>, Vec<SlidingPuzzleAction>) {
+        Self::scrambled_with(&mut rand::thread_rng(), action_count)
+    }
+
+    pub fn scrambled_with<R: Rng + ?Sized>(rng: &mut R, action_count: u32) -> (SlidingPuzzle<W, H>, Vec<SlidingPuzzleAction>) {
+        let mut puzzle = SlidingPuzzle::default();
+        let mut actions: Vec<SlidingPuzzleAction> = Vec::new();
+        for _ in 0..action_count {
+            let action = puzzle.list_actions_after(actions.last()).choose(rng).unwrap().clone();
+            puzzle = puzzle.perform_action(&action);
+            actions.push(action);
+        }
+        actions.reverse();
+        (puzzle, actions)
+    }
+
+    pub fn get_tile(&self, index: usize) -> u8 {
+        match &self.tiles {
+            Tiles::Packed(board) => ((board >> (index * Self::BITS)) & ((1 << Self::BITS) - 1)) as u8,
+            Tiles::Unpacked(tiles) => tiles[index],
+        }
+    }
+
+    fn set_tile(&mut self, index: usize, tile: u8) {
+        match &mut self.tiles {
+            Tiles::Packed(board) => {
+                *board &= !(((1 << Self::BITS) - 1) << (index * Self::BITS));
+                *board |= (tile as u128) << (index * Self::BITS);
+            }
+            Tiles::Unpacked(tiles) => tiles[index] = tile,
+        }
+    }
+
+    pub fn get_blank_index(&self) -> usize {
+        self.blank as usize
+    }
+
+    /// The cells next to the blank, in the order above, below, left, right
+    fn blank_neighbours(&self) -> impl Iterator<Item = usize> {
+        let blank = self.get_blank_index();
+        let (x, y) = (blank % W, blank / W);
+        [
+            (y > 0).then(|| blank - W),
+            (y + 1 < H).then(|| blank + W),
+            (x > 0).then(|| blank - 1),
+            (x + 1 < W).then(|| blank + 1),
+        ].into_iter().flatten()
+    }
+
+    /// Parity of the number of pairs of tiles (not counting the blank) that are out of order
+    pub fn inversion_parity(&self) -> u8 {
+        let tiles = (0..Self::CELLS).map(|i| self.get_tile(i)).filter(|&tile| tile != 0).collect::<Vec<u8>>();
+        let mut inversions = 0;
+        for i in 0..tiles.len() {
+            inversions += tiles[i + 1..].iter().filter(|&&other| other < tiles[i]).count();
+        }
+        (inversions % 2) as u8
+    }
+
+    /// How far every tile is from its own cell, going only up, down, left and right
+    pub fn manhattan_distance(&self) -> u32 {
+        let mut distance = 0;
+        for i in 0..Self::CELLS {
+            let tile = self.get_tile(i) as usize;
+            if tile == 0 { continue; }
+            distance += (i % W).abs_diff(tile % W) + (i / W).abs_diff(tile / W);
+        }
+        distance as u32
+    }
+
+}
+
+impl<const W: usize, const H: usize> Default for SlidingPuzzle<W, H> {
+
+    fn default() -> SlidingPuzzle<W, H> {
+        Self::from_tiles(&(0..Self::CELLS).map(|i| i as u8).collect::<Vec<u8>>()).unwrap()
+    }
+
+}
+
+/// The same as the boards in tests/15_puzzle.txt: the tile in cell 0 is the last hex digit, cell 1 the one before, and
+/// so on. Boards over 16 cells take two digits a tile
+impl<const W: usize, const H: usize> fmt::Display for SlidingPuzzle<W, H> {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x")?;
+        for i in (0..Self::CELLS).rev() {
+            write!(f, "{:0width$x}", self.get_tile(i), width = Self::DIGITS)?;
+        }
+        Ok(())
+    }
+
+}
+
+impl<const W: usize, const H: usize> FromStr for SlidingPuzzle<W, H> {
+
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.trim().trim_start_matches("0x");
+        if digits.len() > Self::CELLS * Self::DIGITS || !digits.is_ascii() {
+            return Err(());
+        }
+        // leading zeros can be left off
+        let digits = format!("{:0>width$}", digits, width = Self::CELLS * Self::DIGITS);
+        let mut tiles = Vec::new();
+        for i in (0..Self::CELLS).rev() {
+            tiles.push(u8::from_str_radix(&digits[i * Self::DIGITS..(i + 1) * Self::DIGITS], 16).map_err(|_| ())?);
+        }
+        Self::from_tiles(&tiles).ok_or(())
+    }
+
+}
+
+impl<const W: usize, const H: usize> State for SlidingPuzzle<W, H> {
+    type Action = SlidingPuzzleAction;
+
+    fn display_pretty(&self) {
+        let width = (Self::CELLS - 1).to_string().len() + 2;
+        let line = format!("+{}", format!("{}+", "-".repeat(width)).repeat(W));
+        println!("{}", line);
+        for y in 0..H {
+            print!("|");
+            for x in 0..W {
+                let tile = self.get_tile(y * W + x);
+                if tile == 0 {
+                    print!("{}|", " ".repeat(width));
+                } else {
+                    print!("{:^width$}|", tile, width = width);
+                }
+            }
+            println!();
+            println!("{}", line);
+        }
+    }
+
+    fn list_actions(&self) -> Vec<Self::Action> {
+        self.blank_neighbours().map(|i| SlidingPuzzleAction::new(self.get_tile(i))).collect()
+    }
+
+    /// Sliding the tile that was just slid would only put it back
+    fn list_actions_after(&self, previous: Option<&Self::Action>) -> Vec<Self::Action> {
+        let mut actions = self.list_actions();
+        actions.retain(|action| Some(action) != previous);
+        actions
+    }
+
+    fn perform_action(&self, action: &Self::Action) -> Self {
+        let Some(index) = self.blank_neighbours().find(|&i| self.get_tile(i) == action.tile) else {
+            return self.clone();
+        };
+        let mut next = self.clone();
+        next.set_tile(self.get_blank_index(), action.tile);
+        next.set_tile(index, 0);
+        next.blank = index as u8;
+        next
+    }
+
+    fn is_goal_state(&self) -> bool {
+        match &self.tiles {
+            Tiles::Packed(board) => *board == Self::GOAL,
+            Tiles::Unpacked(tiles) => tiles.iter().enumerate().all(|(i, &tile)| i == tile as usize),
+        }
+    }
+
+    fn goal_states(&self) -> Vec<Self> {
+        vec![SlidingPuzzle::default()]
+    }
+
+    fn heuristic(&self) -> f64 {
+        self.manhattan_distance() as f64
+    }
+
+    /// Sliding a tile sideways never changes the order of the tiles, and sliding one vertically moves it past W - 1
+    /// others. With an odd width that's an even number, so the inversion parity never changes and has to be even like
+    /// the goal's. With an even width it flips exactly when the blank changes rows, so it has to match the blank's row
+    fn is_solvable(&self) -> bool {
+        let blank_row = (self.get_blank_index() / W) as u8;
+        if W % 2 == 1 {
+            self.inversion_parity() == 0
+        } else {
+            self.inversion_parity() == blank_row % 2
+        }
+    }
+}
+
+/// Slides the tile with this number into the blank, written in decimal like the paths in tests/15_puzzle.txt
+#[derive(PartialEq, Clone)]
+pub struct SlidingPuzzleAction {
+    pub tile: u8,
+}
+
+impl SlidingPuzzleAction {
+
+    pub fn new(tile: u8) -> SlidingPuzzleAction {
+        SlidingPuzzleAction { tile }
+    }
+}
+
+impl fmt::Display for SlidingPuzzleAction {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.tile)
+    }
+
+}
+
+impl FromStr for SlidingPuzzleAction {
+
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u8>().map(SlidingPuzzleAction::new).map_err(|_| ())
+    }
+
+}
+
+impl Action for SlidingPuzzleAction {
+
+    fn get_cost(&self) -> f64 {
+        1.0
+    }
+
+}
+
+impl InvertibleAction for SlidingPuzzleAction {
+
+    /// Sliding the same tile again puts it back where it was
+    fn inverse(&self) -> Self {
+        self.clone()
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, VecDeque};
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::search::{Search, SearchOutcome, AStarGraphSearch};
+
+    use super::*;
+
+    /// Every board reachable from the goal, and how far away it is
+    fn every_board<const W: usize, const H: usize>() -> HashMap<SlidingPuzzle<W, H>, u32> {
+        let goal = SlidingPuzzle::<W, H>::default();
+        let mut distances = HashMap::from([(goal.clone(), 0)]);
+        let mut queue = VecDeque::from([goal]);
+        while let Some(state) = queue.pop_front() {
+            let distance = distances[&state];
+            for action in state.list_actions() {
+                let next_state = state.perform_action(&action);
+                if !distances.contains_key(&next_state) {
+                    distances.insert(next_state.clone(), distance + 1);
+                    queue.push_back(next_state);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Walks every board reachable from the goal, checking the heuristic against the real distance of each one
+    fn check_every_board<const W: usize, const H: usize>() {
+        let distances = every_board::<W, H>();
+        for (state, &distance) in &distances {
+            assert!(state.heuristic() <= distance as f64, "{} for {}, which is {} moves away", state.heuristic(), state, distance);
+            assert!(state.is_solvable(), "{}", state);
+        }
+        // exactly half of the boards can be reached
+        let boards = (1..=W * H).product::<usize>();
+        assert_eq!(distances.len(), boards / 2);
+    }
+
+    #[test]
+    fn every_board_of_small_puzzles() {
+        check_every_board::<2, 2>();
+        check_every_board::<3, 2>();
+        check_every_board::<2, 3>();
+        check_every_board::<3, 3>();
+    }
+
+    #[test]
+    fn random_boards_are_solvable() {
+        let mut rng = StdRng::seed_from_u64(21);
+        for _ in 0..100 {
+            let puzzle = SlidingPuzzle::<4, 4>::random_with(&mut rng);
+            assert!(puzzle.is_solvable(), "{}", puzzle);
+            assert!(puzzle.to_string().parse::<SlidingPuzzle<4, 4>>() == Ok(puzzle.clone()));
+
+            // one swap of two tiles makes any board unsolvable
+            let mut swapped = puzzle.clone();
+            let (first, second) = if puzzle.get_blank_index() < 2 { (2, 3) } else { (0, 1) };
+            swapped.set_tile(first, puzzle.get_tile(second));
+            swapped.set_tile(second, puzzle.get_tile(first));
+            assert!(!swapped.is_solvable(), "{}", swapped);
+        }
+
+        // only 12 boards can be reached on the smallest one, with a single blank in each
+        let reachable = every_board::<2, 2>();
+        for _ in 0..100 {
+            let puzzle = SlidingPuzzle::<2, 2>::random_with(&mut rng);
+            assert_eq!((0..4).filter(|&i| puzzle.get_tile(i) == 0).count(), 1, "{}", puzzle);
+            assert!(reachable.contains_key(&puzzle), "{}", puzzle);
+        }
+    }
+
+    #[test]
+    fn unpacked_boards_solve() {
+        let mut rng = StdRng::seed_from_u64(15);
+        for _ in 0..5 {
+            let (puzzle, path) = SlidingPuzzle::<6, 6>::scrambled_with(&mut rng, 20);
+            let solution = match AStarGraphSearch::<SlidingPuzzle<6, 6>, SlidingPuzzleAction>::new(f64::INFINITY).search(puzzle.clone()) {
+                SearchOutcome::Solved(solution) => solution,
+                _ => panic!("no solution for {}", puzzle),
+            };
+            assert!(solution.path_length() as usize <= path.len());
+            let solved = solution.get_path().iter().fold(puzzle, |puzzle, action| puzzle.perform_action(action));
+            assert!(solved.is_goal_state());
+        }
+
+        // the biggest board there can be
+        let puzzle = SlidingPuzzle::<16, 16>::random_with(&mut rng);
+        assert!(puzzle.is_solvable());
+        assert!(puzzle.to_string().parse::<SlidingPuzzle<16, 16>>() == Ok(puzzle.clone()));
+        assert!(SlidingPuzzle::<16, 16>::default().is_goal_state());
+    }
+
+}
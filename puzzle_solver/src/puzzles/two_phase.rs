@@ -0,0 +1,342 @@
+// Author: Harper Davis
+use std::{collections::VecDeque, time::{Duration, Instant}};
+
+use super::{cubie_cube::CubieCube, rubiks_cube::{CubeAction, CubeState}};
+
+const TWISTS: usize = 2187;
+const FLIPS: usize = 2048;
+const SLICES: usize = 495;
+const CORNER_PERMUTATIONS: usize = 40320;
+const EDGE_PERMUTATIONS: usize = 40320;
+const SLICE_PERMUTATIONS: usize = 24;
+
+/// Indices into CubeAction::ALL of the moves that never leave the phase two group:
+/// any turn of U or D, and half turns of the other four faces
+const PHASE_TWO_MOVES: [usize; 10] = [0, 1, 2, 3, 4, 5, 8, 11, 14, 17];
+/// Phase one never takes more than 12 moves and phase two never more than 18, so the first phase one solution always
+/// has a phase two that fits in this
+const LONGEST_SOLUTION: u32 = 30;
+/// How long solve keeps looking for shorter solutions by default
+const TIME_LIMIT: Duration = Duration::from_millis(100);
+/// How many phase one nodes go by between looks at the clock
+const NODES_PER_CHECK: u64 = 1024;
+
+/// Kociemba's two phase algorithm. Phase one gets the cube into the group generated by
+/// U, D, R2, L2, F2 and B2 (every piece oriented and the middle layer edges in the middle layer),
+/// and phase two solves it from there using only those moves. Both phases are IDA* over small
+/// coordinates instead of the whole cube, with move tables to turn them and pruning tables of exact
+/// distances for pairs of them. Phase one solutions are tried shortest first, and every one after the first only
+/// gets as many phase two moves as would make the whole thing shorter than the best so far, so the solutions keep
+/// getting shorter for as long as it's given
+pub struct TwoPhaseSolver {
+    time_limit: Duration,
+    move_cubes: Vec<CubieCube>,
+    twist_moves: Vec<u16>,
+    flip_moves: Vec<u16>,
+    slice_moves: Vec<u16>,
+    corner_permutation_moves: Vec<u16>,
+    edge_permutation_moves: Vec<u16>,
+    slice_permutation_moves: Vec<u16>,
+    slice_twist_distances: Vec<u8>,
+    slice_flip_distances: Vec<u8>,
+    slice_corner_distances: Vec<u8>,
+    slice_edge_distances: Vec<u8>,
+}
+
+impl TwoPhaseSolver {
+
+    /// Builds every table, which takes a moment, so keep the solver around to solve more than one cube
+    pub fn new() -> TwoPhaseSolver {
+        let move_cubes = CubeAction::ALL.iter().map(CubieCube::from_action).collect::<Vec<CubieCube>>();
+        let phase_two_cubes = PHASE_TWO_MOVES.iter().map(|&m| move_cubes[m]).collect::<Vec<CubieCube>>();
+        let solved_slice = CubieCube::default().slice();
+
+        let twist_moves = move_table(TWISTS, &move_cubes, CubieCube::set_twist, CubieCube::twist);
+        let flip_moves = move_table(FLIPS, &move_cubes, CubieCube::set_flip, CubieCube::flip);
+        let slice_moves = move_table(SLICES, &move_cubes, CubieCube::set_slice, CubieCube::slice);
+        let corner_permutation_moves = move_table(CORNER_PERMUTATIONS, &phase_two_cubes, CubieCube::set_corner_permutation_rank, CubieCube::corner_permutation_rank);
+        let edge_permutation_moves = move_table(EDGE_PERMUTATIONS, &phase_two_cubes, CubieCube::set_edge_permutation_rank, CubieCube::edge_permutation_rank);
+        let slice_permutation_moves = move_table(SLICE_PERMUTATIONS, &phase_two_cubes, CubieCube::set_slice_permutation_rank, CubieCube::slice_permutation_rank);
+
+        let slice_twist_distances = distance_table((&slice_moves, SLICES), (&twist_moves, TWISTS), move_cubes.len(), (solved_slice, 0));
+        let slice_flip_distances = distance_table((&slice_moves, SLICES), (&flip_moves, FLIPS), move_cubes.len(), (solved_slice, 0));
+        let slice_corner_distances = distance_table((&slice_permutation_moves, SLICE_PERMUTATIONS), (&corner_permutation_moves, CORNER_PERMUTATIONS), PHASE_TWO_MOVES.len(), (0, 0));
+        let slice_edge_distances = distance_table((&slice_permutation_moves, SLICE_PERMUTATIONS), (&edge_permutation_moves, EDGE_PERMUTATIONS), PHASE_TWO_MOVES.len(), (0, 0));
+
+        TwoPhaseSolver {
+            time_limit: TIME_LIMIT,
+            move_cubes,
+            twist_moves,
+            flip_moves,
+            slice_moves,
+            corner_permutation_moves,
+            edge_permutation_moves,
+            slice_permutation_moves,
+            slice_twist_distances,
+            slice_flip_distances,
+            slice_corner_distances,
+            slice_edge_distances,
+        }
+    }
+
+    /// How long solve can spend looking for a shorter solution once it has one (100ms unless it's set)
+    pub fn with_time_limit(mut self, time_limit: Duration) -> TwoPhaseSolver {
+        self.time_limit = time_limit;
+        self
+    }
+
+    /// Looks for a solution of at most `max_length` moves, stopping as soon as it finds one or runs out of time,
+    /// and gives back the shortest one it found by then. That can be longer than `max_length` if time ran out
+    /// first, but never more than 30 moves. None only if the cube can't be solved. With the default time limit it
+    /// gets down to 20 moves or so on most cubes, the same as capping it at 20 used to but without the cubes that
+    /// took close to a minute
+    pub fn solve(&self, state: &CubeState, max_length: u32) -> Option<Vec<CubeAction>> {
+        let cubies = CubieCube::from_state(state)?;
+        if !cubies.is_solvable() {
+            return None;
+        }
+
+        let start = PhaseOneNode { twist: cubies.twist(), flip: cubies.flip(), slice: cubies.slice() };
+        let mut progress = Progress {
+            best: None,
+            max_length: LONGEST_SOLUTION.max(max_length),
+            target: max_length,
+            deadline: Instant::now() + self.time_limit,
+            nodes: 0,
+            done: false,
+        };
+        let mut path = Vec::new();
+        let mut depth = 0;
+        while depth <= progress.max_length && !progress.done {
+            self.phase_one(&cubies, start, depth, &mut path, &mut progress);
+            depth += 1;
+        }
+        progress.best.map(|solution| solution.into_iter().map(|m| CubeAction::ALL[m].clone()).collect())
+    }
+
+    fn phase_one_estimate(&self, node: PhaseOneNode) -> u32 {
+        u8::max(
+            self.slice_twist_distances[node.slice * TWISTS + node.twist],
+            self.slice_flip_distances[node.slice * FLIPS + node.flip],
+        ) as u32
+    }
+
+    fn phase_two_estimate(&self, node: PhaseTwoNode) -> u32 {
+        u8::max(
+            self.slice_corner_distances[node.slice_permutation * CORNER_PERMUTATIONS + node.corner_permutation],
+            self.slice_edge_distances[node.slice_permutation * EDGE_PERMUTATIONS + node.edge_permutation],
+        ) as u32
+    }
+
+    /// Looks for phase one solutions of exactly `remaining` more moves, and tries to finish each one with phase two
+    /// in fewer moves than the best solution so far
+    fn phase_one(&self, cubies: &CubieCube, node: PhaseOneNode, remaining: u32, path: &mut Vec<usize>, progress: &mut Progress) {
+        progress.nodes += 1;
+        // there's always a first solution, so the time limit only cuts short the search for a better one
+        if progress.nodes.is_multiple_of(NODES_PER_CHECK) && progress.best.is_some() && Instant::now() >= progress.deadline {
+            progress.done = true;
+        }
+        let estimate = self.phase_one_estimate(node);
+        if progress.done || estimate > remaining || path.len() as u32 + remaining > progress.max_length {
+            return;
+        }
+        if remaining == 0 {
+            // ending on a phase two move means a shorter phase one solution was already tried
+            if path.last().is_some_and(|m| PHASE_TWO_MOVES.contains(m)) {
+                return;
+            }
+            if let Some(solution) = self.start_phase_two(cubies, progress.max_length - path.len() as u32, path) {
+                let length = solution.len() as u32;
+                progress.done = length <= progress.target || Instant::now() >= progress.deadline;
+                progress.max_length = length.saturating_sub(1);
+                progress.best = Some(solution);
+            }
+            return;
+        }
+
+        for m in 0..self.move_cubes.len() {
+            if is_redundant(path.last().copied(), m) {
+                continue;
+            }
+            let next = PhaseOneNode {
+                twist: self.twist_moves[node.twist * CubeAction::ALL.len() + m] as usize,
+                flip: self.flip_moves[node.flip * CubeAction::ALL.len() + m] as usize,
+                slice: self.slice_moves[node.slice * CubeAction::ALL.len() + m] as usize,
+            };
+            path.push(m);
+            self.phase_one(cubies, next, remaining - 1, path, progress);
+            path.pop();
+            if progress.done {
+                return;
+            }
+        }
+    }
+
+    fn start_phase_two(&self, cubies: &CubieCube, max_length: u32, path: &[usize]) -> Option<Vec<usize>> {
+        let mut cube = *cubies;
+        for &m in path {
+            cube = cube.multiply(&self.move_cubes[m]);
+        }
+        let start = PhaseTwoNode {
+            corner_permutation: cube.corner_permutation_rank(),
+            edge_permutation: cube.edge_permutation_rank(),
+            slice_permutation: cube.slice_permutation_rank(),
+        };
+
+        let mut full_path = path.to_vec();
+        for depth in self.phase_two_estimate(start)..=max_length {
+            if self.phase_two(start, depth, &mut full_path) {
+                return Some(full_path);
+            }
+        }
+        None
+    }
+
+    fn phase_two(&self, node: PhaseTwoNode, remaining: u32, path: &mut Vec<usize>) -> bool {
+        let estimate = self.phase_two_estimate(node);
+        // the distances are exact, so 0 only happens at the goal
+        if estimate == 0 {
+            return true;
+        }
+        if estimate > remaining {
+            return false;
+        }
+
+        for (i, &m) in PHASE_TWO_MOVES.iter().enumerate() {
+            if is_redundant(path.last().copied(), m) {
+                continue;
+            }
+            let next = PhaseTwoNode {
+                corner_permutation: self.corner_permutation_moves[node.corner_permutation * PHASE_TWO_MOVES.len() + i] as usize,
+                edge_permutation: self.edge_permutation_moves[node.edge_permutation * PHASE_TWO_MOVES.len() + i] as usize,
+                slice_permutation: self.slice_permutation_moves[node.slice_permutation * PHASE_TWO_MOVES.len() + i] as usize,
+            };
+            path.push(m);
+            if self.phase_two(next, remaining - 1, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+}
+
+impl Default for TwoPhaseSolver {
+
+    fn default() -> Self {
+        TwoPhaseSolver::new()
+    }
+
+}
+
+/// How the search for one cube is going
+struct Progress {
+    best: Option<Vec<usize>>,
+    /// The longest a solution can be and still be better than the best so far
+    max_length: u32,
+    /// Stop as soon as a solution this short turns up
+    target: u32,
+    deadline: Instant,
+    nodes: u64,
+    done: bool,
+}
+
+#[derive(Clone, Copy)]
+struct PhaseOneNode {
+    twist: usize,
+    flip: usize,
+    slice: usize,
+}
+
+#[derive(Clone, Copy)]
+struct PhaseTwoNode {
+    corner_permutation: usize,
+    edge_permutation: usize,
+    slice_permutation: usize,
+}
+
+/// Same as CubeAction::can_follow, on indices into CubeAction::ALL
+fn is_redundant(previous: Option<usize>, m: usize) -> bool {
+    previous.is_some_and(|previous| !CubeAction::ALL[m].can_follow(&CubeAction::ALL[previous]))
+}
+
+/// table[coordinate * moves.len() + m] is the coordinate after doing move m. The coordinate is turned back
+/// into a cube, the move done on that, and the coordinate read off the result
+fn move_table(size: usize, moves: &[CubieCube], set: impl Fn(&mut CubieCube, usize), get: impl Fn(&CubieCube) -> usize) -> Vec<u16> {
+    let mut table = vec![0; size * moves.len()];
+    for coordinate in 0..size {
+        let mut cube = CubieCube::default();
+        set(&mut cube, coordinate);
+        for (m, move_cube) in moves.iter().enumerate() {
+            table[coordinate * moves.len() + m] = get(&cube.multiply(move_cube)) as u16;
+        }
+    }
+    table
+}
+
+/// Exact move counts to the goal for every pair of two coordinates, by breadth first search from the goal
+fn distance_table(first: (&[u16], usize), second: (&[u16], usize), move_count: usize, goal: (usize, usize)) -> Vec<u8> {
+    let (first_moves, first_size) = first;
+    let (second_moves, second_size) = second;
+
+    let mut distances = vec![u8::MAX; first_size * second_size];
+    distances[goal.0 * second_size + goal.1] = 0;
+    let mut queue = VecDeque::from([(goal.0 * second_size + goal.1) as u32]);
+
+    while let Some(index) = queue.pop_front() {
+        let (a, b) = (index as usize / second_size, index as usize % second_size);
+        for m in 0..move_count {
+            let next = first_moves[a * move_count + m] as usize * second_size + second_moves[b * move_count + m] as usize;
+            if distances[next] == u8::MAX {
+                distances[next] = distances[index as usize] + 1;
+                queue.push_back(next as u32);
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::state::State;
+
+    use super::*;
+
+    fn assert_solves(state: &CubeState, solution: &[CubeAction]) {
+        let solved = solution.iter().fold(state.clone(), |state, action| state.perform_action(action));
+        assert!(solved.is_goal_state());
+    }
+
+    /// With no time at all it stops at the first solution, however far that is from what was asked for, and more
+    /// time only makes the solutions shorter
+    #[test]
+    fn time_limit_falls_back_to_the_best_solution() {
+        let quick = TwoPhaseSolver::new().with_time_limit(Duration::ZERO);
+        let patient = TwoPhaseSolver::new().with_time_limit(Duration::from_millis(500));
+        let mut rng = StdRng::seed_from_u64(8);
+        for _ in 0..5 {
+            let (state, _) = CubeState::scrambled_cube_with(&mut rng, 40);
+            let first = quick.solve(&state, 0).unwrap();
+            assert!(first.len() as u32 <= LONGEST_SOLUTION);
+            assert_solves(&state, &first);
+
+            let better = patient.solve(&state, 0).unwrap();
+            assert!(better.len() <= first.len());
+            assert_solves(&state, &better);
+        }
+    }
+
+    #[test]
+    fn stops_at_the_target_length() {
+        let solver = TwoPhaseSolver::new().with_time_limit(Duration::from_secs(60));
+        let (state, scramble) = CubeState::scrambled_cube_with(&mut StdRng::seed_from_u64(8), 6);
+        let solution = solver.solve(&state, scramble.len() as u32).unwrap();
+        assert!(solution.len() <= scramble.len());
+        assert_solves(&state, &solution);
+        assert_eq!(solver.solve(&CubeState::default(), 20), Some(Vec::new()));
+    }
+
+}
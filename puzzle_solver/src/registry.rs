@@ -0,0 +1,223 @@
+// Author: Harper Davis
+use std::fmt;
+
+use crate::{
+    state::State,
+    action::{Action, InvertibleAction},
+    frontier::{Frontier, QueueFrontier, StackFrontier, PriorityQueueFrontier, Evaluation, Estimate, PathCost},
+    heuristic::{Heuristic, PairwiseHeuristic},
+    limits::SearchLimits,
+    search::{Search, SearchOutcome, TreeSearch, DepthLimitedSearch, IterativeDeepeningDepthFirstSearch, GraphSearch, AStarGraphSearch, GreedyBestFirstSearch, UniformCostSearch, IterativeDeepeningAStarSearch},
+    bidirectional_search::{BidirectionalSearch, FrontToFrontSearch},
+    parallel_search::{HashDistributedAStarSearch, ParallelIterativeDeepeningAStarSearch},
+    memory_bounded_search::{RecursiveBestFirstSearch, SimplifiedMemoryBoundedAStarSearch},
+    suboptimal_search::{WeightedAStarSearch, AnytimeRepairingAStarSearch, FocalSearch},
+    local_search::{BeamSearch, HillClimbingSearch, SimulatedAnnealingSearch},
+};
+
+/// Runs a search from the start state, the f64 is the limit that Search::new takes
+pub type SolveFn<S, A> = fn(S, f64, &SearchLimits) -> SearchOutcome<S, A>;
+
+fn solve_with<S: State<Action = A>, A: Action, F: Frontier<S, A>, E: Search<S, A, F>>(start_state: S, limit: f64, limits: &SearchLimits) -> SearchOutcome<S, A> {
+    E::new(limit).search_with_limits(start_state, limits)
+}
+
+/// One of the generic searches with its frontier and heuristic filled in, so it can be picked by name at runtime
+pub struct Solver<S: State<Action = A>, A: Action> {
+    algorithm: &'static str,
+    frontier: &'static str,
+    /// None for the uninformed searches
+    heuristic: Option<&'static str>,
+    solve: SolveFn<S, A>,
+}
+
+impl<S: State<Action = A>, A: Action> Solver<S, A> {
+
+    pub fn get_algorithm(&self) -> &'static str {
+        self.algorithm
+    }
+
+    pub fn get_frontier(&self) -> &'static str {
+        self.frontier
+    }
+
+    pub fn get_heuristic(&self) -> Option<&'static str> {
+        self.heuristic
+    }
+
+    pub fn solve(&self, start_state: S, limit: f64, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        (self.solve)(start_state, limit, limits)
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action> fmt::Display for Solver<S, A> {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.heuristic {
+            Some(heuristic) => write!(f, "{} (frontier {}, heuristic {})", self.algorithm, self.frontier, heuristic),
+            None => write!(f, "{} (frontier {})", self.algorithm, self.frontier),
+        }
+    }
+
+}
+
+///////////////////////////////////////////////////////
+
+/// Every search that can be run on one puzzle, by algorithm, frontier and heuristic name.
+/// The first one registered for an algorithm is what it falls back to when the frontier or heuristic isn't given
+pub struct Registry<S: State<Action = A>, A: Action> {
+    solvers: Vec<Solver<S, A>>,
+}
+
+impl<S: State<Action = A>, A: Action> Default for Registry<S, A> {
+
+    fn default() -> Self {
+        Registry { solvers: Vec::new() }
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action> Registry<S, A> {
+
+    pub fn register<F: Frontier<S, A>, E: Search<S, A, F>>(&mut self, algorithm: &'static str, frontier: &'static str, heuristic: Option<&'static str>) {
+        self.solvers.push(Solver { algorithm, frontier, heuristic, solve: solve_with::<S, A, F, E> });
+    }
+
+    pub fn get_solvers(&self) -> &[Solver<S, A>] {
+        &self.solvers
+    }
+
+    /// The searches that don't take a heuristic
+    pub fn register_uninformed(&mut self) {
+        self.register::<QueueFrontier<S, A>, TreeSearch<S, A, QueueFrontier<S, A>>>("breadth-first", "queue", None);
+        self.register::<StackFrontier<S, A>, TreeSearch<S, A, StackFrontier<S, A>>>("depth-first", "stack", None);
+        self.register::<QueueFrontier<S, A>, TreeSearch<S, A, QueueFrontier<S, A>>>("tree", "queue", None);
+        self.register::<StackFrontier<S, A>, TreeSearch<S, A, StackFrontier<S, A>>>("tree", "stack", None);
+        self.register::<PriorityQueueFrontier<S, A, PathCost>, TreeSearch<S, A, PriorityQueueFrontier<S, A, PathCost>>>("tree", "path-cost", None);
+        self.register::<StackFrontier<S, A>, IterativeDeepeningDepthFirstSearch<S, A>>("iterative-deepening", "stack", None);
+        self.register::<PriorityQueueFrontier<S, A, PathCost>, UniformCostSearch<S, A>>("uniform-cost", "path-cost", None);
+    }
+
+    /// Every search that takes a heuristic, using H. Call it once per heuristic the puzzle has
+    pub fn register_informed<H>(&mut self, heuristic: &'static str)
+    where
+        S: Send + Sync,
+        A: Send + Sync,
+        H: Heuristic<S> + Default + Clone + Send + Sync,
+    {
+        let name = Some(heuristic);
+        self.register::<PriorityQueueFrontier<S, A>, AStarGraphSearch<S, A, H>>("astar", "priority", name);
+        self.register::<PriorityQueueFrontier<S, A, Estimate>, GreedyBestFirstSearch<S, A, H>>("greedy", "estimate", name);
+        self.register::<StackFrontier<S, A>, IterativeDeepeningAStarSearch<S, A, H>>("idastar", "stack", name);
+
+        self.register::<PriorityQueueFrontier<S, A>, GraphSearch<S, A, PriorityQueueFrontier<S, A>, H>>("graph", "priority", name);
+        self.register::<PriorityQueueFrontier<S, A, Estimate>, GraphSearch<S, A, PriorityQueueFrontier<S, A, Estimate>, H>>("graph", "estimate", name);
+        self.register::<PriorityQueueFrontier<S, A, PathCost>, GraphSearch<S, A, PriorityQueueFrontier<S, A, PathCost>, H>>("graph", "path-cost", name);
+        self.register::<QueueFrontier<S, A>, GraphSearch<S, A, QueueFrontier<S, A>, H>>("graph", "queue", name);
+        self.register::<StackFrontier<S, A>, GraphSearch<S, A, StackFrontier<S, A>, H>>("graph", "stack", name);
+
+        self.register::<StackFrontier<S, A>, DepthLimitedSearch<S, A, StackFrontier<S, A>, H>>("depth-limited", "stack", name);
+        self.register::<QueueFrontier<S, A>, DepthLimitedSearch<S, A, QueueFrontier<S, A>, H>>("depth-limited", "queue", name);
+        self.register::<PriorityQueueFrontier<S, A>, DepthLimitedSearch<S, A, PriorityQueueFrontier<S, A>, H>>("depth-limited", "priority", name);
+
+        self.register::<PriorityQueueFrontier<S, A>, HashDistributedAStarSearch<S, A, H>>("hdastar", "priority", name);
+        self.register::<StackFrontier<S, A>, ParallelIterativeDeepeningAStarSearch<S, A, H>>("parallel-idastar", "stack", name);
+
+        self.register::<StackFrontier<S, A>, RecursiveBestFirstSearch<S, A, H>>("rbfs", "stack", name);
+        self.register::<PriorityQueueFrontier<S, A>, SimplifiedMemoryBoundedAStarSearch<S, A, H>>("smastar", "priority", name);
+
+        self.register::<PriorityQueueFrontier<S, A>, WeightedAStarSearch<S, A, H>>("weighted-astar", "priority", name);
+        self.register::<PriorityQueueFrontier<S, A>, AnytimeRepairingAStarSearch<S, A, H>>("arastar", "priority", name);
+        self.register::<PriorityQueueFrontier<S, A>, FocalSearch<S, A, H, H>>("focal", "priority", name);
+
+        self.register::<QueueFrontier<S, A>, BeamSearch<S, A, Evaluation, H>>("beam", "queue", name);
+        self.register::<StackFrontier<S, A>, HillClimbingSearch<S, A, H>>("hill-climbing", "stack", name);
+        self.register::<StackFrontier<S, A>, SimulatedAnnealingSearch<S, A, H>>("annealing", "stack", name);
+    }
+
+    /// Looks up a search, leaving out the frontier or heuristic picks the first one registered
+    pub fn find(&self, algorithm: &str, frontier: Option<&str>, heuristic: Option<&str>) -> Result<&Solver<S, A>, String> {
+        let candidates = self.solvers.iter().filter(|solver| solver.algorithm == algorithm).collect::<Vec<&Solver<S, A>>>();
+        if candidates.is_empty() {
+            return Err(format!("unknown algorithm {}, expected one of {}", algorithm, join_unique(self.solvers.iter().map(|solver| solver.algorithm))));
+        }
+
+        let candidates = match frontier {
+            Some(frontier) => {
+                let matching = candidates.iter().copied().filter(|solver| solver.frontier == frontier).collect::<Vec<&Solver<S, A>>>();
+                if matching.is_empty() {
+                    return Err(format!("{} can't use the {} frontier, expected one of {}", algorithm, frontier, join_unique(candidates.iter().map(|solver| solver.frontier))));
+                }
+                matching
+            }
+            None => candidates,
+        };
+
+        match heuristic {
+            Some(heuristic) => candidates.iter().copied().find(|solver| solver.heuristic == Some(heuristic)).ok_or_else(|| {
+                match join_unique(candidates.iter().filter_map(|solver| solver.heuristic)) {
+                    expected if expected.is_empty() => format!("{} doesn't take a heuristic", algorithm),
+                    expected => format!("{} can't use the {} heuristic, expected one of {}", algorithm, heuristic, expected),
+                }
+            }),
+            None => Ok(candidates[0]),
+        }
+    }
+
+    /// Every search for the given algorithms that uses one of the frontiers and one of the heuristics, for running
+    /// a whole matrix of them. No frontiers or heuristics means any of them, and the uninformed searches are kept
+    /// whatever the heuristics are
+    pub fn find_all(&self, algorithms: &[&str], frontiers: &[&str], heuristics: &[&str]) -> Result<Vec<&Solver<S, A>>, String> {
+        for algorithm in algorithms {
+            if !self.solvers.iter().any(|solver| solver.algorithm == *algorithm) {
+                return Err(format!("unknown algorithm {}, expected one of {}", algorithm, join_unique(self.solvers.iter().map(|solver| solver.algorithm))));
+            }
+        }
+        for frontier in frontiers {
+            if !self.solvers.iter().any(|solver| solver.frontier == *frontier) {
+                return Err(format!("unknown frontier {}, expected one of {}", frontier, join_unique(self.solvers.iter().map(|solver| solver.frontier))));
+            }
+        }
+        for heuristic in heuristics {
+            if !self.solvers.iter().any(|solver| solver.heuristic == Some(heuristic)) {
+                return Err(format!("unknown heuristic {}, expected one of {}", heuristic, join_unique(self.solvers.iter().filter_map(|solver| solver.heuristic))));
+            }
+        }
+
+        let solvers = algorithms.iter().flat_map(|algorithm| self.solvers.iter().filter(move |solver| solver.algorithm == *algorithm))
+            .filter(|solver| frontiers.is_empty() || frontiers.contains(&solver.frontier))
+            .filter(|solver| heuristics.is_empty() || solver.heuristic.is_none_or(|heuristic| heuristics.contains(&heuristic)))
+            .collect::<Vec<&Solver<S, A>>>();
+        if solvers.is_empty() {
+            return Err(format!("none of {} can use those frontiers and heuristics", algorithms.join(", ")));
+        }
+        Ok(solvers)
+    }
+
+}
+
+impl<S: State<Action = A>, A: InvertibleAction> Registry<S, A> {
+
+    /// The searches that also work backwards from the goal. H is what front to front search estimates
+    /// the distance between the two frontiers with
+    pub fn register_bidirectional<H: PairwiseHeuristic<S> + Default>(&mut self, heuristic: &'static str) {
+        if !self.solvers.iter().any(|solver| solver.algorithm == "bidirectional") {
+            self.register::<QueueFrontier<S, A>, BidirectionalSearch<S, A, QueueFrontier<S, A>>>("bidirectional", "queue", None);
+            self.register::<PriorityQueueFrontier<S, A>, BidirectionalSearch<S, A, PriorityQueueFrontier<S, A>>>("bidirectional", "priority", None);
+        }
+        self.register::<PriorityQueueFrontier<S, A>, FrontToFrontSearch<S, A, H>>("front-to-front", "priority", Some(heuristic));
+    }
+
+}
+
+/// The names in order, without repeats
+fn join_unique<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    let mut unique: Vec<&str> = Vec::new();
+    for name in names {
+        if !unique.contains(&name) {
+            unique.push(name);
+        }
+    }
+    unique.join(", ")
+}
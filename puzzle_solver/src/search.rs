@@ -1,22 +1,35 @@
 // Author: Harper Davis
 
-use std::{marker::PhantomData, rc::{Rc}};
+use std::{marker::PhantomData, sync::Arc, fmt, collections::HashMap, io::{self, Write}, thread, time::Duration};
 
-use crate::{state::State, action::Action, frontier::{QueueFrontier, Frontier, StackFrontier, PriorityQueueFrontier}, node::Node};
+use crate::{state::State, action::Action, frontier::{QueueFrontier, Frontier, StackFrontier, PriorityQueueFrontier, Estimate, PathCost}, node::Node, statistics::SearchStatistics, limits::{SearchLimits, Limit}, heuristic::{Heuristic, StateHeuristic, ZeroHeuristic}};
 
 #[derive(Debug)]
 pub struct Solution<S: State<Action = A>, A: Action> {
     start_state: S,
     final_state: S,
     path: Vec<A>,
+    statistics: SearchStatistics,
 }
 
 ///////////////////////////////////////////////////////
 
 impl<S: State<Action = A>, A: Action> Solution<S, A> {
 
-    pub fn new(start_state: S, final_state: S, path: Vec<A>) -> Solution<S, A> {
-        Solution { start_state, final_state, path }
+    pub fn new(start_state: S, final_state: S, path: Vec<A>, statistics: SearchStatistics) -> Solution<S, A> {
+        Solution { start_state, final_state, path, statistics }
+    }
+
+    /// Walks back up the parent chain of a goal node to rebuild the path that reached it
+    pub fn from_node(start_state: S, node: &Node<S, A>, statistics: SearchStatistics) -> Solution<S, A> {
+        let mut path = Vec::new();
+        let mut current_node = node;
+        while let Some(parent_node) = current_node.get_parent_node() {
+            path.push(current_node.get_action().unwrap().to_owned());
+            current_node = parent_node;
+        }
+        path.reverse();
+        Solution::new(start_state, node.get_state().clone(), path, statistics)
     }
 
     pub fn display(&self) {
@@ -24,6 +37,7 @@ impl<S: State<Action = A>, A: Action> Solution<S, A> {
         self.display_final();
         println!("Path ({}): ", self.path_length());
         self.display_path();
+        self.display_statistics();
     }
 
     pub fn display_final(&self) {
@@ -40,103 +54,255 @@ impl<S: State<Action = A>, A: Action> Solution<S, A> {
         println!()
     }
 
+    pub fn display_statistics(&self) {
+        println!("Statistics: {}, effective branching factor {:.3}", self.statistics, self.effective_branching_factor());
+    }
+
     pub fn path_length(&self) -> u32 {
         self.path.len() as u32
     }
 
+    pub fn get_final_state(&self) -> &S {
+        &self.final_state
+    }
+
     pub fn get_path(&self) -> &Vec<A> {
         &self.path
     }
 
+    pub fn get_statistics(&self) -> &SearchStatistics {
+        &self.statistics
+    }
+
+    pub fn effective_branching_factor(&self) -> f64 {
+        self.statistics.effective_branching_factor(self.path_length())
+    }
+
+    pub fn get_start_state(&self) -> &S {
+        &self.start_state
+    }
+
+    pub fn path_cost(&self) -> f64 {
+        // folded rather than summed since an empty sum of floats is -0
+        self.path.iter().fold(0.0, |cost, action| cost + action.get_cost())
+    }
+
+    /// Plays the path again from the start state, checking every action is one the state allows and that it really
+    /// ends at the goal the search said it did. Gives the cost of the path if it does
+    pub fn verify(&self) -> Result<f64, String> {
+        let mut state = self.start_state.clone();
+        for (i, action) in self.path.iter().enumerate() {
+            if !state.list_actions().contains(action) {
+                return Err(format!("move {} of the path, {}, can't be done from {}", i + 1, action.to_string(), state.to_string()));
+            }
+            state = state.perform_action(action);
+        }
+        if state != self.final_state {
+            return Err(format!("the path ends at {} rather than {}", state.to_string(), self.final_state.to_string()));
+        }
+        if !state.is_goal_state() {
+            return Err(format!("the path ends at {}, which isn't a goal", state.to_string()));
+        }
+        Ok(self.path_cost())
+    }
+
+    /// Shows every state along the path one after the other, waiting between each of them.
+    /// With a delay the screen is cleared first so it plays like an animation
+    pub fn replay(&self, delay: Duration) {
+        let mut state = self.start_state.clone();
+        self.display_step(&state, 0, None, delay);
+        for (i, action) in self.path.iter().enumerate() {
+            state = state.perform_action(action);
+            self.display_step(&state, i + 1, Some(action), delay);
+        }
+    }
+
+    fn display_step(&self, state: &S, step: usize, action: Option<&A>, delay: Duration) {
+        if !delay.is_zero() {
+            thread::sleep(delay);
+            print!("\x1b[2J\x1b[H");
+        }
+        match action {
+            Some(action) => println!("Step {}/{}: {}", step, self.path.len(), action.to_string()),
+            None => println!("Step {}/{}: start", step, self.path.len()),
+        }
+        state.display_pretty();
+        // without a flush the frames come out in bursts
+        io::stdout().flush().unwrap();
+    }
+
+}
+
+/// How a search ended: with a solution, with proof that there is none, or cut off by one of its limits.
+/// The searches in local_search.rs aren't complete, so for them Exhausted only means they gave up
+#[derive(Debug)]
+pub enum SearchOutcome<S: State<Action = A>, A: Action> {
+    Solved(Solution<S, A>),
+    Exhausted(SearchStatistics),
+    LimitReached(Limit, SearchStatistics),
+}
+
+impl<S: State<Action = A>, A: Action> SearchOutcome<S, A> {
+
+    pub fn is_solved(&self) -> bool {
+        matches!(self, SearchOutcome::Solved(_))
+    }
+
+    pub fn solution(self) -> Option<Solution<S, A>> {
+        match self {
+            SearchOutcome::Solved(solution) => Some(solution),
+            _ => None,
+        }
+    }
+
+    pub fn get_statistics(&self) -> &SearchStatistics {
+        match self {
+            SearchOutcome::Solved(solution) => solution.get_statistics(),
+            SearchOutcome::Exhausted(statistics) => statistics,
+            SearchOutcome::LimitReached(_, statistics) => statistics,
+        }
+    }
+
 }
 
 pub trait Search<S: State<Action = A>, A: Action, F: Frontier<S, A>>: ToString {
 
     fn new(limit: f64) -> Self;
 
-    fn search(&mut self, start_state: S) -> Option<Solution<S, A>> {
+    fn search(&mut self, start_state: S) -> SearchOutcome<S, A> {
+        self.search_with_limits(start_state, &SearchLimits::none())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        *self.statistics() = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics().clone());
+        }
+        self.statistics().iteration();
+
         let mut frontier = F::new();
-        frontier.insert(Node::new(&start_state, 0.0, 0));
+        frontier.insert(Node::new(&start_state, 0.0, 0, self.heuristic()));
+        self.statistics().frontier_size(frontier.size());
 
         while !frontier.is_empty() {
+            if let Some(limit) = limits.check(self.statistics(), frontier.size()) {
+                return SearchOutcome::LimitReached(limit, self.statistics().clone());
+            }
+
             let node = frontier.pop().unwrap();
 
             if node.get_state().is_goal_state() {
-                let mut path = Vec::new();
-                let mut current_node = &node;
-                while current_node.get_parent_node().is_some() {
-                    path.insert(0, current_node.get_action().unwrap().to_owned());
-                    current_node = current_node.get_parent_node().unwrap();
-                }
-                return Some(Solution::new(start_state.clone(), node.get_state().clone(), path));
+                return SearchOutcome::Solved(Solution::from_node(start_state, &node, self.statistics().clone()));
             }
 
-            let rc_node = Rc::new(node);
-            for action in rc_node.get_possible_actions() {
-                let parent_node = rc_node.get_parent_node();
-                let next_node = Node::<S, A>::next_node(rc_node.clone(), &action);
+            self.statistics().expanded();
+            let shared_node = Arc::new(node);
+            for action in shared_node.get_possible_actions() {
+                let parent_node = shared_node.get_parent_node();
+                let next_node = Node::<S, A>::next_node(shared_node.clone(), &action, self.heuristic());
+                self.statistics().generated();
 
                 if (parent_node.is_some() && parent_node.unwrap().get_state() == next_node.get_state()) || self.prune(&next_node) {
+                    self.statistics().pruned();
                     continue;
                 }
 
                 frontier.insert(next_node);
             }
+            self.statistics().frontier_size(frontier.size());
         }
 
-        return None;
+        SearchOutcome::Exhausted(self.statistics().clone())
     }
 
     fn prune(&mut self, node: &Node<S, A>) -> bool;
+
+    /// The estimate used to order and prune nodes, searches that don't take a heuristic are uninformed
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &ZeroHeuristic
+    }
+
+    /// The counters for the most recent call to search
+    fn statistics(&mut self) -> &mut SearchStatistics;
 }
 
 pub struct TreeSearch<S: State<Action = A>, A: Action, F: Frontier<S, A>> {
+    statistics: SearchStatistics,
     phantom_s: PhantomData<S>,
     phantom_a: PhantomData<A>,
     phantom_f: PhantomData<F>,
 }
 
-impl <S: State<Action = A>, A: Action, F: Frontier<S, A>> ToString for TreeSearch<S, A, F> {
-    fn to_string(&self) -> String {
-        "TreeSearch".to_string()
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> fmt::Display for TreeSearch<S, A, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TreeSearch")
     }
 }
 
 impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> Search<S, A, F> for TreeSearch<S, A, F> {
 
     fn new(_limit: f64) -> TreeSearch<S, A, F> {
-        TreeSearch { phantom_a: PhantomData, phantom_s: PhantomData, phantom_f: PhantomData }
+        TreeSearch { statistics: SearchStatistics::new(), phantom_a: PhantomData, phantom_s: PhantomData, phantom_f: PhantomData }
     }
 
     fn prune(&mut self, _node: &Node<S, A>) -> bool {
         false
     }
 
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
 }
+
 ///////////////////////////////////////////////////////
-/// 
 
-pub struct DepthLimitedSearch<S: State<Action = A>, A: Action, F: Frontier<S, A>> {
+pub struct DepthLimitedSearch<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S> = StateHeuristic> {
     depth_limit: u32,
+    cutoff: bool,
+    heuristic: H,
+    statistics: SearchStatistics,
     phantom_s: PhantomData<S>,
     phantom_a: PhantomData<A>,
     phantom_f: PhantomData<F>,
 }
 
-impl <S: State<Action = A>, A: Action, F: Frontier<S, A>> ToString for DepthLimitedSearch<S, A, F> {
-    fn to_string(&self) -> String {
-        "DepthLimitedSearch".to_string()
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S>> DepthLimitedSearch<S, A, F, H> {
+
+    pub fn with_heuristic(limit: f64, heuristic: H) -> DepthLimitedSearch<S, A, F, H> {
+        DepthLimitedSearch { depth_limit: limit as u32, cutoff: false, heuristic, statistics: SearchStatistics::new(), phantom_a: PhantomData, phantom_s: PhantomData, phantom_f: PhantomData }
+    }
+
+    /// Whether any node was pruned for being too deep, if not then an unsuccessful search covered the whole space
+    fn was_cut_off(&self) -> bool {
+        self.cutoff
+    }
+}
+
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S>> fmt::Display for DepthLimitedSearch<S, A, F, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DepthLimitedSearch")
     }
 }
 
-impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> Search<S, A, F> for DepthLimitedSearch<S, A, F> {
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S> + Default + Clone> Search<S, A, F> for DepthLimitedSearch<S, A, F, H> {
 
-    fn new(limit: f64) -> DepthLimitedSearch<S, A, F> {
-        DepthLimitedSearch { depth_limit: limit as u32, phantom_a: PhantomData, phantom_s: PhantomData, phantom_f: PhantomData }
+    fn new(limit: f64) -> DepthLimitedSearch<S, A, F, H> {
+        DepthLimitedSearch::with_heuristic(limit, H::default())
     }
 
     fn prune(&mut self, node: &Node<S, A>) -> bool {
-        node.get_depth() > self.depth_limit
+        let pruned = node.get_depth() > self.depth_limit;
+        self.cutoff |= pruned;
+        pruned
+    }
+
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &self.heuristic
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
     }
 
 }
@@ -147,30 +313,41 @@ pub type DepthFirstSearch<S, A> = TreeSearch<S, A, StackFrontier<S, A>>;
 ///////////////////////////////////////////////////////
 
 pub struct IterativeDeepeningSearch<S: State<Action = A>, A: Action, F: Frontier<S, A>> {
+    statistics: SearchStatistics,
     phantom_s: PhantomData<S>,
     phantom_a: PhantomData<A>,
     phantom_f: PhantomData<F>,
 }
 
-impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> ToString for IterativeDeepeningSearch<S, A, F> {
-    fn to_string(&self) -> String {
-        "IterativeDeepeningSearch".to_string()
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> fmt::Display for IterativeDeepeningSearch<S, A, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IterativeDeepeningSearch")
     }
 }
 
 impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> Search<S, A, F> for IterativeDeepeningSearch<S, A, F> {
 
     fn new(_limit: f64) -> IterativeDeepeningSearch<S, A, F> {
-        IterativeDeepeningSearch { phantom_a: PhantomData, phantom_s: PhantomData, phantom_f: PhantomData }
+        IterativeDeepeningSearch { statistics: SearchStatistics::new(), phantom_a: PhantomData, phantom_s: PhantomData, phantom_f: PhantomData }
     }
 
-    fn search(&mut self, start_state: S) -> Option<Solution<S, A>> {
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
         let mut depth = 1.0;
         loop {
-            let mut search = DepthLimitedSearch::<S, A, F>::new(depth);
-            let solution = search.search(start_state.clone());
-            if solution.is_some() {
-                return solution;
+            let mut search = DepthLimitedSearch::<S, A, F, ZeroHeuristic>::new(depth);
+            let outcome = search.search_with_limits(start_state.clone(), &limits.remaining(&self.statistics));
+            self.statistics.merge(search.statistics());
+            match outcome {
+                SearchOutcome::Solved(solution) => {
+                    return SearchOutcome::Solved(Solution::new(solution.start_state, solution.final_state, solution.path, self.statistics.clone()));
+                }
+                SearchOutcome::LimitReached(limit, _) => return SearchOutcome::LimitReached(limit, self.statistics.clone()),
+                SearchOutcome::Exhausted(_) if !search.was_cut_off() => return SearchOutcome::Exhausted(self.statistics.clone()),
+                SearchOutcome::Exhausted(_) => {}
             }
             depth += 1.0;
         }
@@ -180,38 +357,48 @@ impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> Search<S, A, F> for Ite
         unreachable!()
     }
 
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
 }
 
 pub type IterativeDeepeningDepthFirstSearch<S, A> = IterativeDeepeningSearch<S, A, StackFrontier<S, A>>;
-pub type AStarSearch<S, A> = DepthLimitedSearch<S, A, PriorityQueueFrontier<S, A>>;
+pub type AStarSearch<S, A, H = StateHeuristic> = DepthLimitedSearch<S, A, PriorityQueueFrontier<S, A>, H>;
 
-pub struct EvaluationLimitedSearch<S: State<Action = A>, A: Action, F: Frontier<S, A>> {
+pub struct EvaluationLimitedSearch<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S> = StateHeuristic> {
     evaluation_limit: f64,
     lowest_evaluation_above_limit: f64,
+    heuristic: H,
+    statistics: SearchStatistics,
     phantom_s: PhantomData<S>,
     phantom_a: PhantomData<A>,
     phantom_f: PhantomData<F>,
 }
 
-impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> EvaluationLimitedSearch<S, A, F> {
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S>> EvaluationLimitedSearch<S, A, F, H> {
+
+    pub fn with_heuristic(limit: f64, heuristic: H) -> EvaluationLimitedSearch<S, A, F, H> {
+        EvaluationLimitedSearch { evaluation_limit: limit, lowest_evaluation_above_limit: f64::INFINITY, heuristic, statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData, phantom_f: PhantomData }
+    }
 
-    fn get_lowest_evaluation_above_limit(&self) -> f64 {
+    pub fn get_lowest_evaluation_above_limit(&self) -> f64 {
         self.lowest_evaluation_above_limit
     }
 }
 
-impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> ToString for EvaluationLimitedSearch<S, A, F> {
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S>> fmt::Display for EvaluationLimitedSearch<S, A, F, H> {
     
-    fn to_string(&self) -> String {
-        "HeuristicLimitedSearch".to_string()
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HeuristicLimitedSearch")
     }
 
 }
 
-impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> Search<S, A, F> for EvaluationLimitedSearch<S, A, F> {
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S> + Default + Clone> Search<S, A, F> for EvaluationLimitedSearch<S, A, F, H> {
 
-    fn new(limit: f64) -> EvaluationLimitedSearch<S, A, F> {
-        EvaluationLimitedSearch { evaluation_limit: limit, lowest_evaluation_above_limit: f64::INFINITY, phantom_s: PhantomData, phantom_a: PhantomData, phantom_f: PhantomData }
+    fn new(limit: f64) -> EvaluationLimitedSearch<S, A, F, H> {
+        EvaluationLimitedSearch::with_heuristic(limit, H::default())
     }
 
     fn prune(&mut self, node: &Node<S, A>) -> bool {
@@ -224,37 +411,67 @@ impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> Search<S, A, F> for Eva
         }
     }
 
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &self.heuristic
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
 }
 
-pub struct IterativeDeepeningEvaluationSearch<S: State<Action = A>, A: Action, F: Frontier<S, A>> {
+pub struct IterativeDeepeningEvaluationSearch<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S> = StateHeuristic> {
+    heuristic: H,
+    statistics: SearchStatistics,
     phantom_s: PhantomData<S>,
     phantom_a: PhantomData<A>,
     phantom_f: PhantomData<F>,
 }
 
-impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> ToString for IterativeDeepeningEvaluationSearch<S, A, F> {
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S>> IterativeDeepeningEvaluationSearch<S, A, F, H> {
+
+    pub fn with_heuristic(heuristic: H) -> IterativeDeepeningEvaluationSearch<S, A, F, H> {
+        IterativeDeepeningEvaluationSearch { heuristic, statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData, phantom_f: PhantomData }
+    }
+}
+
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S>> fmt::Display for IterativeDeepeningEvaluationSearch<S, A, F, H> {
     
-    fn to_string(&self) -> String {
-        "IterativeDeepeningEvaluationSearch".to_string()
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IterativeDeepeningEvaluationSearch")
     }
 
 }
 
-impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> Search<S, A, F> for IterativeDeepeningEvaluationSearch<S, A, F> {
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S> + Default + Clone> Search<S, A, F> for IterativeDeepeningEvaluationSearch<S, A, F, H> {
 
-    fn new(_limit: f64) -> IterativeDeepeningEvaluationSearch<S, A, F> {
-        IterativeDeepeningEvaluationSearch { phantom_s: PhantomData, phantom_a: PhantomData, phantom_f: PhantomData }
+    fn new(_limit: f64) -> IterativeDeepeningEvaluationSearch<S, A, F, H> {
+        IterativeDeepeningEvaluationSearch::with_heuristic(H::default())
     }
 
-    fn search(&mut self, start_state: S) -> Option<Solution<S, A>> {
-        let mut evaluation_limit = start_state.heuristic();
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+        let mut evaluation_limit = self.heuristic.estimate(&start_state);
         loop {
-            let mut search = EvaluationLimitedSearch::<S, A, F>::new(evaluation_limit);
-            let solution = search.search(start_state.clone());
-            if solution.is_some() {
-                return solution;
-            } else {
-                evaluation_limit = search.get_lowest_evaluation_above_limit();
+            let mut search = EvaluationLimitedSearch::<S, A, F, H>::with_heuristic(evaluation_limit, self.heuristic.clone());
+            let outcome = search.search_with_limits(start_state.clone(), &limits.remaining(&self.statistics));
+            self.statistics.merge(search.statistics());
+            match outcome {
+                SearchOutcome::Solved(solution) => {
+                    return SearchOutcome::Solved(Solution::new(solution.start_state, solution.final_state, solution.path, self.statistics.clone()));
+                }
+                SearchOutcome::LimitReached(limit, _) => return SearchOutcome::LimitReached(limit, self.statistics.clone()),
+                SearchOutcome::Exhausted(_) => {
+                    // nothing was pruned by the evaluation limit, so raising it would not find anything new
+                    if search.get_lowest_evaluation_above_limit() == f64::INFINITY {
+                        return SearchOutcome::Exhausted(self.statistics.clone());
+                    }
+                    evaluation_limit = search.get_lowest_evaluation_above_limit();
+                }
             }
         }
     }
@@ -263,6 +480,108 @@ impl<S: State<Action = A>, A: Action, F: Frontier<S, A>> Search<S, A, F> for Ite
         unreachable!()
     }
 
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+pub type IterativeDeepeningAStarSearch<S, A, H = StateHeuristic> = IterativeDeepeningEvaluationSearch<S, A, StackFrontier<S, A>, H>;
+
+///////////////////////////////////////////////////////
+
+/// Like TreeSearch, but remembers the cheapest known path cost to every state it has generated,
+/// so a state reached again by a path that is no cheaper is never put back on the frontier
+pub struct GraphSearch<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S> = StateHeuristic> {
+    heuristic: H,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+    phantom_f: PhantomData<F>,
+}
+
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S>> GraphSearch<S, A, F, H> {
+
+    pub fn with_heuristic(heuristic: H) -> GraphSearch<S, A, F, H> {
+        GraphSearch { heuristic, statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData, phantom_f: PhantomData }
+    }
+}
+
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S>> fmt::Display for GraphSearch<S, A, F, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GraphSearch")
+    }
+}
+
+impl<S: State<Action = A>, A: Action, F: Frontier<S, A>, H: Heuristic<S> + Default + Clone> Search<S, A, F> for GraphSearch<S, A, F, H> {
+
+    fn new(_limit: f64) -> GraphSearch<S, A, F, H> {
+        GraphSearch::with_heuristic(H::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+        self.statistics.iteration();
+
+        let mut frontier = F::new();
+        let mut best_costs: HashMap<S, f64> = HashMap::new();
+        best_costs.insert(start_state.clone(), 0.0);
+        frontier.insert(Node::new(&start_state, 0.0, 0, &self.heuristic));
+        self.statistics.frontier_size(frontier.size());
+
+        while let Some(node) = frontier.pop() {
+            if let Some(limit) = limits.check(&self.statistics, frontier.size()) {
+                return SearchOutcome::LimitReached(limit, self.statistics.clone());
+            }
+
+            // a cheaper path to this state was found after this node was queued, so it is stale
+            if best_costs.get(node.get_state()).is_some_and(|&cost| cost < node.get_path_cost()) {
+                continue;
+            }
+
+            if node.get_state().is_goal_state() {
+                return SearchOutcome::Solved(Solution::from_node(start_state, &node, self.statistics.clone()));
+            }
+
+            self.statistics.expanded();
+            let shared_node = Arc::new(node);
+            for action in shared_node.get_possible_actions() {
+                let next_node = Node::<S, A>::next_node(shared_node.clone(), &action, &self.heuristic);
+                self.statistics.generated();
+
+                let known_cost = best_costs.get(next_node.get_state());
+                if known_cost.is_some_and(|&cost| cost <= next_node.get_path_cost()) || self.prune(&next_node) {
+                    self.statistics.pruned();
+                    continue;
+                }
+
+                best_costs.insert(next_node.get_state().clone(), next_node.get_path_cost());
+                frontier.insert(next_node);
+            }
+            self.statistics.frontier_size(frontier.size());
+        }
+
+        SearchOutcome::Exhausted(self.statistics.clone())
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        false
+    }
+
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &self.heuristic
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
 }
 
-pub type IterativeDeepeningAStarSearch<S, A> = IterativeDeepeningEvaluationSearch<S, A, StackFrontier<S, A>>;
\ No newline at end of file
+pub type BreadthFirstGraphSearch<S, A> = GraphSearch<S, A, QueueFrontier<S, A>>;
+pub type AStarGraphSearch<S, A, H = StateHeuristic> = GraphSearch<S, A, PriorityQueueFrontier<S, A>, H>;
+pub type GreedyBestFirstSearch<S, A, H = StateHeuristic> = GraphSearch<S, A, PriorityQueueFrontier<S, A, Estimate>, H>;
+pub type UniformCostSearch<S, A> = GraphSearch<S, A, PriorityQueueFrontier<S, A, PathCost>, ZeroHeuristic>;
@@ -1,12 +1,32 @@
 // Author: Harper Davis
+use std::{hash::Hash, str::FromStr};
 
-pub trait State: Clone + ToString + PartialEq {
+/// States are written and read back in the same way by Display and FromStr, which is what test suites and the command line use
+pub trait State: Clone + ToString + Eq + Hash + FromStr {
     type Action;
 
     fn display_pretty(&self);
     fn list_actions(&self) -> Vec<Self::Action>;
+
+    /// The actions worth trying after arriving here with `previous`. Puzzles can leave out ones that
+    /// only lead somewhere another ordering of the same moves already gets to, which is what the searches use
+    fn list_actions_after(&self, _previous: Option<&Self::Action>) -> Vec<Self::Action> {
+        self.list_actions()
+    }
     fn perform_action(&self, action: &Self::Action) -> Self;
     fn is_goal_state(&self) -> bool;
 
+    /// Every state that counts as solved, for searches that work backwards from the goal.
+    /// Puzzles that can't list them leave this empty
+    fn goal_states(&self) -> Vec<Self> {
+        Vec::new()
+    }
+
     fn heuristic(&self) -> f64;
+
+    /// Whether the goal can be reached from this state at all, searches check this before starting
+    /// so that they don't spend forever proving that there isn't a solution
+    fn is_solvable(&self) -> bool {
+        true
+    }
 }
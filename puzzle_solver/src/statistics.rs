@@ -0,0 +1,105 @@
+// Author: Harper Davis
+use std::fmt;
+
+/// Counters collected while a search runs, so algorithms can be compared by more than time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchStatistics {
+    nodes_generated: u64,
+    nodes_expanded: u64,
+    max_frontier_size: usize,
+    pruned: u64,
+    iterations: u32,
+}
+
+impl SearchStatistics {
+
+    pub fn new() -> SearchStatistics {
+        SearchStatistics::default()
+    }
+
+    pub fn generated(&mut self) {
+        self.nodes_generated += 1;
+    }
+
+    pub fn expanded(&mut self) {
+        self.nodes_expanded += 1;
+    }
+
+    pub fn pruned(&mut self) {
+        self.pruned += 1;
+    }
+
+    pub fn iteration(&mut self) {
+        self.iterations += 1;
+    }
+
+    pub fn frontier_size(&mut self, size: usize) {
+        self.max_frontier_size = usize::max(self.max_frontier_size, size);
+    }
+
+    /// Adds the counts of another run (for example one iteration of an iterative deepening search) to these ones
+    pub fn merge(&mut self, other: &SearchStatistics) {
+        self.merge_counts(other);
+        self.iterations += other.iterations;
+    }
+
+    /// Same as merge but leaves the iterations alone, for runs that are part of the same iteration (like subtrees searched on different threads)
+    pub fn merge_counts(&mut self, other: &SearchStatistics) {
+        self.nodes_generated += other.nodes_generated;
+        self.nodes_expanded += other.nodes_expanded;
+        self.max_frontier_size = usize::max(self.max_frontier_size, other.max_frontier_size);
+        self.pruned += other.pruned;
+    }
+
+    pub fn get_nodes_generated(&self) -> u64 {
+        self.nodes_generated
+    }
+
+    pub fn get_nodes_expanded(&self) -> u64 {
+        self.nodes_expanded
+    }
+
+    pub fn get_max_frontier_size(&self) -> usize {
+        self.max_frontier_size
+    }
+
+    pub fn get_pruned(&self) -> u64 {
+        self.pruned
+    }
+
+    pub fn get_iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// The branching factor b* that a uniform tree of the given depth would need to contain
+    /// every generated node, solving N + 1 = 1 + b* + b*^2 + ... + b*^depth by bisection
+    pub fn effective_branching_factor(&self, depth: u32) -> f64 {
+        if depth == 0 || self.nodes_generated == 0 {
+            return 0.0;
+        }
+
+        let target = self.nodes_generated as f64 + 1.0;
+        let tree_size = |b: f64| (0..=depth).map(|d| b.powi(d as i32)).sum::<f64>();
+
+        let (mut low, mut high) = (0.0, f64::max(1.0, self.nodes_generated as f64));
+        for _ in 0..100 {
+            let mid = (low + high) / 2.0;
+            if tree_size(mid) < target {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        (low + high) / 2.0
+    }
+
+}
+
+impl fmt::Display for SearchStatistics {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "generated {}, expanded {}, pruned {}, max frontier {}, iterations {}",
+            self.nodes_generated, self.nodes_expanded, self.pruned, self.max_frontier_size, self.iterations)
+    }
+
+}
@@ -0,0 +1,438 @@
+// Author: Harper Davis
+
+use std::{collections::{BTreeSet, BinaryHeap, HashMap, HashSet}, fmt, marker::PhantomData, ops::Bound, sync::Arc};
+
+use crate::{state::State, action::Action, frontier::{PriorityQueueFrontier, Prioritized}, node::Node, statistics::SearchStatistics, limits::{SearchLimits, Limit}, search::{Search, SearchOutcome, Solution, AStarGraphSearch}, heuristic::{Heuristic, StateHeuristic, Weighted}};
+
+/// A* with the heuristic scaled up by a weight, so f = g + w * h. Solutions cost at most w times the cheapest one
+pub type WeightedAStarSearch<S, A, H = StateHeuristic> = AStarGraphSearch<S, A, Weighted<H>>;
+
+///////////////////////////////////////////////////////
+
+/// Called with every better solution Anytime Repairing A* finds, and how many times the cheapest one it could cost at most
+pub type SolutionCallback<S, A> = Box<dyn FnMut(&Solution<S, A>, f64)>;
+
+/// Likhachev, Gordon and Thrun's Anytime Repairing A*. It starts out as weighted A* with a big weight to find some
+/// solution quickly, then keeps lowering the weight and searching again for better ones until the weight gets down to 1
+/// and the solution is the cheapest. Every search after the first reuses the nodes of the ones before, only going back
+/// over states that a cheaper path to was found for. If a limit stops it partway, the best solution so far is returned
+pub struct AnytimeRepairingAStarSearch<S: State<Action = A>, A: Action, H: Heuristic<S> = StateHeuristic> {
+    initial_weight: f64,
+    weight_step: f64,
+    heuristic: H,
+    callback: Option<SolutionCallback<S, A>>,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> AnytimeRepairingAStarSearch<S, A, H> {
+
+    /// Starts at `initial_weight` and lowers it by `weight_step` after every solution, a step of 0 goes straight to 1
+    pub fn with_weights(initial_weight: f64, weight_step: f64, heuristic: H) -> AnytimeRepairingAStarSearch<S, A, H> {
+        AnytimeRepairingAStarSearch {
+            initial_weight: f64::max(initial_weight, 1.0),
+            weight_step: if weight_step > 0.0 { weight_step } else { f64::INFINITY },
+            heuristic,
+            callback: None,
+            statistics: SearchStatistics::new(),
+            phantom_s: PhantomData,
+            phantom_a: PhantomData,
+        }
+    }
+
+    pub fn with_callback(mut self, callback: impl FnMut(&Solution<S, A>, f64) + 'static) -> AnytimeRepairingAStarSearch<S, A, H> {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>> fmt::Display for AnytimeRepairingAStarSearch<S, A, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AnytimeRepairingAStarSearch")
+    }
+}
+
+/// Everything Anytime Repairing A* keeps from one weight to the next. `inconsistent` has the nodes that got
+/// cheaper after their state was already expanded under the current weight, they wait for the next one
+struct Repair<S: State<Action = A>, A: Action> {
+    open: BinaryHeap<Prioritized<S, A>>,
+    best: HashMap<S, Node<S, A>>,
+    closed: HashSet<S>,
+    inconsistent: Vec<Node<S, A>>,
+    incumbent: Option<Node<S, A>>,
+}
+
+impl<S: State<Action = A>, A: Action> Repair<S, A> {
+
+    fn incumbent_cost(&self) -> f64 {
+        self.incumbent.as_ref().map_or(f64::INFINITY, |incumbent| incumbent.get_path_cost())
+    }
+
+    fn is_cheapest(&self, node: &Node<S, A>) -> bool {
+        self.best.get(node.get_state()).is_some_and(|best| best.get_path_cost() >= node.get_path_cost())
+    }
+
+    /// Whether this is still the cheapest node for its state and hasn't been expanded yet
+    fn is_current(&self, node: &Node<S, A>) -> bool {
+        !self.closed.contains(node.get_state()) && self.is_cheapest(node)
+    }
+
+    /// Weighted A* until nothing left could beat the incumbent under this weight
+    fn improve<H: Heuristic<S>>(&mut self, weight: f64, heuristic: &H, limits: &SearchLimits, statistics: &mut SearchStatistics) -> Option<Limit> {
+        while let Some(top) = self.open.peek() {
+            if self.incumbent_cost() <= top.get_priority() {
+                break;
+            }
+            if let Some(limit) = limits.check(statistics, self.open.len()) {
+                return Some(limit);
+            }
+
+            let node = self.open.pop().unwrap().into_node();
+            if !self.is_current(&node) {
+                continue;
+            }
+            self.closed.insert(node.get_state().clone());
+
+            statistics.expanded();
+            let shared_node = Arc::new(node);
+            for action in shared_node.get_possible_actions() {
+                let next_node = Node::<S, A>::next_node(shared_node.clone(), &action, heuristic);
+                statistics.generated();
+
+                if self.best.get(next_node.get_state()).is_some_and(|best| best.get_path_cost() <= next_node.get_path_cost()) {
+                    statistics.pruned();
+                    continue;
+                }
+
+                self.best.insert(next_node.get_state().clone(), next_node.clone());
+                if next_node.get_state().is_goal_state() && next_node.get_path_cost() < self.incumbent_cost() {
+                    self.incumbent = Some(next_node.clone());
+                }
+                if self.closed.contains(next_node.get_state()) {
+                    self.inconsistent.push(next_node);
+                } else {
+                    self.open.push(Prioritized::new(next_node.get_path_cost() + weight * next_node.get_heuristic(), next_node));
+                }
+            }
+            statistics.frontier_size(self.open.len() + self.inconsistent.len());
+        }
+        None
+    }
+
+    /// Puts the inconsistent nodes back with the open ones, and requeues them all under the new weight
+    fn reweigh(&mut self, weight: f64) {
+        self.closed.clear();
+        let nodes = self.open.drain().map(Prioritized::into_node).chain(self.inconsistent.drain(..)).collect::<Vec<Node<S, A>>>();
+        for node in nodes {
+            if self.is_current(&node) {
+                self.open.push(Prioritized::new(node.get_path_cost() + weight * node.get_heuristic(), node));
+            }
+        }
+    }
+
+    /// How many times the cheapest solution the incumbent could cost at most. Nothing still waiting to be
+    /// expanded can lead to anything cheaper than its g + h, and the weight is always a bound as well
+    fn suboptimality(&self, weight: f64) -> f64 {
+        let lowest = self.open.iter().map(Prioritized::get_node).filter(|node| self.is_current(node))
+            .chain(self.inconsistent.iter().filter(|node| self.is_cheapest(node)))
+            .map(|node| node.eval())
+            .fold(f64::INFINITY, f64::min);
+        f64::max(1.0, f64::min(weight, self.incumbent_cost() / lowest))
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S> + Default> Search<S, A, PriorityQueueFrontier<S, A>> for AnytimeRepairingAStarSearch<S, A, H> {
+
+    fn new(_limit: f64) -> AnytimeRepairingAStarSearch<S, A, H> {
+        AnytimeRepairingAStarSearch::with_weights(3.0, 0.5, H::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+
+        let start_node = Node::new(&start_state, 0.0, 0, &self.heuristic);
+        let mut repair = Repair { open: BinaryHeap::new(), best: HashMap::new(), closed: HashSet::new(), inconsistent: Vec::new(), incumbent: None };
+        repair.best.insert(start_state.clone(), start_node.clone());
+        if start_state.is_goal_state() {
+            repair.incumbent = Some(start_node.clone());
+        }
+        let mut weight = self.initial_weight;
+        repair.open.push(Prioritized::new(weight * start_node.get_heuristic(), start_node));
+
+        loop {
+            self.statistics.iteration();
+            let previous_cost = repair.incumbent_cost();
+            let limit = repair.improve(weight, &self.heuristic, limits, &mut self.statistics);
+
+            if repair.incumbent_cost() < previous_cost {
+                if let (Some(callback), Some(incumbent)) = (self.callback.as_mut(), repair.incumbent.as_ref()) {
+                    callback(&Solution::from_node(start_state.clone(), incumbent, self.statistics.clone()), repair.suboptimality(weight));
+                }
+            }
+
+            if let Some(limit) = limit {
+                return match repair.incumbent {
+                    Some(incumbent) => SearchOutcome::Solved(Solution::from_node(start_state, &incumbent, self.statistics.clone())),
+                    None => SearchOutcome::LimitReached(limit, self.statistics.clone()),
+                };
+            }
+            // either there's no solution at all, or the one there is can't be improved on
+            if repair.incumbent.is_none() || repair.suboptimality(weight) <= 1.0 {
+                break;
+            }
+
+            weight = f64::max(1.0, weight - self.weight_step);
+            repair.reweigh(weight);
+        }
+
+        match repair.incumbent {
+            Some(incumbent) => SearchOutcome::Solved(Solution::from_node(start_state, &incumbent, self.statistics.clone())),
+            None => SearchOutcome::Exhausted(self.statistics.clone()),
+        }
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        unreachable!()
+    }
+
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &self.heuristic
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+///////////////////////////////////////////////////////
+
+/// Pearl and Kim's A*ε. The focal list holds every open node whose evaluation is within `weight` times the lowest
+/// one, and out of those the node picked is the one `focal_heuristic` thinks is closest to the goal, rather than
+/// the one with the lowest evaluation. That's still enough for a solution to cost at most `weight` times the cheapest.
+/// By default both heuristics are the puzzle's own, so it goes for whatever looks closest while staying inside the bound
+pub struct FocalSearch<S: State<Action = A>, A: Action, H: Heuristic<S> = StateHeuristic, D: Heuristic<S> = StateHeuristic> {
+    weight: f64,
+    heuristic: H,
+    focal_heuristic: D,
+    statistics: SearchStatistics,
+    phantom_s: PhantomData<S>,
+    phantom_a: PhantomData<A>,
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>, D: Heuristic<S>> FocalSearch<S, A, H, D> {
+
+    pub fn with_heuristics(weight: f64, heuristic: H, focal_heuristic: D) -> FocalSearch<S, A, H, D> {
+        FocalSearch { weight: f64::max(weight, 1.0), heuristic, focal_heuristic, statistics: SearchStatistics::new(), phantom_s: PhantomData, phantom_a: PhantomData }
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S>, D: Heuristic<S>> fmt::Display for FocalSearch<S, A, H, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FocalSearch")
+    }
+}
+
+/// The open and focal lists of a FocalSearch. Open is ordered by evaluation and focal by the focal heuristic, both
+/// as bits, which sort the same way as the numbers as long as they aren't negative. Every state has at most one node
+/// queued. `focus` is the bound focal was last brought up to date with, after the lowest evaluation changes the
+/// nodes that crossed the bound get moved in or out of focal all at once
+struct FocalLists<S: State<Action = A>, A: Action> {
+    weight: f64,
+    focus: f64,
+    nodes: Vec<Option<(Node<S, A>, f64)>>,
+    queued: HashMap<S, usize>,
+    open: BTreeSet<(u64, usize)>,
+    focal: BTreeSet<(u64, u64, usize)>,
+}
+
+impl<S: State<Action = A>, A: Action> FocalLists<S, A> {
+
+    fn bound(&self) -> f64 {
+        self.open.first().map_or(f64::INFINITY, |&(evaluation, _)| self.weight * f64::from_bits(evaluation))
+    }
+
+    fn focal_key(&self, index: usize) -> (u64, u64, usize) {
+        let (node, focal_estimate) = self.nodes[index].as_ref().unwrap();
+        (focal_estimate.to_bits(), node.eval().to_bits(), index)
+    }
+
+    fn insert(&mut self, node: Node<S, A>, focal_estimate: f64) {
+        if let Some(index) = self.queued.get(node.get_state()).copied() {
+            self.remove(index);
+        }
+        let index = self.nodes.len();
+        let evaluation = node.eval();
+        self.queued.insert(node.get_state().clone(), index);
+        self.nodes.push(Some((node, focal_estimate)));
+        if evaluation <= self.focus {
+            self.focal.insert(self.focal_key(index));
+        }
+        self.open.insert((evaluation.to_bits(), index));
+    }
+
+    fn remove(&mut self, index: usize) -> Node<S, A> {
+        self.focal.remove(&self.focal_key(index));
+        let (node, _) = self.nodes[index].take().unwrap();
+        self.open.remove(&(node.eval().to_bits(), index));
+        self.queued.remove(node.get_state());
+        node
+    }
+
+    /// Moves the nodes between the old and the new bound in or out of focal
+    fn refocus(&mut self) {
+        let (old_bound, new_bound) = (self.focus, self.bound());
+        self.focus = new_bound;
+        let range = (Bound::Excluded((f64::min(old_bound, new_bound).to_bits(), usize::MAX)), Bound::Included((f64::max(old_bound, new_bound).to_bits(), usize::MAX)));
+        let crossing = self.open.range(range).map(|&(_, index)| index).collect::<Vec<usize>>();
+        for index in crossing {
+            let key = self.focal_key(index);
+            if new_bound > old_bound {
+                self.focal.insert(key);
+            } else {
+                self.focal.remove(&key);
+            }
+        }
+    }
+
+}
+
+impl<S: State<Action = A>, A: Action, H: Heuristic<S> + Default, D: Heuristic<S> + Default> Search<S, A, PriorityQueueFrontier<S, A>> for FocalSearch<S, A, H, D> {
+
+    fn new(_limit: f64) -> FocalSearch<S, A, H, D> {
+        FocalSearch::with_heuristics(1.5, H::default(), D::default())
+    }
+
+    fn search_with_limits(&mut self, start_state: S, limits: &SearchLimits) -> SearchOutcome<S, A> {
+        self.statistics = SearchStatistics::new();
+        if !start_state.is_solvable() {
+            return SearchOutcome::Exhausted(self.statistics.clone());
+        }
+        self.statistics.iteration();
+
+        let mut lists = FocalLists { weight: self.weight, focus: f64::INFINITY, nodes: Vec::new(), queued: HashMap::new(), open: BTreeSet::new(), focal: BTreeSet::new() };
+        let mut best_costs: HashMap<S, f64> = HashMap::new();
+        best_costs.insert(start_state.clone(), 0.0);
+        lists.insert(Node::new(&start_state, 0.0, 0, &self.heuristic), self.focal_heuristic.estimate(&start_state));
+        lists.refocus();
+        self.statistics.frontier_size(lists.open.len());
+
+        // the node with the lowest evaluation is always in focal, so it's only empty when open is too
+        while let Some(&(_, _, index)) = lists.focal.first() {
+            if let Some(limit) = limits.check(&self.statistics, lists.open.len()) {
+                return SearchOutcome::LimitReached(limit, self.statistics.clone());
+            }
+
+            let node = lists.remove(index);
+            if node.get_state().is_goal_state() {
+                return SearchOutcome::Solved(Solution::from_node(start_state, &node, self.statistics.clone()));
+            }
+
+            self.statistics.expanded();
+            let shared_node = Arc::new(node);
+            for action in shared_node.get_possible_actions() {
+                let next_node = Node::<S, A>::next_node(shared_node.clone(), &action, &self.heuristic);
+                self.statistics.generated();
+
+                if best_costs.get(next_node.get_state()).is_some_and(|&cost| cost <= next_node.get_path_cost()) {
+                    self.statistics.pruned();
+                    continue;
+                }
+
+                best_costs.insert(next_node.get_state().clone(), next_node.get_path_cost());
+                let focal_estimate = self.focal_heuristic.estimate(next_node.get_state());
+                lists.insert(next_node, focal_estimate);
+            }
+            lists.refocus();
+            self.statistics.frontier_size(lists.open.len());
+        }
+
+        SearchOutcome::Exhausted(self.statistics.clone())
+    }
+
+    fn prune(&mut self, _node: &Node<S, A>) -> bool {
+        unreachable!()
+    }
+
+    fn heuristic(&self) -> &dyn Heuristic<S> {
+        &self.heuristic
+    }
+
+    fn statistics(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use rand::rngs::StdRng;
+
+    use crate::{generator::Generator, puzzles::sliding_puzzle::{EightPuzzle, SlidingPuzzleAction}};
+
+    use super::*;
+
+    /// Seeded eight puzzles and what the cheapest solution for each costs
+    fn cases() -> Vec<(EightPuzzle, f64)> {
+        Generator::<StdRng>::new(13).sliding_puzzle_walks::<3, 3>(0..=26, 3).into_iter().map(|test_case| {
+            let start_state = test_case.get_start_state().clone();
+            match AStarGraphSearch::<EightPuzzle, SlidingPuzzleAction>::new(f64::INFINITY).search(start_state.clone()) {
+                SearchOutcome::Solved(solution) => (start_state, solution.path_cost()),
+                _ => panic!("A* didn't solve {}", start_state),
+            }
+        }).collect()
+    }
+
+    /// The cost of a real path found by the search
+    fn solution_cost(outcome: SearchOutcome<EightPuzzle, SlidingPuzzleAction>, start_state: &EightPuzzle) -> f64 {
+        match outcome {
+            SearchOutcome::Solved(solution) => solution.verify().unwrap_or_else(|error| panic!("from {}: {}", start_state, error)),
+            _ => panic!("no solution from {}", start_state),
+        }
+    }
+
+    #[test]
+    fn anytime_solutions_only_get_cheaper() {
+        for (start_state, optimal) in cases() {
+            let found = Rc::new(RefCell::new(Vec::new()));
+            let callback_found = found.clone();
+            let mut search = AnytimeRepairingAStarSearch::<EightPuzzle, SlidingPuzzleAction>::with_weights(5.0, 1.0, StateHeuristic)
+                .with_callback(move |solution, suboptimality| callback_found.borrow_mut().push((solution.path_cost(), suboptimality)));
+            let cost = solution_cost(search.search(start_state.clone()), &start_state);
+            assert_eq!(cost, optimal, "from {}", start_state);
+
+            let found = found.borrow();
+            // a start that's already solved has its solution before any search, so there's nothing to call back with
+            if optimal == 0.0 {
+                assert!(found.is_empty());
+                continue;
+            }
+            assert!(found.windows(2).all(|pair| pair[1].0 < pair[0].0), "{:?} from {}", found, start_state);
+            for &(cost, suboptimality) in found.iter() {
+                assert!(cost <= suboptimality * optimal + 1e-9, "{} isn't within {} of {} from {}", cost, suboptimality, optimal, start_state);
+            }
+            assert_eq!(found.last().unwrap().0, optimal);
+        }
+    }
+
+    #[test]
+    fn within_the_bound_of_the_cheapest() {
+        for (start_state, optimal) in cases() {
+            for weight in [1.0, 1.5, 2.0, 3.0] {
+                let weighted = AStarGraphSearch::with_heuristic(Weighted::new(weight, StateHeuristic)).search(start_state.clone());
+                assert!(solution_cost(weighted, &start_state) <= weight * optimal, "weighted A* with {} from {}", weight, start_state);
+                let focal = FocalSearch::<EightPuzzle, SlidingPuzzleAction>::with_heuristics(weight, StateHeuristic, StateHeuristic).search(start_state.clone());
+                assert!(solution_cost(focal, &start_state) <= weight * optimal, "focal search with {} from {}", weight, start_state);
+            }
+        }
+    }
+}
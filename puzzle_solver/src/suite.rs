@@ -0,0 +1,207 @@
+// Author: Harper Davis
+use std::{fmt, fs};
+
+use crate::{state::State, action::Action, test::TestCase, puzzles::fifteen_puzzle::{FifteenPuzzle, FifteenPuzzleAction}};
+
+/// What was wrong with a test suite file, and on which line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuiteError {
+    /// Counting from 1, or 0 when the file couldn't be read at all
+    line: usize,
+    message: String,
+}
+
+impl SuiteError {
+
+    pub fn new(line: usize, message: String) -> SuiteError {
+        SuiteError { line, message }
+    }
+
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+}
+
+impl fmt::Display for SuiteError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+
+}
+
+///////////////////////////////////////////////////////
+
+/// A suite has one test case per line, with the fields separated by spaces:
+///
+///     <limit> <start state> <action> <action> ...
+///
+/// The limit is what the search gets to start with (the depth of the correct path for most of them), the start state is
+/// written the way the puzzle's Display writes it, and the actions are the correct path from there to a goal (none for a
+/// start that is already solved). Blank lines and lines starting with # are skipped
+pub fn read_test_cases<S: State<Action = A>, A: Action>(path: &str) -> Result<Vec<TestCase<S, A>>, SuiteError> {
+    let contents = fs::read_to_string(path).map_err(|error| SuiteError::new(0, format!("couldn't read {}: {}", path, error)))?;
+    parse_test_cases(&contents)
+}
+
+pub fn parse_test_cases<S: State<Action = A>, A: Action>(contents: &str) -> Result<Vec<TestCase<S, A>>, SuiteError> {
+    let mut test_cases = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        test_cases.push(parse_test_case(line).map_err(|message| SuiteError::new(number + 1, message))?);
+    }
+    Ok(test_cases)
+}
+
+/// One line of a suite, checking that the path really does end at a goal
+pub fn parse_test_case<S: State<Action = A>, A: Action>(line: &str) -> Result<TestCase<S, A>, String> {
+    let mut fields = line.split_whitespace();
+    let limit = fields.next().ok_or("the line is empty")?;
+    let limit = limit.parse::<f64>().map_err(|_| format!("the limit {} isn't a number", limit))?;
+    let start_state = fields.next().ok_or("the start state is missing")?;
+    let start_state = start_state.parse::<S>().map_err(|_| format!("{} isn't a start state", start_state))?;
+
+    let mut correct_path = Vec::new();
+    for (i, action) in fields.enumerate() {
+        correct_path.push(action.parse::<A>().map_err(|_| format!("move {} of the path, {}, isn't an action", i + 1, action))?);
+    }
+
+    let final_state = correct_path.iter().fold(start_state.clone(), |state, action| state.perform_action(action));
+    if !final_state.is_goal_state() {
+        return Err(format!("the path ends at {} rather than a goal", final_state.to_string()));
+    }
+    Ok(TestCase::new(start_state, correct_path, limit))
+}
+
+/// Writes a suite that read_test_cases reads back the same, with each line of the header as a comment at the top
+pub fn write_test_cases<S: State<Action = A>, A: Action>(path: &str, header: &str, test_cases: &[TestCase<S, A>]) -> Result<(), SuiteError> {
+    fs::write(path, format_test_cases(header, test_cases)).map_err(|error| SuiteError::new(0, format!("couldn't write {}: {}", path, error)))
+}
+
+pub fn format_test_cases<S: State<Action = A>, A: Action>(header: &str, test_cases: &[TestCase<S, A>]) -> String {
+    let mut contents = String::new();
+    for line in header.lines() {
+        contents.push_str(&format!("# {}\n", line));
+    }
+    for test_case in test_cases {
+        contents.push_str(&format_test_case(test_case));
+        contents.push('\n');
+    }
+    contents
+}
+
+pub fn format_test_case<S: State<Action = A>, A: Action>(test_case: &TestCase<S, A>) -> String {
+    let mut fields = vec![test_case.get_limit().to_string(), test_case.get_start_state().to_string()];
+    fields.extend(test_case.get_correct_path().iter().map(|action| action.to_string()));
+    fields.join(" ")
+}
+
+///////////////////////////////////////////////////////
+
+/// The layout of tests/15_puzzle.txt, which came from the class spreadsheet:
+///
+///     <depth> UL <pieces> <hex board> <manhattan distance> <path>
+///
+/// where the path is the pieces to move in decimal, separated by dashes
+pub fn read_fifteen_puzzle_test_cases(path: &str) -> Result<Vec<TestCase<FifteenPuzzle, FifteenPuzzleAction>>, SuiteError> {
+    let contents = fs::read_to_string(path).map_err(|error| SuiteError::new(0, format!("couldn't read {}: {}", path, error)))?;
+    let mut test_cases = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        test_cases.push(parse_fifteen_puzzle_test_case(line).map_err(|message| SuiteError::new(number + 1, message))?);
+    }
+    Ok(test_cases)
+}
+
+fn parse_fifteen_puzzle_test_case(line: &str) -> Result<TestCase<FifteenPuzzle, FifteenPuzzleAction>, String> {
+    let split = line.split_whitespace().collect::<Vec<&str>>();
+    if split.len() != 6 {
+        return Err(format!("expected 6 fields but there are {}", split.len()));
+    }
+    let depth_limit = split[0].parse::<u32>().map_err(|_| format!("the depth {} isn't a number", split[0]))?;
+    let start_state = split[3].parse::<FifteenPuzzle>().map_err(|_| format!("{} isn't a fifteen puzzle board", split[3]))?;
+    let mut correct_path = Vec::new();
+    for piece in split[5].split('-') {
+        let piece = piece.parse::<u8>().ok().filter(|&piece| piece < 16).ok_or_else(|| format!("{} in the path isn't a piece", piece))?;
+        correct_path.push(FifteenPuzzleAction::new(piece));
+    }
+    Ok(TestCase::new(start_state, correct_path, depth_limit as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type FifteenPuzzleCase = TestCase<FifteenPuzzle, FifteenPuzzleAction>;
+
+    fn parse(contents: &str) -> Result<Vec<FifteenPuzzleCase>, SuiteError> {
+        parse_test_cases::<FifteenPuzzle, FifteenPuzzleAction>(contents)
+    }
+
+    /// The line an error was on, and whether its message mentions the given text
+    fn error_at(contents: &str, line: usize, text: &str) {
+        let error = parse(contents).err().unwrap_or_else(|| panic!("{:?} should be rejected", contents));
+        assert_eq!(error.get_line(), line, "{}", error);
+        assert!(error.get_message().contains(text), "{}", error);
+        assert!(error.to_string().starts_with(&format!("line {}: ", line)));
+    }
+
+    #[test]
+    fn test_cases_read_back_the_same() {
+        let line = "2 0xfedcba9876543021 2 1";
+        let test_case: FifteenPuzzleCase = parse_test_case(line).unwrap();
+        assert_eq!(test_case.get_limit(), 2.0);
+        assert!(*test_case.get_start_state() == "0xfedcba9876543021".parse::<FifteenPuzzle>().unwrap());
+        assert!(*test_case.get_correct_path() == vec![FifteenPuzzleAction::new(2), FifteenPuzzleAction::new(1)]);
+        assert_eq!(format_test_case(&test_case), line);
+
+        let contents = format_test_cases("made by hand\nfor the tests", &[test_case, TestCase::new(FifteenPuzzle::default(), Vec::new(), 0.0)]);
+        assert!(contents.starts_with("# made by hand\n# for the tests\n"));
+        let test_cases = parse(&contents).unwrap();
+        assert_eq!(test_cases.len(), 2);
+        assert_eq!(format_test_case(&test_cases[0]), line);
+        assert!(test_cases[1].get_start_state().is_goal_state());
+        assert!(test_cases[1].get_correct_path().is_empty());
+    }
+
+    #[test]
+    fn errors_say_which_line() {
+        error_at("1 0xfedcba9876543201 1\n\ndeep 0xfedcba9876543201 1", 3, "limit");
+        error_at("# a comment\n1 0xfedcba98765432zz 1", 2, "start state");
+        error_at("1 0xfedcba9876543201 1\n1 0xfedcba9876543201 up", 2, "move 1");
+        error_at("1 0xfedcba9876543201 2", 1, "rather than a goal");
+        assert_eq!(parse("\n# nothing but comments\n").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn reads_the_fifteen_puzzle_file() {
+        let test_cases = read_fifteen_puzzle_test_cases("./tests/15_puzzle.txt").unwrap();
+        assert_eq!(test_cases.len(), 800);
+        assert_eq!(test_cases[0].get_depth_limit(), 1);
+        assert_eq!(test_cases[0].get_start_state().to_string(), "0xfedcba9876543201");
+        assert!(*test_cases[0].get_correct_path() == vec![FifteenPuzzleAction::new(1)]);
+        for test_case in &test_cases {
+            assert_eq!(test_case.get_correct_path().len() as u32, test_case.get_depth_limit());
+            let final_state = test_case.get_correct_path().iter().fold(test_case.get_start_state().clone(), |state, action| state.perform_action(action));
+            assert!(final_state.is_goal_state());
+        }
+
+        assert!(read_fifteen_puzzle_test_cases("./tests/no_such_file.txt").is_err_and(|error| error.get_line() == 0));
+        assert_eq!(parse_fifteen_puzzle_test_case("1 UL 0xfedcba9876543201 1 1").err().unwrap(), "expected 6 fields but there are 5");
+        assert!(parse_fifteen_puzzle_test_case("1 UL 1,0 0xfedcba9876543201 1 16").is_err());
+    }
+}
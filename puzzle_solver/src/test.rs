@@ -1,9 +1,9 @@
 // Author: Harper Davis
-use std::{time::Instant, fs, collections::HashMap};
+use std::{time::Instant, collections::HashMap};
 
 use colored::Colorize;
 
-use crate::{search::{Search, Solution}, state::State, action::Action, puzzles::{fifteen_puzzle::{FifteenPuzzle, FifteenPuzzleAction}}, frontier::Frontier};
+use crate::{search::{Search, SearchOutcome}, statistics::SearchStatistics, state::State, action::Action, puzzles::fifteen_puzzle::{FifteenPuzzle, FifteenPuzzleAction}, frontier::Frontier, suite::{read_test_cases, read_fifteen_puzzle_test_cases}};
 
 pub struct TestCase<S: State<Action = A>, A: Action> {
     start_state: S,
@@ -17,39 +17,38 @@ impl<S: State<Action = A>, A: Action> TestCase<S, A> {
         TestCase { start_state, correct_path, limit, depth_limit: limit as u32 }
     }
 
-    fn get_limit(&self) -> f64 {
+    pub fn get_limit(&self) -> f64 {
         self.limit
     }
 
-    fn get_depth_limit(&self) -> u32 {
+    pub fn get_depth_limit(&self) -> u32 {
         self.depth_limit
     }
 
-    fn get_correct_path(&self) -> &Vec<A> {
+    pub fn get_correct_path(&self) -> &Vec<A> {
         &self.correct_path
     }
 
-    fn get_start_state(&self) -> &S {
+    pub fn get_start_state(&self) -> &S {
         &self.start_state
     }
 
-    fn check_solution(&self, solution: &Solution<S, A>) -> bool {
-        let path = solution.get_path();
-        if path.len() > self.correct_path.len() {
-            println!("Failed because path lengths is greater: {} > {}", path.len(), self.correct_path.len());
-            return false;
+    /// What the correct path costs, added up the same way as Solution::path_cost
+    pub fn get_correct_cost(&self) -> f64 {
+        self.correct_path.iter().fold(0.0, |cost, action| cost + action.get_cost())
+    }
+
+    /// How the solution's cost compares to the correct path's, 1 means just as cheap and 1.5 half again as expensive
+    fn check_solution(&self, solution_cost: f64) -> f64 {
+        let correct_cost = self.get_correct_cost();
+        if correct_cost == 0.0 {
+            return if solution_cost == 0.0 { 1.0 } else { f64::INFINITY };
         }
-        // for i in 0..path.len() {
-        //     if path[i] != self.correct_path[i] {
-        //         println!("Failed because path is different at index {}: {} vs {}", i, path[i].to_string(), self.correct_path[i].to_string());
-        //         return false;
-        //     }
-        // }
-        true
+        solution_cost / correct_cost
     }
 }
 
-pub fn timed_test<S: State<Action = A>, A: Action, F: Frontier<S, A>, E: Search<S, A, F>>(start_state: S, limit: f64) -> (Option<Solution<S, A>>, f64) {
+pub fn timed_test<S: State<Action = A>, A: Action, F: Frontier<S, A>, E: Search<S, A, F>>(start_state: S, limit: f64) -> (SearchOutcome<S, A>, f64) {
     let mut searcher = E::new(limit);
     let start = Instant::now();
     let solution = searcher.search(start_state);
@@ -57,55 +56,95 @@ pub fn timed_test<S: State<Action = A>, A: Action, F: Frontier<S, A>, E: Search<
     (solution, elapsed)
 }
 
-pub fn test_one<S: State<Action = A>, A: Action, F: Frontier<S, A>, E: Search<S, A, F>>(test_case: &TestCase<S, A>) -> (bool, f64) {
+pub fn test_one<S: State<Action = A>, A: Action, F: Frontier<S, A>, E: Search<S, A, F>>(test_case: &TestCase<S, A>) -> (Option<f64>, f64, Option<(u32, SearchStatistics, f64)>) {
     let (solution, time) = timed_test::<S, A, F, E>(test_case.get_start_state().clone(), test_case.get_limit());
-    if solution.is_none() {
-        println!("No solution found!");
-        (false, time)
-    } else {
-        (test_case.check_solution(&solution.unwrap()), time)
+    match solution {
+        SearchOutcome::Solved(solution) => match solution.verify() {
+            Ok(cost) => (Some(test_case.check_solution(cost)), time, Some((solution.path_length(), solution.get_statistics().clone(), solution.effective_branching_factor()))),
+            Err(error) => {
+                println!("Invalid solution, {}!", error);
+                (None, time, None)
+            }
+        },
+        SearchOutcome::Exhausted(_) => {
+            println!("No solution found!");
+            (None, time, None)
+        }
+        SearchOutcome::LimitReached(limit, _) => {
+            println!("Stopped by the {}!", limit);
+            (None, time, None)
+        }
     }
 }
 
 pub fn test<S: State<Action = A>, A: Action, F: Frontier<S, A>, E: Search<S, A, F>>(test_cases: Vec<TestCase<S, A>>) {
-    let mut averages_for_depth: HashMap<u32, (f64, f64)> = HashMap::new();
+    // tests, time, nodes expanded, solution quality and how many were solved
+    let mut averages_for_depth: HashMap<u32, (f64, f64, f64, f64, f64)> = HashMap::new();
     
-    let mut prev_depth = if let Some(test_case) = test_cases.first() { test_case.depth_limit } else { 0 };
+    let mut prev_depth = if let Some(test_case) = test_cases.first() { test_case.get_depth_limit() } else { 0 };
     for test_case in &test_cases {
 
-        if test_case.depth_limit != prev_depth {
-            let (num_tests, total_time) = averages_for_depth.get(&prev_depth).unwrap();
-            println!("{} avg for depth {} {} ms", "[AVERAGE]".blue(), prev_depth, format!("{: >10.3}", (total_time / num_tests)).yellow());
+        if test_case.get_depth_limit() != prev_depth {
+            print_averages(prev_depth, averages_for_depth[&prev_depth]);
         }
-        prev_depth = test_case.depth_limit;
+        prev_depth = test_case.get_depth_limit();
 
-        let (result, time) = test_one::<S, A, F, E>(test_case);
+        let (quality, time, statistics) = test_one::<S, A, F, E>(test_case);
         
         print!("\r");
-        println!("{} with depth {}, took {: >10.3} ms", if result { "[PASS]".green() } else { "[FAIL]".red() }, test_case.depth_limit, time);
-
-        averages_for_depth.entry(test_case.depth_limit).and_modify(|x| {
+        // the length is what to compare between runs, parallel searches can find a different path of the same length each time
+        let length = statistics.as_ref().map_or("-".to_string(), |(length, _, _)| length.to_string());
+        let result = match quality {
+            // a little leeway for adding up costs that aren't whole numbers
+            Some(quality) if quality <= 1.0 + 1e-9 => "[PASS]".green(),
+            Some(_) => "[WORSE]".yellow(),
+            None => "[FAIL]".red(),
+        };
+        let times_correct = quality.map_or("-".to_string(), |quality| format!("{:.3}", quality));
+        println!("{} with depth {}, length {} ({} times the correct cost), took {: >10.3} ms", result, test_case.get_depth_limit(), length, times_correct, time);
+
+        let expanded = if let Some((_, statistics, branching_factor)) = statistics {
+            println!("       {}, b* {:.3}", statistics.to_string().dimmed(), branching_factor);
+            statistics.get_nodes_expanded() as f64
+        } else { 0.0 };
+
+        let (quality, solved) = quality.map_or((0.0, 0.0), |quality| (quality, 1.0));
+        averages_for_depth.entry(test_case.get_depth_limit()).and_modify(|x| {
             x.0 += 1.0;
             x.1 += time;
-        }).or_insert((1.0, time));
+            x.2 += expanded;
+            x.3 += quality;
+            x.4 += solved;
+        }).or_insert((1.0, time, expanded, quality, solved));
 
         
     }
 
+    // the last depth never changes to another one
+    if let Some(&averages) = averages_for_depth.get(&prev_depth) {
+        print_averages(prev_depth, averages);
+    }
     println!("{}", "[FINISHED]".yellow());
 }
 
-pub fn test_fifteen_puzzle_from_file<F: Frontier<FifteenPuzzle, FifteenPuzzleAction>, E: Search<FifteenPuzzle, FifteenPuzzleAction, F>>(test_cases_path: &'static str) {
-    let mut test_cases = Vec::new();
-    for line in fs::read_to_string(test_cases_path).unwrap().lines() {
-        let split = line.split(" ").collect::<Vec<&str>>();
-        let depth_limit = split[0].parse::<u32>().unwrap();
-        let starting_state = u64::from_str_radix(split[3].trim_start_matches("0x"), 16).unwrap();
-        let correct_path = split[5].split("-").map(|x| FifteenPuzzleAction::new(x.parse::<u8>().unwrap())).collect::<Vec<FifteenPuzzleAction>>();
-        let start_state = FifteenPuzzle::new(starting_state);
-        let test_case = TestCase::new(start_state, correct_path, depth_limit as f64);
-        test_cases.push(test_case);
+/// Tests, time, nodes expanded, solution quality and how many were solved, all added up over the cases of one depth
+fn print_averages(depth: u32, (num_tests, total_time, total_expanded, total_quality, num_solved): (f64, f64, f64, f64, f64)) {
+    // only the solved ones have a cost to compare
+    let times_correct = if num_solved == 0.0 { "n/a".to_string() } else { format!("{:.3}", total_quality / num_solved) };
+    println!("{} avg for depth {} {} ms, {} nodes expanded, {} times the correct cost", "[AVERAGE]".blue(), depth, format!("{: >10.3}", (total_time / num_tests)).yellow(), format!("{:.0}", total_expanded / num_tests).yellow(), times_correct.yellow());
+}
+
+/// Runs every case in a suite file, see suite::read_test_cases for the format
+pub fn test_from_file<S: State<Action = A>, A: Action, F: Frontier<S, A>, E: Search<S, A, F>>(test_cases_path: &str) {
+    match read_test_cases(test_cases_path) {
+        Ok(test_cases) => test::<S, A, F, E>(test_cases),
+        Err(error) => println!("{} {}", "[ERROR]".red(), error),
     }
+}
 
-    test::<FifteenPuzzle, FifteenPuzzleAction, F, E>(test_cases);
-}
\ No newline at end of file
+pub fn test_fifteen_puzzle_from_file<F: Frontier<FifteenPuzzle, FifteenPuzzleAction>, E: Search<FifteenPuzzle, FifteenPuzzleAction, F>>(test_cases_path: &str) {
+    match read_fifteen_puzzle_test_cases(test_cases_path) {
+        Ok(test_cases) => test::<FifteenPuzzle, FifteenPuzzleAction, F, E>(test_cases),
+        Err(error) => println!("{} {}", "[ERROR]".red(), error),
+    }
+}